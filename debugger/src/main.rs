@@ -5,6 +5,7 @@ use std::rc::Rc;
 use std::time::{Duration, Instant};
 use std::{env, thread, time};
 
+use emulator::gameboy::instruction::Symbols;
 use emulator::*;
 use glium::backend::Facade;
 use glium::glutin::event::{Event, KeyboardInput, StartCause, VirtualKeyCode, WindowEvent};
@@ -36,23 +37,27 @@ fn main() {
     env::set_var("RUST_BACKTRACE", "1");
     //
     // Emulator
-    // Read boot ROM file
-    let mut bootstrap_buffer: Vec<u8> = Vec::new();
     let mut rom_buffer: Vec<u8> = Vec::new();
 
-    let mut bootstrap_rom = std::fs::File::open("../roms/DMG_ROM.bin").expect("INVALID ROM");
-    let mut rom = std::fs::File::open("../roms/Pokemon Red.gb").expect("INVALID ROM");
+    let rom_path = "../roms/Pokemon Red.gb";
+    let mut rom = std::fs::File::open(rom_path).expect("INVALID ROM");
     //let mut rom = std::fs::File::open("../roms/individual/02-interrupts.gb").expect("INVALID ROM");
 
     //let mut rom = std::fs::File::open("../roms/Kirby.gb").expect("INVALID ROM");
     //let mut rom = std::fs::File::open("../roms/dmg-acid2.gb").expect("INVALID ROM");
-    bootstrap_rom.read_to_end(&mut bootstrap_buffer).unwrap();
     rom.read_to_end(&mut rom_buffer).unwrap();
 
     // Create emulator
-    let mut gameboy = GameBoy::new();
-    gameboy.read_rom(&rom_buffer);
-    //gameboy.read_boot_rom(&bootstrap_buffer);
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom_buffer).expect("UNSUPPORTED ROM");
+    let symbols = load_symbols(rom_path);
+    // With --boot, start from a boot ROM: the real one when supplied, the built-in sequence otherwise
+    if env::args().any(|arg| arg == "--boot") {
+        match std::fs::read("../roms/DMG_ROM.bin") {
+            Ok(boot_rom) => gameboy.read_boot_rom(&boot_rom),
+            Err(_) => gameboy.use_builtin_boot_rom(),
+        }
+    }
 
     // Common setup for creating a winit window and imgui context, not specifc
     // to this renderer at all except that glutin is used to create the window
@@ -70,9 +75,8 @@ fn main() {
     // Timer for FPS calculation
     let mut first_frame = Instant::now(); // Used to keep track of current fps
     let mut last_frame = std::time::Instant::now(); // Used by imgui for time/delta manipulation
-    let frame_duration = Duration::new(0, (1000.0 / 60.0) as u32);
-    let cycles_per_frame = (4194304 / 60) as f64; //4194304f64 / 70224f64;
-    let mut last_gb_frame = Instant::now(); // Used to track last gameboy frame
+    let frame_duration = Duration::from_secs_f64(1.0 / FRAME_RATE);
+    let mut next_gb_frame = Instant::now(); // Used to schedule the next gameboy frame
     let mut frame_count = 0;
 
     // Screen Renders
@@ -109,6 +113,14 @@ fn main() {
     let mut is_playing: bool = false;
     let mut tick_rate: i16 = 5;
 
+    //  > Watchpoints
+    let mut watchpoint_inputs = WatchpointInputs::new();
+
+    //  > Assembler
+    let mut assemble_address = String::with_capacity(8);
+    let mut assemble_source = String::new();
+    let mut assemble_status = String::new();
+
     // Input handling
     let input_mapper: HashMap<VirtualKeyCode, JoypadInputKey> = HashMap::from([
         (VirtualKeyCode::W, JoypadInputKey::Up),
@@ -132,12 +144,7 @@ fn main() {
             }) => {
                 // Tick emulator
                 //println!("resume time reached! {:?}\t{:?}", start, requested_resume);
-                perform_gameboy_frame(
-                    &mut is_playing,
-                    &mut gameboy,
-                    cycles_per_frame,
-                    &breakpoints,
-                );
+                perform_gameboy_frame(&mut is_playing, &mut gameboy, &breakpoints);
                 update_display(
                     &mut gameboy,
                     &display,
@@ -146,7 +153,12 @@ fn main() {
                     &mut gb_debugger_manager,
                     &mut oam_stms,
                 );
-                last_gb_frame = Instant::now();
+                // Schedule from the previous deadline (not from now) to avoid drifting, unless too far behind
+                next_gb_frame += frame_duration;
+                let now = Instant::now();
+                if now > next_gb_frame + frame_duration * 4 {
+                    next_gb_frame = now;
+                }
 
                 if is_playing {
                     frame_count += 1;
@@ -195,8 +207,18 @@ fn main() {
                         &mut selected_breakpoint,
                         &mut tick_rate,
                         &mut is_playing,
+                        &symbols,
                     );
                     ppu_debugger(ui, &mut gameboy, &oam_stms);
+                    render_watchpoints(ui, &mut gameboy, &mut watchpoint_inputs, &symbols);
+                    render_assembler(
+                        ui,
+                        &mut gameboy,
+                        &mut assemble_address,
+                        &mut assemble_source,
+                        &mut assemble_status,
+                        &symbols,
+                    );
                     if prev_playing != is_playing {
                         first_frame = Instant::now();
                         frame_count = 0;
@@ -251,12 +273,35 @@ fn main() {
         }
 
         if *control_flow != ControlFlow::Exit {
-            *control_flow = ControlFlow::WaitUntil(last_gb_frame + frame_duration);
+            *control_flow = ControlFlow::WaitUntil(next_gb_frame);
         }
         //*control_flow = ControlFlow::Poll;
     });
 }
 
+/// Symbols from the .sym file next to the ROM (as written by RGBDS or no$gmb), if there is one
+fn load_symbols(rom_path: &str) -> gameboy::SymbolTable {
+    let sym_path = std::path::Path::new(rom_path).with_extension("sym");
+    let Ok(text) = std::fs::read_to_string(&sym_path) else {
+        return gameboy::SymbolTable::new();
+    };
+
+    match gameboy::SymbolTable::parse(&text) {
+        Ok(symbols) => {
+            println!(
+                "{} symbols loaded from {}",
+                symbols.len(),
+                sym_path.display()
+            );
+            symbols
+        }
+        Err(err) => {
+            println!("{}: {}", sym_path.display(), err);
+            gameboy::SymbolTable::new()
+        }
+    }
+}
+
 fn create_window() -> (EventLoop<()>, glium::Display) {
     let event_loop = EventLoop::new();
     let context = glium::glutin::ContextBuilder::new().with_vsync(true);
@@ -290,24 +335,9 @@ fn imgui_init(display: &glium::Display) -> (imgui_winit_support::WinitPlatform,
 }
 
 /* PROCESS */
-fn perform_gameboy_frame(
-    is_playing: &mut bool,
-    gameboy: &mut GameBoy,
-    cycles_per_frame: f64,
-    breakpoints: &Vec<u16>,
-) {
-    let mut cycles = 0f64;
-    if *is_playing {
-        while cycles < cycles_per_frame {
-            //while cycles < gameboy.bus.timer.get_clock_freq() {
-            cycles += gameboy.step() as f64;
-            if breakpoints.contains(&gameboy.cpu.registers.pc) {
-                *is_playing = false;
-                break;
-            }
-            //}
-        }
-        //println!("cycles consumed {:?}", cycles);
+fn perform_gameboy_frame(is_playing: &mut bool, gameboy: &mut GameBoy, breakpoints: &Vec<u16>) {
+    if *is_playing && gameboy.run_frame_bp(Some(breakpoints)).is_breakpoint_hit {
+        *is_playing = false;
     }
 }
 /* UPDATE DISPLAY BUFFERS */
@@ -464,14 +494,8 @@ fn render_gameboy_registers(ui: &mut Ui, gameboy: &mut GameBoy) {
                 .build(ui)
             {
                 ui.text(format!("IME - {:?}", gameboy.cpu.interrupts_enabled));
-                ui.text(format!(
-                    "IF Reg - {:08b}",
-                    gameboy.bus.ram_read_byte(0xFF0F)
-                ));
-                ui.text(format!(
-                    "IE Reg - {:08b}",
-                    gameboy.bus.ram_read_byte(0xFFFF)
-                ));
+                ui.text(format!("IF Reg - {:08b}", gameboy.bus.peek_byte(0xFF0F)));
+                ui.text(format!("IE Reg - {:08b}", gameboy.bus.peek_byte(0xFFFF)));
                 ui.separator();
                 ui.text(format!("HALT? - {:?}", gameboy.cpu.is_halted));
                 ui.text(format!("HALT BUG? - {:?}", gameboy.cpu.is_halt_bugged));
@@ -495,22 +519,10 @@ fn render_gameboy_registers(ui: &mut Ui, gameboy: &mut GameBoy) {
                 .default_open(true)
                 .build(ui)
             {
-                ui.text(format!(
-                    "DIV Reg - {:#X}",
-                    gameboy.bus.ram_read_byte(0xFF04)
-                ));
-                ui.text(format!(
-                    "TIMA Reg - {:#X}",
-                    gameboy.bus.ram_read_byte(0xFF05)
-                ));
-                ui.text(format!(
-                    "TMA Reg - {:#X}",
-                    gameboy.bus.ram_read_byte(0xFF06)
-                ));
-                ui.text(format!(
-                    "TAC Reg - {:#X}",
-                    gameboy.bus.ram_read_byte(0xFF07)
-                ));
+                ui.text(format!("DIV Reg - {:#X}", gameboy.bus.peek_byte(0xFF04)));
+                ui.text(format!("TIMA Reg - {:#X}", gameboy.bus.peek_byte(0xFF05)));
+                ui.text(format!("TMA Reg - {:#X}", gameboy.bus.peek_byte(0xFF06)));
+                ui.text(format!("TAC Reg - {:#X}", gameboy.bus.peek_byte(0xFF07)));
             }
 
             // Serial Port
@@ -551,6 +563,7 @@ fn render_gameboy_instruction_stepper(
     selected_breakpoint: &mut i32,
     tick_rate: &mut i16,
     is_playing: &mut bool,
+    symbols: &gameboy::SymbolTable,
 ) {
     ui.window("Stepper")
         .size([335.0, 700.0], Condition::FirstUseEver)
@@ -598,49 +611,76 @@ fn render_gameboy_instruction_stepper(
                     [0.0, 0.0],
                     0.0,
                 ) {
+                    let rom_bank = gameboy.bus.rom_bank();
                     let mut pc_addr = gameboy.cpu.registers.pc.saturating_sub(5);
 
                     for _ in 0..10 {
-                        let opcode = gameboy.bus.ram_read_byte(pc_addr);
-                        let next_byte = gameboy.bus.ram_read_byte(pc_addr.wrapping_add(1));
-                        let next_word = gameboy.bus.ram_read_word(pc_addr.wrapping_add(1));
-
-                        let mut text = format!("");
-
-                        if !*is_playing || true {
-                            if let Ok((cycles, bytes_consumed)) = gameboy::instruction::parse_opcode(
-                                opcode, next_byte, next_word, &mut text,
-                            ) {
-                                let text: [String; 4] = [
-                                    format!("0x{:04X}", pc_addr),
-                                    match bytes_consumed {
-                                        1 => format!("{:02X}", opcode),
-                                        2 => format!("{:02X}\t{:02X}", opcode, next_byte),
-                                        _ => format!("{:02X}\t{:04X}", opcode, next_word),
-                                    },
-                                    format!("{}", text),
-                                    format!("{}", cycles),
-                                ];
-
-                                for col in 0..4 {
-                                    ui.table_next_column();
-                                    if pc_addr == gameboy.cpu.registers.pc {
-                                        ui.table_set_bg_color(
-                                            TableBgTarget::all(),
-                                            ImColor32::from_rgba(255, 0, 0, 125),
-                                        );
-                                    }
-
-                                    ui.text(&text[col]);
-                                }
+                        let location = gameboy::BankedAddress::new(pc_addr, rom_bank);
+                        if let Some(name) = symbols.symbol(location) {
+                            ui.table_next_column();
+                            ui.table_next_column();
+                            ui.table_next_column();
+                            ui.text_colored([0.0, 0.0, 0.6, 1.0], format!("{}:", name));
+                            ui.table_next_column();
+                        }
+
+                        let (instruction, info) = Instruction::decode_at(&gameboy.bus, location);
+                        let bytes: Vec<String> = (0..info.length as u16)
+                            .map(|offset| {
+                                format!(
+                                    "{:02X}",
+                                    gameboy.bus.peek_byte(pc_addr.wrapping_add(offset))
+                                )
+                            })
+                            .collect();
 
-                                pc_addr = pc_addr.wrapping_add(bytes_consumed as u16);
+                        let text: [String; 4] = [
+                            format!("0x{:04X}", pc_addr),
+                            bytes.join("\t"),
+                            describe_instruction(&instruction, rom_bank, symbols),
+                            if info.is_conditional() {
+                                format!("{}/{}", info.cycles, info.branch_cycles)
+                            } else {
+                                format!("{}", info.cycles)
+                            },
+                        ];
+
+                        for col in 0..4 {
+                            ui.table_next_column();
+                            if pc_addr == gameboy.cpu.registers.pc {
+                                ui.table_set_bg_color(
+                                    TableBgTarget::all(),
+                                    ImColor32::from_rgba(255, 0, 0, 125),
+                                );
                             }
+
+                            ui.text(&text[col]);
                         }
+
+                        pc_addr = pc_addr.wrapping_add(info.length as u16);
                     }
                 }
             }
 
+            if CollapsingHeader::new("Call Stack").build(ui) {
+                let rom_bank = gameboy.bus.rom_bank();
+                let calls = gameboy::instruction::call_stack(
+                    &gameboy.bus,
+                    gameboy.cpu.registers.sp,
+                    rom_bank,
+                );
+                for (location, instruction) in calls {
+                    let caller = symbols
+                        .describe(location)
+                        .unwrap_or_else(|| format!("0x{:04X}", location.address));
+                    ui.text(format!(
+                        "{}: {}",
+                        caller,
+                        describe_instruction(&instruction, rom_bank, symbols)
+                    ));
+                }
+            }
+
             ui.separator();
             // Step/Play Functionality
             {
@@ -683,6 +723,19 @@ fn render_gameboy_instruction_stepper(
                 if ui.button(if *is_playing { "Pause" } else { "Play" }) {
                     *is_playing = !*is_playing;
                 }
+
+                // Trace every executed instruction (Gameboy Doctor format)
+                let mut is_tracing = gameboy.cpu.is_tracing();
+                if ui.checkbox("Trace to gb-log", &mut is_tracing) {
+                    if is_tracing {
+                        match gameboy::trace::FileTraceSink::create("gb-log") {
+                            Ok(sink) => gameboy.cpu.set_trace_sink(Some(Box::new(sink))),
+                            Err(err) => println!("{:?}", err),
+                        }
+                    } else {
+                        gameboy.cpu.set_trace_sink(None);
+                    }
+                }
             }
             ui.separator();
             // Breakpoint Manager
@@ -705,14 +758,33 @@ fn render_gameboy_instruction_stepper(
                         .always_auto_resize(true)
                         .build(|| {
                             ui.input_text("Breakpoint Address", breakpoint_input)
-                                .hint("0x0100")
+                                .hint("0x0100 or label")
                                 .build();
                             ui.separator();
 
                             let without_prefix = breakpoint_input.trim_start_matches("0x");
                             if ui.button_with_size("OK", [120.0, 0.0]) {
                                 let result = u16::from_str_radix(&without_prefix, 16);
-                                if let Ok(addr) = &result {
+                                let symbol = symbols.address_of(breakpoint_input.trim());
+                                // Breakpoints only hold an address, they would stop in every bank
+                                let banked = symbol.filter(|location| {
+                                    (0x4000..=0x7FFF).contains(&location.address)
+                                });
+                                if let Some(location) = banked {
+                                    println!(
+                                        "{} is in ROM bank {:02X}, banked labels can not be breakpoints",
+                                        breakpoint_input.trim(),
+                                        location.bank
+                                    );
+                                } else if let Some(location) = symbol {
+                                    breakpoints.push(location.address);
+                                    breakpoint_labels.push(format!(
+                                        "0x{:04X} ({})",
+                                        location.address,
+                                        breakpoint_input.trim()
+                                    ));
+                                    ui.close_current_popup();
+                                } else if let Ok(addr) = &result {
                                     breakpoints.push(*addr);
                                     breakpoint_labels.push(format!("0x{:04X}", addr));
                                     ui.close_current_popup();
@@ -743,26 +815,246 @@ fn render_gameboy_instruction_stepper(
         });
 }
 
+/// What is being typed in the watchpoint window, kept between frames
+struct WatchpointInputs {
+    start: String,
+    end: String,
+    bank: String,
+    /// Index in the "Break on" combo box
+    kind: usize,
+    /// Index in the "When" combo box
+    condition: usize,
+    value: String,
+}
+
+impl WatchpointInputs {
+    fn new() -> Self {
+        WatchpointInputs {
+            start: String::with_capacity(8),
+            end: String::with_capacity(8),
+            bank: String::with_capacity(4),
+            kind: 1, // Write
+            condition: 0,
+            value: String::with_capacity(4),
+        }
+    }
+}
+
+/// Watchpoints break on memory accesses by the CPU, after the instruction that made them
+fn render_watchpoints(
+    ui: &mut Ui,
+    gameboy: &mut GameBoy,
+    inputs: &mut WatchpointInputs,
+    symbols: &gameboy::SymbolTable,
+) {
+    use gameboy::watchpoint::{WatchCondition, WatchKind};
+    const KINDS: [(&str, WatchKind); 3] = [
+        ("Read", WatchKind::Read),
+        ("Write", WatchKind::Write),
+        ("Access", WatchKind::Access),
+    ];
+    const CONDITIONS: [&str; 3] = ["Always", "Equals", "Changes"];
+
+    ui.window("Watchpoints")
+        .size([300.0, 400.0], Condition::FirstUseEver)
+        .position([985.0, 5.0], Condition::FirstUseEver)
+        .build(|| {
+            let watchpoints = &mut gameboy.bus.watchpoints;
+            let mut removed = None;
+            for (index, watchpoint) in watchpoints.list.iter().enumerate() {
+                let bank = match watchpoint.bank {
+                    Some(bank) => format!("{:02X}:", bank),
+                    None => String::new(),
+                };
+                let condition = match watchpoint.condition {
+                    WatchCondition::Always => String::new(),
+                    WatchCondition::Equals(value) => format!(" == 0x{:02X}", value),
+                    WatchCondition::Changes => " changes".to_string(),
+                };
+                ui.text(format!(
+                    "{}0x{:04X}-0x{:04X} {:?}{} ({} hits)",
+                    bank,
+                    watchpoint.range.start(),
+                    watchpoint.range.end(),
+                    watchpoint.kind,
+                    condition,
+                    watchpoint.hit_count.get()
+                ));
+                ui.same_line();
+                if ui.small_button(format!("-##watchpoint{}", index)) {
+                    removed = Some(index);
+                }
+            }
+            if let Some(index) = removed {
+                watchpoints.list.remove(index);
+            }
+            if ui.button("Reset hits") {
+                for watchpoint in &watchpoints.list {
+                    watchpoint.hit_count.set(0);
+                }
+            }
+            if let Some(hit) = watchpoints.last_hit() {
+                ui.text(format!(
+                    "Last hit: {} 0x{:04X} = 0x{:02X}",
+                    if hit.is_write { "write" } else { "read" },
+                    hit.address,
+                    hit.value
+                ));
+            }
+
+            ui.separator();
+            ui.input_text("From", &mut inputs.start)
+                .hint("0xC000 or label")
+                .build();
+            ui.input_text("To", &mut inputs.end)
+                .hint("same as From")
+                .build();
+            ui.input_text("Bank", &mut inputs.bank)
+                .hint("any (ROMX/SRAM only)")
+                .build();
+            let kind_names = KINDS.map(|(name, _)| name);
+            ui.combo_simple_string("Break on", &mut inputs.kind, &kind_names);
+            ui.combo_simple_string("When", &mut inputs.condition, &CONDITIONS);
+            if inputs.condition == 1 {
+                ui.input_text("Value", &mut inputs.value)
+                    .hint("0x00")
+                    .build();
+            }
+
+            if ui.button("Add") {
+                let parse_hex = |input: &str| {
+                    u16::from_str_radix(input.trim().trim_start_matches("0x"), 16).ok()
+                };
+                let parse_address = |input: &str| {
+                    symbols
+                        .address_of(input.trim())
+                        .map(|location| location.address)
+                        .or_else(|| parse_hex(input))
+                };
+                let start = parse_address(&inputs.start);
+                let end = if inputs.end.trim().is_empty() {
+                    start
+                } else {
+                    parse_address(&inputs.end)
+                };
+                let condition = match inputs.condition {
+                    0 => Some(WatchCondition::Always),
+                    1 => parse_hex(&inputs.value)
+                        .filter(|value| *value <= 0xFF)
+                        .map(|value| WatchCondition::Equals(value as u8)),
+                    _ => Some(WatchCondition::Changes),
+                };
+
+                match (start, end, condition) {
+                    (Some(start), Some(end), Some(condition)) if start <= end => {
+                        let mut watchpoint =
+                            gameboy::Watchpoint::new(start..=end, KINDS[inputs.kind].1, condition);
+                        // A label in switchable ROM or external RAM only names the address in its own bank
+                        let label_bank = symbols
+                            .address_of(inputs.start.trim())
+                            .filter(|location| {
+                                matches!(location.address, 0x4000..=0x7FFF | 0xA000..=0xBFFF)
+                            })
+                            .map(|location| location.bank);
+                        if let Some(bank) = parse_hex(&inputs.bank).or(label_bank) {
+                            watchpoint = watchpoint.in_bank(bank);
+                        }
+                        watchpoints.list.push(watchpoint);
+                        inputs.start.clear();
+                        inputs.end.clear();
+                    }
+                    _ => println!("Invalid watchpoint"),
+                }
+            }
+        });
+}
+
+/// Instruction text with symbols, branches to an address without a symbol of its own are annotated with
+/// the closest one ("JP $0162 ; Main+$12")
+fn describe_instruction(
+    instruction: &Instruction,
+    rom_bank: u16,
+    symbols: &gameboy::SymbolTable,
+) -> String {
+    let text = instruction.format(rom_bank, symbols);
+    let target = instruction
+        .target()
+        .map(|target| gameboy::BankedAddress::new(target, rom_bank));
+    match target {
+        Some(target) if symbols.symbol(target).is_none() => match symbols.describe(target) {
+            Some(name) => format!("{} ; {}", text, name),
+            None => text,
+        },
+        _ => text,
+    }
+}
+
+/// Assemble code at an address: WRAM/HRAM are written to, ROM addresses patch the loaded ROM image
+fn render_assembler(
+    ui: &mut Ui,
+    gameboy: &mut GameBoy,
+    address_input: &mut String,
+    source: &mut String,
+    status: &mut String,
+    symbols: &gameboy::SymbolTable,
+) {
+    ui.window("Assembler")
+        .size([300.0, 280.0], Condition::FirstUseEver)
+        .position([675.0, 515.0], Condition::FirstUseEver)
+        .build(|| {
+            ui.input_text("Address", address_input)
+                .hint("0xC000 or 02:4000")
+                .build();
+            ui.input_text_multiline("##source", source, [-1.0, 180.0])
+                .build();
+
+            if ui.button("Assemble") {
+                *status = match assemble_at(gameboy, address_input, source, symbols) {
+                    Ok(length) => format!("{} bytes written", length),
+                    Err(err) => err,
+                };
+            }
+            ui.text_wrapped(&*status);
+        });
+}
+
+/// `address` is either "0xAAAA" (in the mapped ROM bank) or "BB:AAAA", returns the number of bytes written
+fn assemble_at(
+    gameboy: &mut GameBoy,
+    address: &str,
+    source: &str,
+    symbols: &gameboy::SymbolTable,
+) -> Result<usize, String> {
+    let invalid = || format!("invalid address: {}", address);
+    let (bank, address) = match address.trim().split_once(':') {
+        Some((bank, address)) => (
+            u16::from_str_radix(bank, 16).map_err(|_| invalid())?,
+            address,
+        ),
+        None => (gameboy.bus.rom_bank(), address.trim()),
+    };
+    let address =
+        u16::from_str_radix(address.trim_start_matches("0x"), 16).map_err(|_| invalid())?;
+
+    let bytes = gameboy::assembler::assemble(source, address, symbols)?;
+    for (offset, byte) in bytes.iter().enumerate() {
+        let location = gameboy::BankedAddress::new(address.wrapping_add(offset as u16), bank);
+        gameboy.bus.poke_banked(location, *byte);
+    }
+    Ok(bytes.len())
+}
+
 fn ppu_debugger(ui: &mut Ui, gameboy: &mut GameBoy, oam_stms: &Vec<ScreenTextureManager>) {
     ui.window("PPU Info")
         .size([300.0, 500.0], Condition::FirstUseEver)
         .position([675.0, 5.0], Condition::Always)
         .build(|| {
-            ui.text(format!("LY Reg - {:#X}", gameboy.bus.ram_read_byte(0xFF44)));
-            ui.text(format!(
-                "LYC Reg - {:#X}",
-                gameboy.bus.ram_read_byte(0xFF45)
-            ));
-            ui.text(format!(
-                "LCDC Reg - {:08b}",
-                gameboy.bus.ram_read_byte(0xFF40)
-            ));
-            ui.text(format!(
-                "STAT Reg - {:08b}",
-                gameboy.bus.ram_read_byte(0xFF41)
-            ));
-            ui.text(format!("SCY: {:#X}", gameboy.bus.ram_read_byte(0xFF42)));
-            ui.text(format!("SCX: {:#X}", gameboy.bus.ram_read_byte(0xFF43)));
+            ui.text(format!("LY Reg - {:#X}", gameboy.bus.peek_byte(0xFF44)));
+            ui.text(format!("LYC Reg - {:#X}", gameboy.bus.peek_byte(0xFF45)));
+            ui.text(format!("LCDC Reg - {:08b}", gameboy.bus.peek_byte(0xFF40)));
+            ui.text(format!("STAT Reg - {:08b}", gameboy.bus.peek_byte(0xFF41)));
+            ui.text(format!("SCY: {:#X}", gameboy.bus.peek_byte(0xFF42)));
+            ui.text(format!("SCX: {:#X}", gameboy.bus.peek_byte(0xFF43)));
             ui.text(format!(
                 "sprite cache: {:?}",
                 gameboy.bus.ppu.scanline_sprite_cache
@@ -771,6 +1063,10 @@ fn ppu_debugger(ui: &mut Ui, gameboy: &mut GameBoy, oam_stms: &Vec<ScreenTexture
                 "sprite height: {:?}",
                 gameboy.bus.ppu.get_sprite_height()
             ));
+            ui.checkbox(
+                "Block VRAM/OAM access by PPU mode",
+                &mut gameboy.bus.ppu.is_access_restricted,
+            );
             ui.separator();
 
             ui.child_window("OAM")
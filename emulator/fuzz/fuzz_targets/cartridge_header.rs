@@ -0,0 +1,16 @@
+//! Parse arbitrary bytes as a cartridge header
+
+#![no_main]
+
+use emulator::CartridgeHeader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let header = CartridgeHeader::new(data);
+
+    // Must survive a save/load cycle unchanged
+    let bytes = bincode::serialize(&header).expect("header failed to serialize");
+    let restored: CartridgeHeader =
+        bincode::deserialize(&bytes).expect("header failed to deserialize");
+    assert!(header == restored, "header changed after a round trip");
+});
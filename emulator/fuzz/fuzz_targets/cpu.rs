@@ -0,0 +1,35 @@
+//! Execute a random instruction stream for a fixed number of steps
+
+#![no_main]
+
+use emulator::{GameBoy, Model, CPU};
+use libfuzzer_sys::fuzz_target;
+
+const STEPS: usize = 1000;
+
+fuzz_target!(|data: &[u8]| {
+    // Wrap the input in a 32 KiB ROM only cartridge
+    let mut rom = vec![0u8; 0x8000];
+    let length = data.len().min(rom.len());
+    rom[..length].copy_from_slice(&data[..length]);
+    rom[0x0147] = 0x00; // ROM ONLY
+    rom[0x0148] = 0x00; // 32 KiB
+
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy
+        .read_rom(&rom)
+        .expect("ROM only cartridge was rejected");
+
+    for _ in 0..STEPS {
+        gameboy.step();
+    }
+
+    // Must survive a save/load cycle unchanged
+    let bytes = bincode::serialize(&gameboy.cpu).expect("CPU failed to serialize");
+    let restored: CPU = bincode::deserialize(&bytes).expect("CPU failed to deserialize");
+    assert_eq!(
+        bytes,
+        bincode::serialize(&restored).unwrap(),
+        "CPU changed after a round trip"
+    );
+});
@@ -0,0 +1,23 @@
+//! Load arbitrary bytes as a ROM
+
+#![no_main]
+
+use emulator::gameboy::cartridge::MBC;
+use emulator::{GameBoy, Model};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut gameboy = GameBoy::new(Model::DMG);
+    if gameboy.read_rom(&data.to_vec()).is_err() {
+        return;
+    }
+
+    // The loaded mapper must survive a save/load cycle unchanged
+    let bytes = bincode::serialize(&gameboy.bus.mbc).expect("MBC failed to serialize");
+    let restored: Box<dyn MBC> = bincode::deserialize(&bytes).expect("MBC failed to deserialize");
+    assert_eq!(
+        bytes,
+        bincode::serialize(&restored).unwrap(),
+        "MBC changed after a round trip"
+    );
+});
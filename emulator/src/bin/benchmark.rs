@@ -0,0 +1,113 @@
+//! Headless emulation speed benchmark
+//!
+//! Runs a ROM (or, without one, a built-in loop that keeps the CPU, timer and PPU busy) for a number of frames
+//! as fast as possible, and reports how many times faster than a real Game Boy that was.
+//!
+//! Usage: benchmark [<rom>] [--frames <count>]
+
+use std::process::ExitCode;
+use std::time::Instant;
+
+use emulator::{GameBoy, Model};
+
+const DEFAULT_FRAMES: u32 = 3600;
+/// Frames per second of a real Game Boy
+const FRAME_RATE: f64 = 59.7275;
+
+struct Options {
+    rom_path: Option<String>,
+    frames: u32,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Usage: benchmark [<rom>] [--frames <count>]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut positional: Vec<String> = Vec::new();
+    let mut frames = DEFAULT_FRAMES;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--frames" => {
+                let value = iter.next().ok_or("--frames requires a value")?;
+                frames = value
+                    .parse()
+                    .map_err(|_| format!("invalid frame count: {}", value))?;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() > 1 {
+        return Err("expected at most one ROM".to_string());
+    }
+
+    Ok(Options {
+        rom_path: positional.pop(),
+        frames,
+    })
+}
+
+/// Enables the timer, then adds to every byte of a 256 byte buffer in WRAM, forever
+fn builtin_rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0103].copy_from_slice(&[0xC3, 0x50, 0x01]); // JP $0150
+    rom[0x0150..0x0161].copy_from_slice(&[
+        0x3E, 0x05, // LD A, $05
+        0xE0, 0x07, // LDH (TAC), A
+        0x21, 0x00, 0xC0, // .outer: LD HL, $C000
+        0x06, 0x00, // LD B, 0
+        0x2A, // .inner: LD A, (HL+)
+        0x80, // ADD A, B
+        0x77, // LD (HL), A
+        0x05, // DEC B
+        0x20, 0xFA, // JR NZ, .inner
+        0x18, 0xF3, // JR .outer
+    ]);
+    rom
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let rom = match &options.rom_path {
+        Some(rom_path) => {
+            std::fs::read(rom_path).map_err(|e| format!("unable to read {}: {}", rom_path, e))?
+        }
+        None => builtin_rom(),
+    };
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom)?;
+
+    let start = Instant::now();
+    for _ in 0..options.frames {
+        gameboy.run_frame();
+    }
+    let seconds = start.elapsed().as_secs_f64();
+
+    let frames_per_second = options.frames as f64 / seconds;
+    println!(
+        "{} frames in {:.3} s: {:.0} frames/s, {:.1}x real time",
+        options.frames,
+        seconds,
+        frames_per_second,
+        frames_per_second / FRAME_RATE
+    );
+
+    Ok(())
+}
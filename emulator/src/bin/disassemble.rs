@@ -0,0 +1,113 @@
+//! Static disassembler, writes a ROM out as RGBDS source that rebuilds into the same ROM
+//!
+//! Code is found from the entry point and the RST and interrupt vectors, following jumps, calls and
+//! bank switches to constant banks. A .sym file names addresses and adds entry points, a code/data log
+//! (one flag byte per ROM byte, as written by Mesen) marks what was seen running.
+//!
+//! Usage: disassemble <rom> [--sym <file>] [--cdl <file>] [-o <file>]
+
+use std::fs::File;
+use std::io::Read;
+use std::process::ExitCode;
+
+use emulator::gameboy::{Disassembler, SymbolTable};
+
+struct Options {
+    rom_path: String,
+    sym_path: Option<String>,
+    cdl_path: Option<String>,
+    output_path: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Usage: disassemble <rom> [--sym <file>] [--cdl <file>] [-o <file>]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut positional: Vec<String> = Vec::new();
+    let mut sym_path = None;
+    let mut cdl_path = None;
+    let mut output_path = None;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sym" => sym_path = Some(iter.next().ok_or("--sym requires a file")?),
+            "--cdl" => cdl_path = Some(iter.next().ok_or("--cdl requires a file")?),
+            "-o" => output_path = Some(iter.next().ok_or("-o requires a file")?),
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() != 1 {
+        return Err("expected a ROM".to_string());
+    }
+
+    Ok(Options {
+        rom_path: positional[0].clone(),
+        sym_path,
+        cdl_path,
+        output_path,
+    })
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut buffer))
+        .map_err(|e| format!("unable to read {}: {}", path, e))?;
+
+    Ok(buffer)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let rom = read_file(&options.rom_path)?;
+    let code_data_log = match &options.cdl_path {
+        Some(cdl_path) => Some(read_file(cdl_path)?),
+        None => None,
+    };
+    let mut disassembler = Disassembler::new(&rom)?;
+
+    if let Some(sym_path) = &options.sym_path {
+        let text = String::from_utf8_lossy(&read_file(sym_path)?).into_owned();
+        let symbols = SymbolTable::parse(&text).map_err(|e| format!("{}: {}", sym_path, e))?;
+        disassembler.set_symbols(symbols);
+    }
+    if let Some(code_data_log) = &code_data_log {
+        disassembler.set_code_data_log(code_data_log)?;
+    }
+
+    disassembler.analyze();
+    let mut source = String::new();
+    disassembler
+        .write_source(&mut source)
+        .map_err(|e| format!("unable to write the source: {}", e))?;
+
+    match &options.output_path {
+        Some(output_path) => std::fs::write(output_path, source)
+            .map_err(|e| format!("unable to write {}: {}", output_path, e))?,
+        None => print!("{}", source),
+    }
+    eprintln!(
+        "{} of {} bytes found to be code",
+        disassembler.code_size(),
+        rom.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! Differential trace comparison against a reference log (Gameboy Doctor format)
+//!
+//! Runs the ROM and compares every traced instruction against the reference log, line by line.
+//! Stops at the first divergence and prints the instructions around it along with a register diff.
+//!
+//! Usage: trace_compare <rom> <reference log> [--window <lines>] [--boot-rom <file>] [--model <model>] [--real-ly]
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::process::ExitCode;
+use std::sync::mpsc;
+
+use emulator::gameboy::trace::diff_trace_lines;
+use emulator::{GameBoy, Model};
+
+const DEFAULT_WINDOW: usize = 10;
+/// Give up if the emulator runs this many steps without executing an instruction (e.g. stuck in HALT)
+const MAX_IDLE_STEPS: usize = 10_000_000;
+
+struct Options {
+    rom_path: String,
+    reference_path: String,
+    boot_rom_path: Option<String>,
+    model: Model,
+    window: usize,
+    stub_ly: bool,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Usage: trace_compare <rom> <reference log> [--window <lines>] [--boot-rom <file>] [--model <model>] [--real-ly]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut positional: Vec<String> = Vec::new();
+    let mut boot_rom_path = None;
+    let mut model = Model::DMG;
+    let mut window = DEFAULT_WINDOW;
+    let mut stub_ly = true;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--window" => {
+                let value = iter.next().ok_or("--window requires a value")?;
+                window = value
+                    .parse()
+                    .map_err(|_| format!("invalid window size: {}", value))?;
+            }
+            "--boot-rom" => {
+                boot_rom_path = Some(iter.next().ok_or("--boot-rom requires a file")?);
+            }
+            "--model" => {
+                model = iter.next().ok_or("--model requires a value")?.parse()?;
+            }
+            "--real-ly" => stub_ly = false,
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() != 2 {
+        return Err("expected a ROM and a reference log".to_string());
+    }
+
+    Ok(Options {
+        rom_path: positional[0].clone(),
+        reference_path: positional[1].clone(),
+        boot_rom_path,
+        model,
+        window,
+        stub_ly,
+    })
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut buffer))
+        .map_err(|e| format!("unable to read {}: {}", path, e))?;
+
+    Ok(buffer)
+}
+
+/// Returns whether the emulator matched the entire reference log
+fn run(options: &Options) -> Result<bool, String> {
+    let mut gameboy = GameBoy::new(options.model);
+    if let Some(boot_rom_path) = &options.boot_rom_path {
+        gameboy.read_boot_rom(&read_file(boot_rom_path)?);
+    }
+    gameboy.read_rom(&read_file(&options.rom_path)?)?;
+    if options.stub_ly {
+        gameboy.bus.ly_override = Some(0x90);
+    }
+
+    let (sender, receiver) = mpsc::channel::<String>();
+    gameboy.cpu.set_trace_sink(Some(Box::new(sender)));
+
+    let reference = File::open(&options.reference_path)
+        .map_err(|e| format!("unable to open {}: {}", options.reference_path, e))?;
+    let mut reference_lines = BufReader::new(reference).lines();
+
+    let mut history: VecDeque<String> = VecDeque::with_capacity(options.window + 1);
+    let mut line_no: usize = 0;
+    let mut idle_steps: usize = 0;
+
+    loop {
+        gameboy.step();
+
+        let mut traced = false;
+        for actual in receiver.try_iter() {
+            traced = true;
+            let expected = match reference_lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Err(format!("unable to read reference log: {}", e)),
+                None => {
+                    println!(
+                        "Reference log exhausted after {} lines, no divergence",
+                        line_no
+                    );
+                    return Ok(true);
+                }
+            };
+            line_no += 1;
+
+            if !expected.trim().eq_ignore_ascii_case(actual.trim()) {
+                let expected_after = reference_lines
+                    .by_ref()
+                    .take(options.window)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("unable to read reference log: {}", e))?;
+                let actual_after = next_traced_lines(&mut gameboy, &receiver, options.window);
+                report_divergence(
+                    &history,
+                    line_no,
+                    (&expected, &expected_after),
+                    (&actual, &actual_after),
+                );
+                return Ok(false);
+            }
+
+            history.push_back(actual);
+            if history.len() > options.window {
+                history.pop_front();
+            }
+        }
+
+        idle_steps = if traced { 0 } else { idle_steps + 1 };
+        if idle_steps > MAX_IDLE_STEPS {
+            println!(
+                "Emulator stopped executing instructions after {} matching lines",
+                line_no
+            );
+            return Ok(false);
+        }
+    }
+}
+
+/// Keep running until `count` more instructions were traced (or the emulator stops executing them)
+fn next_traced_lines(
+    gameboy: &mut GameBoy,
+    receiver: &mpsc::Receiver<String>,
+    count: usize,
+) -> Vec<String> {
+    let mut lines: Vec<String> = receiver.try_iter().collect();
+    let mut idle_steps: usize = 0;
+    while lines.len() < count && idle_steps <= MAX_IDLE_STEPS {
+        gameboy.step();
+        let count_before = lines.len();
+        lines.extend(receiver.try_iter());
+        idle_steps = if lines.len() > count_before {
+            0
+        } else {
+            idle_steps + 1
+        };
+    }
+    lines.truncate(count);
+    lines
+}
+
+/// `expected` and `actual` are the divergent lines, followed by the lines after them
+fn report_divergence(
+    history: &VecDeque<String>,
+    line_no: usize,
+    expected: (&str, &[String]),
+    actual: (&str, &[String]),
+) {
+    println!("Divergence at line {}\n", line_no);
+
+    let first_line = line_no - history.len();
+    for (offset, line) in history.iter().enumerate() {
+        println!("  {:>8}  {}", first_line + offset, line);
+    }
+    for ((line, after), source) in [(expected, "expected"), (actual, "actual")] {
+        println!("> {:>8}  {}  ({})", line_no, line, source);
+        for (offset, line) in after.iter().enumerate() {
+            println!("  {:>8}  {}", line_no + 1 + offset, line);
+        }
+        println!();
+    }
+
+    println!("Register diff:");
+    for difference in diff_trace_lines(expected.0, actual.0) {
+        println!("  {}", difference);
+    }
+}
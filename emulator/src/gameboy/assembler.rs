@@ -0,0 +1,509 @@
+//! SM83 assembler
+//!
+//! Accepts what the disassembler writes, in either syntax ("LD A, (HL+)" or "ld a, [hl+]"), along with
+//! labels ("name:"), `DB`/`DW` data and expressions made of numbers ($FF, 0xFF, %1010 or 255), labels,
+//! symbols, `@` (the address of the current line) and the + - * / operators.
+//! Instructions are looked up in the opcode table, so that only what the CPU executes is accepted.
+
+use std::collections::HashMap;
+
+use super::opcode::{Condition, Opcode, OperandKind, CB_OPCODES, OPCODES};
+use super::symbols::SymbolTable;
+
+/// Mnemonics whose first operand may be left out when it is A ("SUB B" and "SUB A, B")
+const ALU_MNEMONICS: [&str; 8] = ["ADD", "ADC", "SUB", "SBC", "AND", "XOR", "OR", "CP"];
+
+/// Register and condition names, anything else is an expression
+const NAMES: [&str; 15] = [
+    "A", "B", "C", "D", "E", "H", "L", "AF", "BC", "DE", "HL", "SP", "NZ", "Z", "NC",
+];
+
+/// An operand as written in the source
+#[derive(Clone, Debug, PartialEq)]
+enum Argument {
+    /// Register or condition, upper case
+    Name(String),
+    Expression(String),
+    /// (...) or [...], the name "C" stands for ($FF00 + C)
+    Memory(Box<Argument>),
+    /// SP + e8, the expression includes the sign
+    SPPlus(String),
+}
+
+enum Statement {
+    Instruction(String, Vec<Argument>),
+    /// Values, with the size of each in bytes
+    Data(usize, Vec<String>),
+}
+
+struct Line {
+    number: usize,
+    address: u16,
+    statement: Statement,
+}
+
+/// Assemble `source` as if placed at `origin`, names that are not labels are looked up in `symbols`
+pub fn assemble(source: &str, origin: u16, symbols: &SymbolTable) -> Result<Vec<u8>, String> {
+    let mut labels: HashMap<String, u16> = HashMap::new();
+    let mut lines: Vec<Line> = Vec::new();
+    let mut address = origin as u32;
+
+    // First pass, find the address of every line (labels defined later are taken as 0 meanwhile)
+    for (index, text) in source.lines().enumerate() {
+        let number = index + 1;
+        let error = |message: String| format!("line {}: {}", number, message);
+
+        let (label, statement) = parse_line(text).map_err(error)?;
+        if let Some(label) = label {
+            if labels.insert(label.clone(), address as u16).is_some() {
+                return Err(error(format!("label {} is defined twice", label)));
+            }
+        }
+        let Some(statement) = statement else {
+            continue;
+        };
+
+        let resolve = |name: &str| Some(lookup(name, &labels, symbols).unwrap_or(0));
+        let size = match &statement {
+            Statement::Instruction(mnemonic, arguments) => {
+                find_opcode(mnemonic, arguments, &resolve, address as u16)
+                    .map_err(error)?
+                    .1
+                    .length as u32
+            }
+            Statement::Data(size, values) => (size * values.len()) as u32,
+        };
+        if address + size > 0x10000 {
+            return Err(error("the code goes past $FFFF".to_string()));
+        }
+        lines.push(Line {
+            number,
+            address: address as u16,
+            statement,
+        });
+        address += size;
+    }
+
+    // Second pass, encode with every label known
+    let mut bytes = Vec::new();
+    for line in &lines {
+        let error = |message: String| format!("line {}: {}", line.number, message);
+        let resolve = |name: &str| lookup(name, &labels, symbols);
+
+        match &line.statement {
+            Statement::Instruction(mnemonic, arguments) => {
+                let (arguments, info, opcode) =
+                    find_opcode(mnemonic, arguments, &resolve, line.address).map_err(error)?;
+                let start = bytes.len();
+                bytes.extend_from_slice(&opcode);
+                let next_address = line.address.wrapping_add(info.length as u16);
+                for (kind, argument) in info.operands.iter().zip(&arguments) {
+                    encode(
+                        *kind,
+                        argument,
+                        next_address,
+                        line.address,
+                        &resolve,
+                        &mut bytes,
+                    )
+                    .map_err(error)?;
+                }
+                // STOP is followed by a padding byte
+                bytes.resize(start + info.length as usize, 0);
+            }
+            Statement::Data(size, values) => {
+                for value in values {
+                    let value = evaluate(value, line.address, &resolve).map_err(error)?;
+                    match size {
+                        1 => bytes.push(check_range(value, -0x80, 0xFF).map_err(error)? as u8),
+                        _ => bytes.extend_from_slice(
+                            &(check_range(value, -0x8000, 0xFFFF).map_err(error)? as u16)
+                                .to_le_bytes(),
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn lookup(name: &str, labels: &HashMap<String, u16>, symbols: &SymbolTable) -> Option<i64> {
+    labels
+        .get(name)
+        .copied()
+        .or_else(|| symbols.address_of(name).map(|address| address.address))
+        .map(i64::from)
+}
+
+/// Split a line into its label and statement
+fn parse_line(text: &str) -> Result<(Option<String>, Option<Statement>), String> {
+    let mut text = text.split(';').next().unwrap_or("").trim();
+
+    let mut label = None;
+    if let Some((name, rest)) = text.split_once(':') {
+        let name = name.trim();
+        if is_identifier(name) && !NAMES.contains(&name.to_uppercase().as_str()) {
+            label = Some(name.to_string());
+            // "name::" exports the label in RGBDS
+            text = rest.strip_prefix(':').unwrap_or(rest).trim();
+        }
+    }
+    if text.is_empty() {
+        return Ok((label, None));
+    }
+
+    let (mnemonic, operands) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let mnemonic = mnemonic.to_uppercase();
+    let operands: Vec<&str> = if operands.trim().is_empty() {
+        Vec::new()
+    } else {
+        operands.split(',').map(str::trim).collect()
+    };
+
+    let statement = match mnemonic.as_str() {
+        "DB" | "DW" if operands.is_empty() => {
+            return Err(format!("{} requires at least one value", mnemonic))
+        }
+        "DB" => Statement::Data(1, operands.iter().map(|s| s.to_string()).collect()),
+        "DW" => Statement::Data(2, operands.iter().map(|s| s.to_string()).collect()),
+        _ => {
+            let arguments = operands
+                .iter()
+                .map(|operand| parse_argument(operand))
+                .collect::<Result<_, _>>()?;
+            Statement::Instruction(mnemonic, arguments)
+        }
+    };
+    Ok((label, Some(statement)))
+}
+
+fn parse_argument(text: &str) -> Result<Argument, String> {
+    if text.is_empty() {
+        return Err("missing operand".to_string());
+    }
+
+    let upper = text.to_uppercase();
+    let inside = upper
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .or_else(|| {
+            upper
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+        });
+    if let Some(inside) = inside {
+        let compact: String = inside.chars().filter(|c| !c.is_whitespace()).collect();
+        let argument = match compact.as_str() {
+            "HL+" | "HLI" => Argument::Name("HL+".to_string()),
+            "HL-" | "HLD" => Argument::Name("HL-".to_string()),
+            "C" | "$FF00+C" | "0XFF00+C" => Argument::Name("C".to_string()),
+            "BC" | "DE" | "HL" => Argument::Name(compact),
+            _ => Argument::Expression(text[1..text.len() - 1].trim().to_string()),
+        };
+        return Ok(Argument::Memory(Box::new(argument)));
+    }
+
+    if NAMES.contains(&upper.as_str()) {
+        return Ok(Argument::Name(upper));
+    }
+    if text.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("sp")) {
+        let offset = text[2..].trim_start();
+        if offset.starts_with('+') || offset.starts_with('-') {
+            return Ok(Argument::SPPlus(offset.to_string()));
+        }
+    }
+    Ok(Argument::Expression(text.to_string()))
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Find the opcode for an instruction, returns the arguments as they line up with its operands and the
+/// opcode bytes (including the CB prefix)
+fn find_opcode(
+    mnemonic: &str,
+    arguments: &[Argument],
+    resolve: &impl Fn(&str) -> Option<i64>,
+    address: u16,
+) -> Result<(Vec<Argument>, &'static Opcode, Vec<u8>), String> {
+    let a = Argument::Name("A".to_string());
+    let mut candidates = vec![arguments.to_vec()];
+    if ALU_MNEMONICS.contains(&mnemonic) {
+        match arguments {
+            [first, rest @ ..] if *first == a && !rest.is_empty() => candidates.push(rest.to_vec()),
+            [_] => candidates.push([a.clone()].into_iter().chain(arguments.to_vec()).collect()),
+            _ => {}
+        }
+    }
+    // "JP (HL)" is another way to write "JP HL"
+    if mnemonic == "JP" && arguments == [Argument::Memory(Box::new(Argument::Name("HL".into())))] {
+        candidates.push(vec![Argument::Name("HL".to_string())]);
+    }
+    // "LDH A, (C)" is another way to write "LD A, ($FF00 + C)"
+    let high_c = Argument::Memory(Box::new(Argument::Name("C".to_string())));
+    let mnemonic = if mnemonic == "LDH" && arguments.contains(&high_c) {
+        "LD"
+    } else {
+        mnemonic
+    };
+
+    let opcodes = OPCODES
+        .iter()
+        .enumerate()
+        .map(|(opcode, info)| (info, vec![opcode as u8]))
+        .chain(
+            CB_OPCODES
+                .iter()
+                .enumerate()
+                .map(|(opcode, info)| (info, vec![0xCB, opcode as u8])),
+        );
+    let mut is_known = false;
+    for (info, bytes) in opcodes {
+        if info.mnemonic != mnemonic || info.is_illegal() {
+            continue;
+        }
+        is_known = true;
+        for candidate in &candidates {
+            if candidate.len() == info.operands.len()
+                && info
+                    .operands
+                    .iter()
+                    .zip(candidate)
+                    .all(|(kind, argument)| fits(*kind, argument, address, resolve))
+            {
+                return Ok((candidate.clone(), info, bytes));
+            }
+        }
+    }
+
+    if is_known && mnemonic != "PREFIX" {
+        Err(format!("invalid operands for {}", mnemonic))
+    } else {
+        Err(format!("unknown instruction {}", mnemonic))
+    }
+}
+
+/// Whether an argument can be the given operand, value ranges are checked when encoding
+fn fits(
+    kind: OperandKind,
+    argument: &Argument,
+    address: u16,
+    resolve: &impl Fn(&str) -> Option<i64>,
+) -> bool {
+    let name = |name: &str| *argument == Argument::Name(name.to_string());
+    let memory = |name: &str| *argument == Argument::Memory(Box::new(Argument::Name(name.into())));
+    let is_memory_expression =
+        matches!(argument, Argument::Memory(inside) if matches!(**inside, Argument::Expression(_)));
+    let value = || match argument {
+        Argument::Expression(text) => evaluate(text, address, resolve).ok(),
+        _ => None,
+    };
+
+    match kind {
+        OperandKind::A => name("A"),
+        OperandKind::B => name("B"),
+        OperandKind::C => name("C"),
+        OperandKind::D => name("D"),
+        OperandKind::E => name("E"),
+        OperandKind::H => name("H"),
+        OperandKind::L => name("L"),
+        OperandKind::AF => name("AF"),
+        OperandKind::BC => name("BC"),
+        OperandKind::DE => name("DE"),
+        OperandKind::HL => name("HL"),
+        OperandKind::SP => name("SP"),
+        OperandKind::AddressBC => memory("BC"),
+        OperandKind::AddressDE => memory("DE"),
+        OperandKind::AddressHL => memory("HL"),
+        OperandKind::AddressHLIncrement => memory("HL+"),
+        OperandKind::AddressHLDecrement => memory("HL-"),
+        OperandKind::HighAddressC => memory("C"),
+        OperandKind::Immediate8
+        | OperandKind::Immediate16
+        | OperandKind::Signed8
+        | OperandKind::Relative8 => matches!(argument, Argument::Expression(_)),
+        OperandKind::SPPlusSigned8 => matches!(argument, Argument::SPPlus(_)),
+        OperandKind::HighAddress8 | OperandKind::Address16 => is_memory_expression,
+        OperandKind::Condition(condition) => name(match condition {
+            Condition::NZ => "NZ",
+            Condition::Z => "Z",
+            Condition::NC => "NC",
+            Condition::C => "C",
+        }),
+        OperandKind::Bit(bit) => value() == Some(bit as i64),
+        OperandKind::Vector(vector) => value() == Some(vector as i64),
+    }
+}
+
+/// Append the immediate bytes of an operand
+fn encode(
+    kind: OperandKind,
+    argument: &Argument,
+    next_address: u16,
+    address: u16,
+    resolve: &impl Fn(&str) -> Option<i64>,
+    bytes: &mut Vec<u8>,
+) -> Result<(), String> {
+    let text = match argument {
+        Argument::Expression(text) | Argument::SPPlus(text) => text,
+        Argument::Memory(inside) => match &**inside {
+            Argument::Expression(text) => text,
+            _ => return Ok(()),
+        },
+        Argument::Name(_) => return Ok(()),
+    };
+    if kind.size() == 0 {
+        return Ok(());
+    }
+    let value = evaluate(text, address, resolve)?;
+
+    match kind {
+        OperandKind::Immediate8 => bytes.push(check_range(value, -0x80, 0xFF)? as u8),
+        OperandKind::Signed8 | OperandKind::SPPlusSigned8 => {
+            bytes.push(check_range(value, -0x80, 0x7F)? as u8)
+        }
+        OperandKind::Relative8 => {
+            let offset = value - next_address as i64;
+            if !(-0x80..=0x7F).contains(&offset) {
+                return Err(format!("${:04X} is too far for a relative jump", value));
+            }
+            bytes.push(offset as u8);
+        }
+        OperandKind::HighAddress8 => match value {
+            0xFF00..=0xFFFF | 0x00..=0xFF => bytes.push(value as u8),
+            _ => return Err(format!("${:04X} is not in $FF00-$FFFF", value)),
+        },
+        _ => bytes.extend_from_slice(&(check_range(value, -0x8000, 0xFFFF)? as u16).to_le_bytes()),
+    }
+    Ok(())
+}
+
+fn check_range(value: i64, min: i64, max: i64) -> Result<i64, String> {
+    if (min..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("{} does not fit", value))
+    }
+}
+
+/// Evaluate an expression: terms separated by + and -, factors separated by * and /
+fn evaluate(
+    text: &str,
+    address: u16,
+    resolve: &impl Fn(&str) -> Option<i64>,
+) -> Result<i64, String> {
+    let tokens = tokenize(text)?;
+    let mut position = 0;
+    let value = evaluate_sum(&tokens, &mut position, address, resolve)?;
+    if position != tokens.len() {
+        return Err(format!("invalid expression: {}", text));
+    }
+    Ok(value)
+}
+
+fn evaluate_sum(
+    tokens: &[String],
+    position: &mut usize,
+    address: u16,
+    resolve: &impl Fn(&str) -> Option<i64>,
+) -> Result<i64, String> {
+    let mut value = evaluate_product(tokens, position, address, resolve)?;
+    while let Some(operator) = tokens.get(*position).filter(|t| *t == "+" || *t == "-") {
+        *position += 1;
+        let rhs = evaluate_product(tokens, position, address, resolve)?;
+        value = if operator == "+" {
+            value + rhs
+        } else {
+            value - rhs
+        };
+    }
+    Ok(value)
+}
+
+fn evaluate_product(
+    tokens: &[String],
+    position: &mut usize,
+    address: u16,
+    resolve: &impl Fn(&str) -> Option<i64>,
+) -> Result<i64, String> {
+    let mut value = evaluate_factor(tokens, position, address, resolve)?;
+    while let Some(operator) = tokens.get(*position).filter(|t| *t == "*" || *t == "/") {
+        *position += 1;
+        let rhs = evaluate_factor(tokens, position, address, resolve)?;
+        value = match operator.as_str() {
+            "*" => value * rhs,
+            _ if rhs == 0 => return Err("division by zero".to_string()),
+            _ => value / rhs,
+        };
+    }
+    Ok(value)
+}
+
+fn evaluate_factor(
+    tokens: &[String],
+    position: &mut usize,
+    address: u16,
+    resolve: &impl Fn(&str) -> Option<i64>,
+) -> Result<i64, String> {
+    let token = tokens
+        .get(*position)
+        .ok_or_else(|| "missing value".to_string())?;
+    *position += 1;
+
+    match token.as_str() {
+        "-" => Ok(-evaluate_factor(tokens, position, address, resolve)?),
+        "+" => evaluate_factor(tokens, position, address, resolve),
+        "@" => Ok(address as i64),
+        _ => parse_number(token)
+            .or_else(|| resolve(token))
+            .ok_or_else(|| format!("unknown name {}", token)),
+    }
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let lower = text.to_lowercase();
+    if let Some(hex) = lower.strip_prefix('$').or_else(|| lower.strip_prefix("0x")) {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = lower.strip_prefix('%').or_else(|| lower.strip_prefix("0b")) {
+        i64::from_str_radix(binary, 2).ok()
+    } else if lower.starts_with(|c: char| c.is_ascii_digit()) {
+        lower.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if "+-*/@".contains(c) {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if c.is_ascii_alphanumeric() || "$%_.".contains(c) {
+            let mut token = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || "$%_.".contains(**c))
+            {
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        } else {
+            return Err(format!("unexpected '{}' in {}", c, text));
+        }
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,147 @@
+use super::{Bus, Model};
+
+/// Number of frames the logo scrolls for, SCY goes from this value down to 0
+const SCROLL_FRAMES: u16 = 0x64;
+/// Frames the logo stays still after the scroll, before the cartridge is started
+const PAUSE_FRAMES: u16 = 0x20;
+/// (frame, NR13 value) for the two notes of the startup chime
+const CHIME: [(u16, u8); 2] = [(0x62, 0x83), (0x64, 0xC1)];
+/// Logo every licensed cartridge carries at 0x0104-0x0133, compared by the boot ROM
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BootState {
+    /// Waiting for the first step, the cartridge may not be loaded yet
+    NotStarted,
+    /// Scrolling the logo down, then pausing
+    Animating,
+    /// The logo or the header checksum did not match, the real hardware locks up here as well
+    LockedUp,
+    /// Ready to jump to 0x0100
+    Finished,
+}
+
+/// High-level replacement for the boot ROM
+///
+/// Draws the cartridge's logo, scrolls it down and plays the chime on the same frames as the DMG boot ROM
+/// (one animation step per frame), then verifies the logo and the header checksum.
+/// The CPU is not running while this happens; once finished, the hardware is handed over in the model's post-boot state.
+pub struct BootSequence {
+    pub state: BootState,
+    /// Frames since the logo was first displayed
+    frame: u16,
+    last_ly: u8,
+    /// Cartridge header (0x0100-0x014F), read from the cartridge when the sequence starts
+    pub header: Vec<u8>,
+}
+
+impl BootSequence {
+    pub fn new() -> Self {
+        BootSequence {
+            state: BootState::NotStarted,
+            frame: 0,
+            last_ly: 0,
+            header: Vec::new(),
+        }
+    }
+
+    /// Advance the boot sequence, should be called after the rest of the hardware has been ticked
+    ///
+    /// Returns whether the sequence has finished and the cartridge can be started
+    pub fn tick(&mut self, bus: &mut Bus, model: Model) -> bool {
+        match self.state {
+            BootState::NotStarted => self.start(bus, model),
+            BootState::Animating => {
+                // One animation step at the start of every V-Blank
+                let ly = bus.ppu.ly;
+                if ly == 144 && self.last_ly != 144 {
+                    self.next_frame(bus, model);
+                }
+                self.last_ly = ly;
+            }
+            BootState::LockedUp | BootState::Finished => (),
+        }
+
+        self.state == BootState::Finished
+    }
+
+    /// Set up the audio, palette and logo, then turn on the LCD
+    fn start(&mut self, bus: &mut Bus, model: Model) {
+        self.header = (0x0100..=0x014F)
+            .map(|address| bus.read_byte_unwatched(address))
+            .collect();
+
+        // Audio - enable channel 1 for the chime
+        bus.write_byte_unwatched(0xFF26, 0x80); // NR52
+        bus.write_byte_unwatched(0xFF11, 0x80); // NR11
+        bus.write_byte_unwatched(0xFF12, 0xF3); // NR12
+        bus.write_byte_unwatched(0xFF25, 0xF3); // NR51
+        bus.write_byte_unwatched(0xFF24, 0x77); // NR50
+        bus.write_byte_unwatched(0xFF47, 0xFC); // BGP
+
+        for (address, byte) in model.post_boot_vram(&self.header) {
+            bus.write_byte_unwatched(address, byte);
+        }
+
+        if model.has_logo_animation() {
+            bus.write_byte_unwatched(0xFF42, SCROLL_FRAMES as u8); // SCY
+            bus.write_byte_unwatched(0xFF40, 0x91); // LCDC
+            self.last_ly = bus.ppu.ly;
+            self.state = BootState::Animating;
+        } else {
+            self.finish(model);
+        }
+    }
+
+    fn next_frame(&mut self, bus: &mut Bus, model: Model) {
+        self.frame += 1;
+
+        for (frame, frequency) in CHIME {
+            if self.frame == frame {
+                bus.write_byte_unwatched(0xFF13, frequency); // NR13
+                bus.write_byte_unwatched(0xFF14, 0x87); // NR14 - Trigger
+            }
+        }
+
+        if self.frame <= SCROLL_FRAMES {
+            let scy = (SCROLL_FRAMES - self.frame) as u8;
+            bus.write_byte_unwatched(0xFF42, scy); // SCY
+        }
+
+        if self.frame == SCROLL_FRAMES + PAUSE_FRAMES {
+            self.finish(model);
+        }
+    }
+
+    fn finish(&mut self, model: Model) {
+        self.state = if self.is_logo_valid(model) && self.is_header_checksum_valid() {
+            BootState::Finished
+        } else {
+            BootState::LockedUp
+        };
+    }
+
+    /// The logo at 0x0104-0x0133 must match, the CGB only compares its top half
+    fn is_logo_valid(&self, model: Model) -> bool {
+        let length = if model == Model::CGB {
+            NINTENDO_LOGO.len() / 2
+        } else {
+            NINTENDO_LOGO.len()
+        };
+
+        self.header[0x04..0x04 + length] == NINTENDO_LOGO[..length]
+    }
+
+    /// The checksum at 0x014D covers the header bytes 0x0134-0x014C
+    fn is_header_checksum_valid(&self) -> bool {
+        let checksum = self.header[0x34..=0x4C]
+            .iter()
+            .fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1));
+
+        checksum == self.header[0x4D]
+    }
+}
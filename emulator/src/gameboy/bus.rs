@@ -1,17 +1,67 @@
 use super::{
     cartridge::{MBC, MBC0},
-    interrupt, Interrupt, Joypad, Memory, Timer, PPU,
+    instruction::BankedAddress,
+    interrupt, Event, Interrupt, Joypad, MemoryAccess, Model, OamBugAccess, Scheduler, Sgb, Timer,
+    Watchpoints, PPU,
 };
 
 const BOOT_ROM_SIZE: u16 = 0x100;
+/// T-cycles per serial bit with the internal clock (8192 Hz)
+const SERIAL_BIT_CYCLES: u64 = 512;
+/// T-cycles per second, the cartridge's real time clock ticks at this rate
+const RTC_TICK_CYCLES: u64 = 4194304;
+
+/// (unused bits, writable bits) of every IO register (0xFF00-0xFF7F)
+///
+/// Unused bits always read as 1. Registers that are not mapped read 0xFF and ignore writes.
+/// Registers handled by a component (joypad, timer, PPU) apply their own write rules, the writable bits
+/// are only used for registers the bus simply stores (serial, audio).
+fn io_register_masks(address: u16) -> (u8, u8) {
+    match address {
+        0xFF00 => (0xC0, 0x30),          // P1/JOYP
+        0xFF01 => (0x00, 0xFF),          // SB
+        0xFF02 => (0x7E, 0x81),          // SC
+        0xFF04..=0xFF06 => (0x00, 0xFF), // DIV, TIMA, TMA
+        0xFF07 => (0xF8, 0x07),          // TAC
+        0xFF0F => (0xE0, 0x1F),          // IF
+        // Audio - write-only registers (e.g. the frequency low bytes) read as 0xFF
+        0xFF10 => (0x80, 0x7F),          // NR10
+        0xFF11 | 0xFF16 => (0x3F, 0xFF), // NR11, NR21 - length is write-only
+        0xFF12 | 0xFF17 | 0xFF21 | 0xFF22 | 0xFF24 | 0xFF25 => (0x00, 0xFF), // NR12, NR22, NR42, NR43, NR50, NR51
+        0xFF13 | 0xFF18 | 0xFF1B | 0xFF1D | 0xFF20 => (0xFF, 0xFF), // NR13, NR23, NR31, NR33, NR41
+        0xFF14 | 0xFF19 | 0xFF1E | 0xFF23 => (0xBF, 0xFF), // NR14, NR24, NR34, NR44 - only the length enable reads back
+        0xFF1A => (0x7F, 0x80),                            // NR30
+        0xFF1C => (0x9F, 0x60),                            // NR32
+        0xFF26 => (0x70, 0x80), // NR52 - the channel status bits are read-only
+        0xFF30..=0xFF3F => (0x00, 0xFF), // Wave RAM
+        0xFF40 => (0x00, 0xFF), // LCDC
+        0xFF41 => (0x80, 0x78), // STAT
+        0xFF42..=0xFF43 => (0x00, 0xFF), // SCY, SCX
+        0xFF44 => (0x00, 0x00), // LY
+        0xFF45..=0xFF4B => (0x00, 0xFF), // LYC, DMA, BGP, OBP0, OBP1, WY, WX
+        0xFF4D => (0x7E, 0x01), // KEY1 - CGB only, reads 0xFF on the other models
+        _ => (0xFF, 0x00),
+    }
+}
 
 pub struct Bus {
-    ram: Memory,
     pub ppu: PPU,
     pub timer: Timer,
     pub serial_buffer: Vec<char>,
     pub mbc: Box<dyn MBC>,
     pub joypad: Joypad,
+    /// Keeps time, and when the timer, PPU, serial port, DMA and RTC next need to run
+    pub scheduler: Scheduler,
+    /// Time the PPU and timer last caught up to
+    ppu_synced_at: u64,
+    timer_synced_at: u64,
+    /// Next byte (0x00-0x9F) to be copied by the OAM DMA transfer in progress
+    dma_index: Option<u8>,
+    /// Bits left to shift in the serial transfer in progress, and the byte that is being sent
+    serial_bits_left: u8,
+    serial_out: u8,
+    /// Super Game Boy functions, only enabled for SGB models running a game that supports them
+    pub sgb: Option<Sgb>,
     is_boot_rom_mapped: bool, // Used to determine r/w to the range 0x000-0x0100
     boot_rom: [u8; BOOT_ROM_SIZE as usize],
     // Memory specifically allocated for the Boot ROM
@@ -19,32 +69,110 @@ pub struct Bus {
     wram: [u8; 0x8000], // Work RAM
     hram: [u8; 0x7F],   // High RAM
     serial: [u8; 2],
+    /// Audio registers and wave RAM (0xFF10-0xFF3F), only stored as there is no APU yet
+    audio: [u8; 0x30],
     /// IF_REG
     interrupt_flags: u8,
     interrupts_enabled: u8,
+    model: Model,
+    /// STOP mode: only the joypad keeps running, the CPU waits for a key press
+    pub is_stopped: bool,
+    /// CGB speed switch requested through KEY1 (0xFF4D), performed by the next STOP
+    is_speed_switch_armed: bool,
+    /// CGB double speed mode: the CPU and timer run twice as fast, the PPU does not
+    pub is_double_speed: bool,
+    /// Value returned when reading LY (0xFF44) instead of the PPU's, when set
+    ///
+    /// Gameboy Doctor reference logs are produced with LY stubbed to 0x90.
+    pub ly_override: Option<u8>,
+    /// Emulate the DMG OAM corruption bug, enabled by default on the models that have it
+    pub is_oam_bug_enabled: bool,
+    /// Checked on every read and write from the CPU
+    pub watchpoints: Watchpoints,
 }
 
 impl Bus {
-    pub fn new() -> Self {
+    pub fn new(model: Model) -> Self {
+        let mut ppu = PPU::new();
+        ppu.has_stat_write_bug = model != Model::CGB;
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule_in(Event::RtcTick, RTC_TICK_CYCLES);
+
         Bus {
-            ram: Memory::new(),
-            ppu: PPU::new(),
+            ppu,
             timer: Timer::new(),
             serial_buffer: Vec::new(),
             mbc: Box::new(MBC0::new()),
             joypad: Joypad::new(),
+            scheduler,
+            ppu_synced_at: 0,
+            timer_synced_at: 0,
+            dma_index: None,
+            serial_bits_left: 0,
+            serial_out: 0,
+            sgb: None,
             is_boot_rom_mapped: false,
             boot_rom: [0; BOOT_ROM_SIZE as usize],
             dma_address_upper: 0,
             wram: [0; 0x8000],
             hram: [0; 0x7F],
             serial: [0xFF, 0], //[0; 2],
+            audio: [0; 0x30],
             interrupt_flags: 0,
             interrupts_enabled: 0,
+            model,
+            is_stopped: false,
+            is_speed_switch_armed: false,
+            is_double_speed: false,
+            ly_override: None,
+            is_oam_bug_enabled: model.has_oam_bug(),
+            watchpoints: Watchpoints::new(),
         }
     }
 
     pub fn ram_read_byte(&self, address: u16) -> u8 {
+        let byte = self.read_byte_unwatched(address);
+        if !self.watchpoints.is_empty() {
+            self.watch_read(address, byte);
+        }
+        byte
+    }
+
+    pub fn ram_write_byte(&mut self, address: u16, byte: u8) {
+        if !self.watchpoints.is_empty() {
+            self.watch_write(address, byte);
+        }
+        self.write_byte_unwatched(address, byte);
+    }
+
+    // Kept out of line, the accesses stay fast while there are no watchpoints
+    #[cold]
+    fn watch_read(&self, address: u16, byte: u8) {
+        self.watchpoints
+            .check_read(address, self.bank_at(address), byte);
+    }
+
+    #[cold]
+    fn watch_write(&self, address: u16, byte: u8) {
+        let old_value = self
+            .watchpoints
+            .needs_old_value(address)
+            .then(|| self.read_byte_unwatched(address));
+        self.watchpoints
+            .check_write(address, self.bank_at(address), byte, old_value);
+    }
+
+    /// ROM or external RAM bank mapped at an address, for watchpoints
+    fn bank_at(&self, address: u16) -> u16 {
+        match address {
+            0x4000..=0x7FFF => self.mbc.rom_bank(),
+            0xA000..=0xBFFF => self.mbc.ram_bank(),
+            _ => 0,
+        }
+    }
+
+    /// Same as `ram_read_byte`, for accesses that are not made by the program (see `MemoryAccess`)
+    pub fn read_byte_unwatched(&self, address: u16) -> u8 {
         match address {
             0x0000..=0x7FFF => {
                 if self.is_boot_rom_mapped && address < BOOT_ROM_SIZE {
@@ -62,34 +190,47 @@ impl Bus {
             0xFE00..=0xFE9F => self
                 .ppu
                 .read_byte((address - 0x5200) as usize, address as usize), // PPU - OAM
-            0xFEA0..=0xFEFF => 0x00,                                   // TODO: Not Usable
+            // Not usable - reads 0x00 on DMG, unless OAM is locked by the PPU
+            0xFEA0..=0xFEFF => {
+                if self.ppu.is_oam_accessible() {
+                    0x00
+                } else {
+                    0xFF
+                }
+            }
             0xFF00..=0xFF7F => {
                 // IO Registers
-                match address {
-                    0xFF00 => self.joypad.read_byte(), // Joypad Input
-                    0xFF01..=0xFF02 => self.serial[(address & 0x1) as usize], // SERIAL
-                    0xFF04..=0xFF07 => self.timer.read_byte((address - 0xFF04) as usize), // Timer
-                    0xFF0F => self.interrupt_flags | 0b1110_0000,
-                    0xFF10..=0xFF3F => 0x0, // TODO: Audio & Audio Wave
-                    0xFF40..=0xFF45 => self
-                        .ppu
-                        .read_byte((address - 0xFF40) as usize, address as usize), // PPU
-                    0xFF46 => self.dma_address_upper,
-                    0xFF47..=0xFF4B => self
-                        .ppu
-                        .read_byte((address - 0xFF40) as usize, address as usize), // PPU
-                    0xFF4F => 0xFF, // TODO: VRAM Bank Select - CBG Only, Need Not Implement?
-                    0xFF50 => self.is_boot_rom_mapped as u8,
-                    _ => self.ram.read_byte(address), // 0x0
-                }
+                let (unused_bits, _) = io_register_masks(address);
+                self.read_io_register(address) | unused_bits
             }
             0xFF80..=0xFFFE => self.hram[(address - 0xFF80) as usize],
             0xFFFF => self.interrupts_enabled, // TODO: Access CPU for value?
-            _ => self.ram.read_byte(address),  // 0x0
         }
     }
 
-    pub fn ram_write_byte(&mut self, address: u16, byte: u8) {
+    /// Raw value of an IO register, before the unused bits are set
+    fn read_io_register(&self, address: u16) -> u8 {
+        match address {
+            0xFF00 => self.joypad.read_byte(), // Joypad Input
+            0xFF01..=0xFF02 => self.serial[(address & 0x1) as usize], // SERIAL
+            0xFF04..=0xFF07 => self.synced_timer().read_byte((address - 0xFF04) as usize), // Timer
+            0xFF0F => self.interrupt_flags,
+            0xFF44 => self.ly_override.unwrap_or(self.ppu.get_ly()),
+            0xFF10..=0xFF3F => self.audio[(address - 0xFF10) as usize], // TODO: Audio & Audio Wave (registers are only stored)
+            0xFF40..=0xFF45 => self
+                .ppu
+                .read_byte((address - 0xFF40) as usize, address as usize), // PPU
+            0xFF46 => self.dma_address_upper,
+            0xFF47..=0xFF4B => self
+                .ppu
+                .read_byte((address - 0xFF40) as usize, address as usize), // PPU
+            0xFF4D => self.read_key1(),
+            _ => 0xFF, // Not mapped (including the CGB-only registers and the boot ROM switch)
+        }
+    }
+
+    /// Same as `ram_write_byte`, for accesses that are not made by the program (see `MemoryAccess`)
+    pub fn write_byte_unwatched(&mut self, address: u16, byte: u8) {
         match address {
             0x0000..=0x7FFF => self.mbc.write_byte(address, byte),
 
@@ -109,48 +250,151 @@ impl Bus {
             0xFF00..=0xFF7F => {
                 // IO Registers
                 match address {
-                    0xFF00 => self.joypad.write_byte(byte), // Joypad Input
-                    0xFF01..=0xFF02 => self.serial[(address & 0x1) as usize] = byte, // SERIAL
-                    0xFF04..=0xFF07 => self.timer.write_byte((address - 0xFF04) as usize, byte), // Timer and Divider Registers
+                    0xFF00 => {
+                        // Joypad Input
+                        self.joypad.write_byte(byte);
+                        if let Some(packet) = self.joypad.sgb_packet.take() {
+                            self.receive_sgb_packet(&packet);
+                        }
+                    }
+                    0xFF01..=0xFF02 => {
+                        let (_, writable_bits) = io_register_masks(address);
+                        self.serial[(address & 0x1) as usize] = byte & writable_bits;
+                        // SERIAL
+                        if address == 0xFF02 {
+                            self.start_serial_transfer();
+                        }
+                    }
+                    0xFF04..=0xFF07 => {
+                        // Timer and Divider Registers
+                        self.sync_timer();
+                        self.timer.write_byte((address - 0xFF04) as usize, byte);
+                        self.sync_timer();
+                    }
                     0xFF0F => self.interrupt_flags = byte,
-                    0xFF10..=0xFF3F => (), // TODO: Audio
-                    0xFF40..=0xFF45 => {
+                    0xFF10..=0xFF3F => {
+                        // TODO: Audio (registers are only stored)
+                        let (_, writable_bits) = io_register_masks(address);
+                        let register = &mut self.audio[(address - 0xFF10) as usize];
+                        *register = (*register & !writable_bits) | (byte & writable_bits);
+                    }
+                    0xFF40..=0xFF45 | 0xFF47..=0xFF4B => {
+                        // PPU - LCDC writes can turn the LCD on/off, STAT/LYC writes can raise the STAT interrupt right away
+                        self.sync_ppu();
                         self.ppu
-                            .write_byte((address - 0xFF40) as usize, address as usize, byte)
+                            .write_byte((address - 0xFF40) as usize, address as usize, byte);
+                        self.sync_ppu();
                     }
                     0xFF46 => self.dma_transfer(byte),
-                    0xFF47..=0xFF4B => {
-                        self.ppu
-                            .write_byte((address - 0xFF40) as usize, address as usize, byte)
+                    0xFF4D => {
+                        if self.model == Model::CGB {
+                            self.is_speed_switch_armed = byte & 0x1 != 0;
+                        }
                     }
                     0xFF4F => (), // TODO: VRAM Bank Select - CBG Only, Need Not Implement?
                     0xFF50 => self.is_boot_rom_mapped = false,
-                    _ => self.ram.write_byte(address, byte), // ()
+                    _ => (), // Not mapped
                 }
             }
             0xFF80..=0xFFFE => self.hram[(address - 0xFF80) as usize] = byte,
             0xFFFF => self.interrupts_enabled = byte,
-            _ => self.ram.write_byte(address, byte), // ()
         }
     }
 
+    /// Start an OAM DMA transfer, the first byte is copied after a one M-cycle setup delay, then one byte per M-cycle
     fn dma_transfer(&mut self, address: u8) {
         self.dma_address_upper = address;
+        self.dma_index = Some(0);
+        self.scheduler.schedule_in(Event::DmaByte, 8);
+    }
+
+    fn dma_byte(&mut self) {
+        if let Some(index) = self.dma_index {
+            let real_addr = ((self.dma_address_upper as u16) << 8) + index as u16;
+            let value = self.peek_byte(real_addr);
+            self.ppu.poke_byte(0xFE00 + index as usize, value);
+
+            self.dma_index = if index < 0x9F {
+                self.scheduler.schedule_in(Event::DmaByte, 4);
+                Some(index + 1)
+            } else {
+                None
+            };
+        }
+    }
+
+    /// SC was written: transfer bit 7 and the internal clock (bit 0) start a transfer, clearing bit 7 stops it
+    ///
+    /// Transfers using the external clock never complete, since there is no link cable partner.
+    fn start_serial_transfer(&mut self) {
+        if self.serial[1] & 0x81 == 0x81 {
+            self.serial_bits_left = 8;
+            self.serial_out = self.serial[0];
+            self.scheduler
+                .schedule_in(Event::SerialBit, SERIAL_BIT_CYCLES);
+        } else if self.serial[1] & 0x80 == 0 {
+            self.serial_bits_left = 0;
+            self.scheduler.cancel(Event::SerialBit);
+        }
+    }
+
+    /// Shift out a bit of SB, without a link cable partner 1s are shifted in
+    fn serial_bit(&mut self) {
+        self.serial[0] = (self.serial[0] << 1) | 1;
+        self.serial_bits_left -= 1;
+
+        if self.serial_bits_left > 0 {
+            self.scheduler
+                .schedule_in(Event::SerialBit, SERIAL_BIT_CYCLES);
+        } else {
+            // Transfer complete, the sent byte is captured as serial output
+            self.serial[1] &= 0x7F;
+            self.serial_buffer.push(char::from(self.serial_out));
+            self.trigger_interrupt(Interrupt::Serial);
+        }
+    }
+
+    /// Read a byte without any side effects, VRAM and OAM can be read regardless of the PPU mode
+    ///
+    /// Meant for debugging tools (memory/VRAM viewers, disassembly) that must not disturb the emulation.
+    pub fn peek_byte(&self, address: u16) -> u8 {
+        match address {
+            0x8000..=0x9FFF | 0xFE00..=0xFE9F => self.ppu.peek_byte(address as usize),
+            _ => self.read_byte_unwatched(address),
+        }
+    }
+
+    /// Same as `peek_byte`, but reads the given ROM bank instead of the mapped one in 0x4000-0x7FFF
+    pub fn peek_banked(&self, address: BankedAddress) -> u8 {
+        match address.address {
+            0x0000..=0x7FFF if !self.is_boot_rom_mapped || address.address >= BOOT_ROM_SIZE => {
+                self.mbc.peek_rom(address.bank, address.address)
+            }
+            _ => self.peek_byte(address.address),
+        }
+    }
 
-        let real_addr = (address as u16) << 8;
-        for index in 0..0xA0 {
-            let value = self.ram_read_byte(real_addr + index);
-            self.ram_write_byte(0xFE00 + index, value);
+    /// Write a byte for debugging tools, ROM addresses patch the loaded ROM image in the given bank
+    pub fn poke_banked(&mut self, address: BankedAddress, byte: u8) {
+        match address.address {
+            0x0000..=0x7FFF => self.mbc.patch_rom(address.bank, address.address, byte),
+            _ => self.write_byte_unwatched(address.address, byte),
         }
     }
 
+    /// ROM bank currently mapped at 0x4000-0x7FFF
+    pub fn rom_bank(&self) -> u16 {
+        self.mbc.rom_bank()
+    }
+
     pub fn ram_read_word(&self, address: u16) -> u16 {
-        ((self.ram_read_byte(address + 1) as u16) << 8) | (self.ram_read_byte(address) as u16)
+        ((self.ram_read_byte(address.wrapping_add(1)) as u16) << 8)
+            | (self.ram_read_byte(address) as u16)
     }
 
     pub fn ram_write_word(&mut self, address: u16, word: u16) {
         self.ram_write_byte(address, (word & 0xFF) as u8);
-        self.ram_write_byte(address + 1, (word >> 8) as u8);
+        self.ram_write_byte(address.wrapping_add(1), (word >> 8) as u8);
     }
 
     /// Load the provided ROM into memory
@@ -170,52 +414,216 @@ impl Bus {
         self.is_boot_rom_mapped = true;
     }
 
+    /// KEY1 - CGB speed switch: bit 7 is the current speed, bit 0 a pending switch
+    fn read_key1(&self) -> u8 {
+        if self.model == Model::CGB {
+            ((self.is_double_speed as u8) << 7) | (self.is_speed_switch_armed as u8)
+        } else {
+            0xFF
+        }
+    }
+
+    pub fn is_boot_rom_mapped(&self) -> bool {
+        self.is_boot_rom_mapped
+    }
+
+    /// Put the hardware into the state the supplied model's boot ROM leaves it in
+    ///
+    /// `header` is the cartridge header (0x0100-0x014F), used to draw the logo into VRAM.
+    pub fn load_post_boot_state(&mut self, model: Model, header: &[u8]) {
+        for (address, byte) in model.post_boot_io() {
+            match address {
+                0xFF46 => self.dma_address_upper = byte, // Don't start a transfer
+                0xFF40..=0xFF4B => self.ppu.poke_byte(address as usize, byte),
+                // Including the read-only audio status bits
+                0xFF10..=0xFF3F => self.audio[(address - 0xFF10) as usize] = byte,
+                _ => self.write_byte_unwatched(address, byte),
+            }
+        }
+        for (address, byte) in model.post_boot_vram(header) {
+            self.ppu.poke_byte(address as usize, byte);
+        }
+
+        self.ppu.set_vblank_line(model.post_boot_ly());
+        self.ppu.take_interrupt_requests();
+        self.timer
+            .set_system_counter(model.post_boot_system_counter());
+
+        // Both were set up directly, they continue from the current time
+        self.sync_ppu();
+        self.sync_timer();
+    }
+
+    /// Turn the Super Game Boy functions on or off, from a fresh state
+    pub fn set_sgb_enabled(&mut self, is_enabled: bool) {
+        self.sgb = if is_enabled { Some(Sgb::new()) } else { None };
+        self.joypad.is_sgb_enabled = is_enabled;
+        self.joypad.set_player_count(1);
+    }
+
+    /// Forward an SGB packet received by the joypad
+    fn receive_sgb_packet(&mut self, packet: &[u8; 16]) {
+        if let Some(sgb) = self.sgb.as_mut() {
+            sgb.receive_packet(packet);
+            if let Some(player_count) = sgb.multiplayer_request.take() {
+                self.joypad.set_player_count(player_count);
+            }
+        }
+    }
+
     /// Request an interrupt by writing its flag to the IF Reg in memory (0xFF0F)
     pub fn trigger_interrupt(&mut self, interrupt: Interrupt) {
         let interrupt_bit = interrupt.get_flag_mask();
-        let ifr = self.ram_read_byte(0xFF0F); // IF_REG
+        let ifr = self.read_byte_unwatched(0xFF0F); // IF_REG
 
-        self.ram_write_byte(0xFF0F, ifr | interrupt_bit);
+        self.write_byte_unwatched(0xFF0F, ifr | interrupt_bit);
     }
 
-    /// Tick all appropriate components and handle their interrupts.
+    /// Advance time, running the components whose events are due and handling their interrupts.
     ///
-    /// Components: Timer, PPU, Joypad
+    /// Components: Timer, PPU, Serial, DMA, RTC, Joypad
     pub fn tick(&mut self, cycles: u8) {
-        // Timer
-        self.timer.tick(cycles);
-        self.timer.raise_interrupt = match self.timer.raise_interrupt {
-            None => None,
-            Some(x) => {
-                self.trigger_interrupt(x);
-                None
+        // Time stands still in STOP mode, except for the joypad and the cartridge's RTC
+        if !self.is_stopped {
+            self.scheduler.advance(cycles as u64);
+            while let Some(event) = self.scheduler.pop_due() {
+                match event {
+                    Event::Ppu => self.sync_ppu(),
+                    Event::TimerInterrupt => self.sync_timer(),
+                    Event::SerialBit => self.serial_bit(),
+                    Event::DmaByte => self.dma_byte(),
+                    Event::RtcTick => self.rtc_tick(),
+                }
             }
-        };
+        } else {
+            self.scheduler.bring_forward(Event::RtcTick, cycles as u64);
+            if self.scheduler.pop_due().is_some() {
+                // Nothing else can be due while time is frozen
+                self.rtc_tick();
+            }
+        }
 
-        // PPU
-        let ppu_interrupts = self.ppu.tick(cycles as u16);
-        for interrupt in ppu_interrupts {
+        // Joypad
+        if let Some(interrupt) = self.joypad.raise_interrupt.take() {
             self.trigger_interrupt(interrupt);
         }
+    }
 
-        // Joypad
-        self.joypad.read_byte();
-        self.joypad.raise_interrupt = match self.joypad.raise_interrupt {
-            None => None,
-            Some(x) => {
-                self.trigger_interrupt(x);
-                None
-            }
+    fn rtc_tick(&mut self) {
+        self.mbc.tick_rtc();
+        // The RTC runs on its own crystal, twice as many CPU cycles make a second in double speed mode
+        let cycles = if self.is_double_speed {
+            RTC_TICK_CYCLES * 2
+        } else {
+            RTC_TICK_CYCLES
         };
+        self.scheduler.schedule_in(Event::RtcTick, cycles);
     }
 
-    pub fn debug(&mut self) {
-        // Build
-        if self.ram_read_byte(0xFF02) == 0x81 {
-            let c_byte = self.ram_read_byte(0xFF01);
-            let c = char::from(c_byte);
-            self.serial_buffer.push(c);
-            self.ram_write_byte(0xFF02, 0);
+    /// Catch the PPU up to the current time, forward its interrupts and schedule its next event
+    fn sync_ppu(&mut self) {
+        let elapsed = self.scheduler.now() - self.ppu_synced_at;
+        self.ppu_synced_at = self.scheduler.now();
+
+        // The PPU is not affected by double speed mode
+        let dots = if self.is_double_speed {
+            elapsed / 2
+        } else {
+            elapsed
+        };
+        self.ppu.tick(dots);
+
+        let interrupt_requests = self.ppu.take_interrupt_requests();
+        if interrupt_requests & Interrupt::VBlank.get_flag_mask() != 0 {
+            if let Some(sgb) = self.sgb.as_mut() {
+                sgb.vblank(self.ppu.get_display());
+            }
+        }
+        self.interrupt_flags |= interrupt_requests;
+
+        match self.ppu.dots_until_next_event() {
+            Some(dots) => {
+                let cycles = if self.is_double_speed { dots * 2 } else { dots };
+                self.scheduler.schedule_in(Event::Ppu, cycles);
+            }
+            None => self.scheduler.cancel(Event::Ppu),
         }
     }
+
+    /// Catch the timer up to the current time, forward its interrupt and schedule the next one
+    fn sync_timer(&mut self) {
+        self.timer
+            .advance(self.scheduler.now() - self.timer_synced_at);
+        self.timer_synced_at = self.scheduler.now();
+
+        if let Some(interrupt) = self.timer.raise_interrupt.take() {
+            self.trigger_interrupt(interrupt);
+        }
+
+        match self.timer.cycles_until_interrupt() {
+            Some(cycles) => self.scheduler.schedule_in(Event::TimerInterrupt, cycles),
+            None => self.scheduler.cancel(Event::TimerInterrupt),
+        }
+    }
+
+    /// The timer as of the current time, for reading its registers without having to mutate it
+    fn synced_timer(&self) -> Timer {
+        let mut timer = self.timer.clone();
+        timer.advance(self.scheduler.now() - self.timer_synced_at);
+        timer
+    }
+}
+
+impl MemoryAccess for Bus {
+    fn ram_read_byte(&self, address: u16) -> u8 {
+        Bus::ram_read_byte(self, address)
+    }
+
+    fn ram_write_byte(&mut self, address: u16, byte: u8) {
+        Bus::ram_write_byte(self, address, byte)
+    }
+
+    fn read_byte_unwatched(&self, address: u16) -> u8 {
+        Bus::read_byte_unwatched(self, address)
+    }
+
+    fn write_byte_unwatched(&mut self, address: u16, byte: u8) {
+        Bus::write_byte_unwatched(self, address, byte)
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        Bus::tick(self, cycles)
+    }
+
+    fn oam_bug(&mut self, address: u16, access: OamBugAccess) {
+        if self.is_oam_bug_enabled && (0xFE00..=0xFEFF).contains(&address) {
+            // The corrupted row depends on how far into the OAM scan the PPU is
+            self.sync_ppu();
+            self.ppu.corrupt_oam(access);
+        }
+    }
+
+    fn speed_switch(&mut self) -> bool {
+        if !self.is_speed_switch_armed {
+            return false;
+        }
+
+        self.is_speed_switch_armed = false;
+        // The PPU's next event is scheduled in CPU cycles, which change length
+        self.sync_ppu();
+        self.is_double_speed = !self.is_double_speed;
+        self.sync_ppu();
+        true
+    }
+
+    fn set_stop_mode(&mut self, is_stopped: bool) {
+        self.is_stopped = is_stopped;
+        if is_stopped {
+            self.ppu.blank_screen();
+        }
+    }
+
+    fn is_stop_mode(&self) -> bool {
+        self.is_stopped
+    }
 }
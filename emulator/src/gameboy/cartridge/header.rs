@@ -3,14 +3,16 @@ use std::fmt;
 const CARTRIDGE_HEADER_SIZE: usize = 0x014F - 0x0100;
 const HEADER_START: usize = 0x0100;
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct CartridgeHeader {
     pub title: String,
     pub cartridge_type_code: u8,
     pub rom_size_code: u8,
     pub ram_size_code: u8,
+    /// The game supports SGB functions (0x0146 is 0x03 and the old licensee code is 0x33)
+    pub is_sgb_supported: bool,
 
-    /// ROM Size in KiB (8 => 8KiB)
+    /// ROM Size in KiB (8 => 8KiB), 0 if the ROM size code is not recognized
     pub rom_size: i32,
     /// Amount of ROM banks needed
     pub rom_bank_count: i32,
@@ -22,16 +24,21 @@ pub struct CartridgeHeader {
 }
 
 impl CartridgeHeader {
+    /// Parse the cartridge header (0x0100-0x014F)
+    ///
+    /// Missing bytes (a truncated header) are read as 0x00.
     pub fn new(header_bytes: &[u8]) -> Self {
         // [u8; CARTRIDGE_HEADER_SIZE]
-        let cartridge_code: u8 = header_bytes[0x0147 - HEADER_START];
-        let rom_code: u8 = header_bytes[0x0148 - HEADER_START];
-        let ram_code: u8 = header_bytes[0x0149 - HEADER_START];
+        let header_byte =
+            |addr: usize| -> u8 { header_bytes.get(addr - HEADER_START).copied().unwrap_or(0) };
+        let cartridge_code: u8 = header_byte(0x0147);
+        let rom_code: u8 = header_byte(0x0148);
+        let ram_code: u8 = header_byte(0x0149);
+        let is_sgb_supported = header_byte(0x0146) == 0x03 && header_byte(0x014B) == 0x33;
 
         let mut title: Vec<char> = Vec::with_capacity(16);
-        for index in 0..=(0x0143 - 0x0134) {
-            let c_byte = header_bytes[0x0134 - HEADER_START + index];
-            let c = char::from(c_byte);
+        for addr in 0x0134..=0x0143 {
+            let c = char::from(header_byte(addr));
             if c == '\0' {
                 break;
             }
@@ -39,7 +46,7 @@ impl CartridgeHeader {
             title.push(c);
         }
 
-        let rom_size = (1 << rom_code as usize) * 32;
+        let rom_size = CartridgeHeader::rom_size_from_code(rom_code);
         let rom_banks = rom_size / 16;
 
         let (ram_size, ram_banks) = match ram_code {
@@ -57,6 +64,7 @@ impl CartridgeHeader {
             cartridge_type_code: cartridge_code,
             rom_size_code: rom_code,
             ram_size_code: ram_code,
+            is_sgb_supported,
 
             rom_size: rom_size,
             rom_bank_count: rom_banks,
@@ -65,16 +73,25 @@ impl CartridgeHeader {
             ram_bank_count: ram_banks,
         }
     }
+
+    /// ROM size in KiB for the supplied ROM size code (0x0148), 0 if unknown
+    fn rom_size_from_code(rom_code: u8) -> i32 {
+        match rom_code {
+            0x00..=0x08 => 32 << rom_code, // 32KiB-8MiB
+            0x52 => 1152,                  // 72 banks
+            0x53 => 1280,                  // 80 banks
+            0x54 => 1536,                  // 96 banks
+            _ => 0,
+        }
+    }
 }
 
 impl fmt::Debug for CartridgeHeader {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let rom_size = (1 << self.rom_size_code as usize) * 32;
-        let rom_banks = rom_size / 16;
         write!(
             f,
             "Cartridge Title: {:?}\nCartridge Code: {:?}\nROM Code: {:?}\n\tSize: {:?}\n\tBanks: {:?}\nRAM Code: {:?}\n\tSize:{:?}\n\tBanks:{:?}",
-            self.title, self.cartridge_type_code, self.rom_size_code, rom_size, rom_banks, self.ram_size_code, self.ram_size, self.ram_bank_count
+            self.title, self.cartridge_type_code, self.rom_size_code, self.rom_size, self.rom_bank_count, self.ram_size_code, self.ram_size, self.ram_bank_count
         )
     }
 }
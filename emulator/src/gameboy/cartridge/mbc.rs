@@ -6,4 +6,20 @@ pub trait MBC {
     fn read_byte(&self, addr: u16) -> u8;
     fn write_byte(&mut self, addr: u16, byte: u8);
     fn load_rom(&mut self, rom_data: &[u8]);
+    /// One second went by, for cartridges with a real time clock
+    fn tick_rtc(&mut self) {}
+    /// ROM bank currently mapped at 0x4000-0x7FFF
+    fn rom_bank(&self) -> u16 {
+        1
+    }
+    /// External RAM bank currently mapped at 0xA000-0xBFFF (or RTC register, for MBC3)
+    fn ram_bank(&self) -> u16 {
+        0
+    }
+    /// Read ROM without side effects, from `bank` instead of the mapped one when in 0x4000-0x7FFF
+    fn peek_rom(&self, bank: u16, addr: u16) -> u8 {
+        self.read_byte(addr)
+    }
+    /// Change the loaded ROM image, in `bank` when in 0x4000-0x7FFF (to patch code from the debugger)
+    fn patch_rom(&mut self, bank: u16, addr: u16, byte: u8);
 }
@@ -35,8 +35,11 @@ impl MBC for MBC0 {
     }
 
     fn load_rom(&mut self, rom_data: &[u8]) {
-        for i in 0..rom_data.len() {
-            self.rom[i] = rom_data[i];
-        }
+        let length = rom_data.len().min(self.rom.len());
+        self.rom[..length].copy_from_slice(&rom_data[..length]);
+    }
+
+    fn patch_rom(&mut self, _bank: u16, addr: u16, byte: u8) {
+        self.rom[addr as usize & 0x7FFF] = byte;
     }
 }
@@ -90,8 +90,7 @@ impl MBC for MBC1 {
             0x4000..=0x7FFF => {
                 // Switchable ROM Bank
                 // ROM Bank 01-7F
-                let bank_no = (self.bank2 << 5) | self.bank1;
-                self.rom_read_byte(bank_no as usize, addr as usize - 0x4000)
+                self.rom_read_byte(self.rom_bank() as usize, addr as usize - 0x4000)
             }
             0xA000..=0xBFFF => {
                 // RAM Bank 0-3
@@ -106,6 +105,34 @@ impl MBC for MBC1 {
         }
     }
 
+    fn rom_bank(&self) -> u16 {
+        // Banks past the end of the ROM wrap around, like in `get_rom_address`
+        (((self.bank2 << 5) | self.bank1) as usize & self.rom_mask) as u16
+    }
+
+    fn ram_bank(&self) -> u16 {
+        if self.mode {
+            (self.bank2 as usize & self.ram_mask) as u16
+        } else {
+            0
+        }
+    }
+
+    fn peek_rom(&self, bank: u16, addr: u16) -> u8 {
+        match addr {
+            0x4000..=0x7FFF => self.rom_read_byte(bank as usize, addr as usize - 0x4000),
+            _ => self.read_byte(addr),
+        }
+    }
+
+    fn patch_rom(&mut self, bank: u16, addr: u16, byte: u8) {
+        match addr {
+            0x0000..=0x3FFF => self.rom_write_byte(0, addr as usize, byte),
+            0x4000..=0x7FFF => self.rom_write_byte(bank as usize, addr as usize - 0x4000, byte),
+            _ => {}
+        }
+    }
+
     fn write_byte(&mut self, addr: u16, byte: u8) {
         match addr {
             0x0000..=0x1FFF => {
@@ -155,9 +182,9 @@ impl MBC for MBC1 {
         println!("\tRAM SIZE: {:?}", self.ram.len());
         println!("\t\tMask: {:#b}", self.ram_mask);
 
-        for i in 0..rom_data.len() {
-            self.rom[i] = rom_data[i];
-        }
+        // Anything past the size declared in the header is not addressable
+        let length = rom_data.len().min(self.rom.len());
+        self.rom[..length].copy_from_slice(&rom_data[..length]);
     }
 }
 
@@ -194,7 +221,8 @@ impl MBC1 {
 
     fn get_rom_address(&self, bank_no: usize, offset: usize) -> usize {
         let real_bank_no: usize = bank_no & self.rom_mask;
-        real_bank_no * ROM_BANK_SIZE + offset
+        // Bank counts that are not a power of two (e.g. 72 banks) are mirrored
+        (real_bank_no * ROM_BANK_SIZE + offset) % self.rom.len()
     }
 
     // RAM R/W
@@ -213,8 +241,7 @@ impl MBC1 {
         } else {
             0
         }) as usize;
-        let real_offset = offset % (self.ram.len() + 1);
-
-        bank_no * RAM_BANK_SIZE + real_offset
+        // Smaller RAM chips are mirrored across the address range
+        (bank_no * RAM_BANK_SIZE + offset) % self.ram.len()
     }
 }
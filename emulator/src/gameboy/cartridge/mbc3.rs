@@ -63,6 +63,10 @@ pub struct MBC3 {
     /// > Bit 7  Day Counter Carry Bit (1=Counter Overflow)
     rtc: [u8; 5],
 
+    /// RTC registers as of the last latch (writing 0x00 then 0x01 to 0x6000-0x7FFF), which is what reads return
+    #[serde(default)]
+    latched_rtc: [u8; 5],
+
     rom_bank_count: i32,
     ram_bank_count: i32,
 
@@ -84,6 +88,7 @@ impl MBC3 {
             mode: false,
 
             rtc: [0; 5],
+            latched_rtc: [0; 5],
 
             rom_bank_count: ch.rom_bank_count,
             ram_bank_count: ch.ram_bank_count,
@@ -105,8 +110,7 @@ impl MBC for MBC3 {
             0x4000..=0x7FFF => {
                 // Switchable ROM Bank
                 // ROM Bank 01-7F
-                let bank_no = ((self.bank2 & 0b11) << 7) | self.bank1;
-                self.rom_read_byte(bank_no as usize, addr as usize - 0x4000)
+                self.rom_read_byte(self.rom_bank() as usize, addr as usize - 0x4000)
             }
             0xA000..=0xBFFF => {
                 // RAM Bank 0-3
@@ -114,7 +118,7 @@ impl MBC for MBC3 {
                     match self.bank2 {
                         0x08..=0x0C => {
                             // RTC
-                            self.rtc[(self.bank2 - 0x08) as usize]
+                            self.latched_rtc[(self.bank2 - 0x08) as usize]
                         }
                         _ => {
                             // RAM
@@ -132,6 +136,30 @@ impl MBC for MBC3 {
         }
     }
 
+    fn rom_bank(&self) -> u16 {
+        // Banks past the end of the ROM wrap around, like in `get_rom_address`
+        ((((self.bank2 & 0b11) << 7) | self.bank1) as usize & self.rom_mask) as u16
+    }
+
+    fn ram_bank(&self) -> u16 {
+        self.bank2 as u16
+    }
+
+    fn peek_rom(&self, bank: u16, addr: u16) -> u8 {
+        match addr {
+            0x4000..=0x7FFF => self.rom_read_byte(bank as usize, addr as usize - 0x4000),
+            _ => self.read_byte(addr),
+        }
+    }
+
+    fn patch_rom(&mut self, bank: u16, addr: u16, byte: u8) {
+        match addr {
+            0x0000..=0x3FFF => self.rom_write_byte(0, addr as usize, byte),
+            0x4000..=0x7FFF => self.rom_write_byte(bank as usize, addr as usize - 0x4000, byte),
+            _ => {}
+        }
+    }
+
     fn write_byte(&mut self, addr: u16, byte: u8) {
         match addr {
             0x0000..=0x1FFF => {
@@ -172,7 +200,9 @@ impl MBC for MBC3 {
                     match self.bank2 {
                         0x08..=0x0C => {
                             // RTC
-                            self.rtc[(self.bank2 - 0x08) as usize] = byte
+                            let index = (self.bank2 - 0x08) as usize;
+                            self.rtc[index] = byte;
+                            self.latched_rtc[index] = byte;
                         }
                         _ => {
                             // RAM
@@ -196,8 +226,47 @@ impl MBC for MBC3 {
         println!("\tRAM SIZE: {:?}", self.ram.len());
         println!("\t\tMask: {:#b}", self.ram_mask);
 
-        for i in 0..rom_data.len() {
-            self.rom[i] = rom_data[i];
+        // Anything past the size declared in the header is not addressable
+        let length = rom_data.len().min(self.rom.len());
+        self.rom[..length].copy_from_slice(&rom_data[..length]);
+    }
+
+    fn tick_rtc(&mut self) {
+        let [seconds, minutes, hours, days_low, days_high] = &mut self.rtc;
+
+        // Halted
+        if *days_high & 0b0100_0000 != 0 {
+            return;
+        }
+
+        // Registers only wrap around when they reach their limit, out of range values count up to the register size
+        *seconds = (*seconds + 1) & 0x3F;
+        if *seconds != 60 {
+            return;
+        }
+        *seconds = 0;
+
+        *minutes = (*minutes + 1) & 0x3F;
+        if *minutes != 60 {
+            return;
+        }
+        *minutes = 0;
+
+        *hours = (*hours + 1) & 0x1F;
+        if *hours != 24 {
+            return;
+        }
+        *hours = 0;
+
+        // 9-bit day counter, the carry bit stays set once it overflows
+        let (next_days_low, is_overflow) = days_low.overflowing_add(1);
+        *days_low = next_days_low;
+        if is_overflow {
+            if *days_high & 0x01 != 0 {
+                *days_high = (*days_high & !0x01) | 0b1000_0000;
+            } else {
+                *days_high |= 0x01;
+            }
         }
     }
 }
@@ -218,8 +287,10 @@ impl MBC3 {
         mask
     }
 
-    /// Update internal RTC
-    fn update_rtc(&mut self) {}
+    /// Latch the RTC registers, reads keep returning these values until the next latch
+    fn update_rtc(&mut self) {
+        self.latched_rtc = self.rtc;
+    }
 
     /// RAM & RTC are only accessible when RAMG's lower nibble is 0xA and there is RAM
     fn is_ram_rtc_accessible(&self) -> bool {
@@ -238,7 +309,8 @@ impl MBC3 {
 
     fn get_rom_address(&self, bank_no: usize, offset: usize) -> usize {
         let real_bank_no: usize = bank_no & self.rom_mask;
-        real_bank_no * ROM_BANK_SIZE + offset
+        // Bank counts that are not a power of two (e.g. 72 banks) are mirrored
+        (real_bank_no * ROM_BANK_SIZE + offset) % self.rom.len()
     }
 
     // RAM R/W
@@ -257,8 +329,7 @@ impl MBC3 {
         } else {
             0
         }) as usize;
-        let real_offset = offset % (self.ram.len() + 1);
-
-        bank_no * RAM_BANK_SIZE + real_offset
+        // Smaller RAM chips are mirrored across the address range
+        (bank_no * RAM_BANK_SIZE + offset) % self.ram.len()
     }
 }
@@ -1,22 +1,30 @@
-use super::Bus;
+use super::opcode::{Opcode, CB_OPCODES, OPCODES};
 use super::Flag;
+use super::MemoryAccess;
+use super::OamBugAccess;
 use super::Registers;
+use super::TraceSink;
 
-use std::fs::OpenOptions;
-use std::io::prelude::*;
-
-// const IS_DEBUGGING: bool = false;
 const IF_REG: u16 = 0xFF0F;
 const IE_REG: u16 = 0xFFFF;
-pub const IS_DEBUGGING: bool = false;
 
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct CPU {
     pub registers: Registers,
     pub is_halted: bool,
     pub is_halt_bugged: bool,
+    /// M-cycles left before the CPU resumes after a CGB speed switch
+    speed_switch_delay: u16,
+    /// Set by EI: IME is enabled once the instruction following EI has been executed
     interrupt_action: Option<bool>,
     /// CPU Internal Flag: IME
     pub interrupts_enabled: bool,
+    /// Receives a trace line before every executed instruction (disabled when None)
+    #[serde(skip)]
+    tracer: Option<Box<dyn TraceSink>>,
+    /// T-cycles of the current step that have already been ticked on the bus by memory accesses
+    #[serde(skip)]
+    ticked_cycles: u8,
 }
 
 #[allow(unreachable_patterns)] // Some opcodes may fall under two categories, but either one will lead to the same result state
@@ -26,53 +34,96 @@ impl CPU {
             registers: Registers::new_post_boot_rom(), //Registers::new()
             is_halted: false,
             is_halt_bugged: false,
+            speed_switch_delay: 0,
             interrupt_action: Option::None,
             interrupts_enabled: false,
+            tracer: None,
+            ticked_cycles: 0,
         }
     }
 
-    pub fn log_state(&self, bus: &mut Bus) {
-        bus.debug();
-
-        log(format!(
-            "A:{} F:{} B:{} C:{} D:{} E:{} H:{} L:{} SP:{} PC:{} PCMEM:{},{},{},{}",
-            hex8(self.registers.a),
-            hex8(u8::from(self.registers.f)),
-            hex8(self.registers.b),
-            hex8(self.registers.c),
-            hex8(self.registers.d),
-            hex8(self.registers.e),
-            hex8(self.registers.h),
-            hex8(self.registers.l),
-            hex16(self.registers.sp),
-            hex16(self.registers.pc),
-            hex8(bus.ram_read_byte(self.registers.pc)),
-            hex8(bus.ram_read_byte(self.registers.pc.wrapping_add(1))),
-            hex8(bus.ram_read_byte(self.registers.pc.wrapping_add(2))),
-            hex8(bus.ram_read_byte(self.registers.pc.wrapping_add(3)))
-        ));
+    /// Enable tracing into the supplied sink, or disable it by passing None
+    pub fn set_trace_sink(&mut self, sink: Option<Box<dyn TraceSink>>) {
+        self.tracer = sink;
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
     }
 
-    pub fn is_interrupt_pending(&self, bus: &Bus) -> bool {
-        bus.ram_read_byte(IF_REG) & bus.ram_read_byte(IE_REG) & 0x1F != 0
+    /// Format the current CPU state as a Gameboy Doctor log line
+    pub fn trace_line(&self, bus: &impl MemoryAccess) -> String {
+        format!(
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            self.registers.a,
+            u8::from(self.registers.f),
+            self.registers.b,
+            self.registers.c,
+            self.registers.d,
+            self.registers.e,
+            self.registers.h,
+            self.registers.l,
+            self.registers.sp,
+            self.registers.pc,
+            bus.read_byte_unwatched(self.registers.pc),
+            bus.read_byte_unwatched(self.registers.pc.wrapping_add(1)),
+            bus.read_byte_unwatched(self.registers.pc.wrapping_add(2)),
+            bus.read_byte_unwatched(self.registers.pc.wrapping_add(3))
+        )
     }
 
-    pub fn step(&mut self, bus: &mut Bus) -> u8 {
-        // Handle pending (delayed) interrupt action
-        self.interrupt_action = match self.interrupt_action {
-            Some(x) => {
-                self.interrupts_enabled = x;
-                None
+    fn log_state(&mut self, bus: &impl MemoryAccess) {
+        if self.tracer.is_some() {
+            let line = self.trace_line(bus);
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.trace(&line);
             }
-            None => None,
-        };
+        }
+    }
+
+    pub fn is_interrupt_pending(&self, bus: &impl MemoryAccess) -> bool {
+        bus.read_byte_unwatched(IF_REG) & bus.read_byte_unwatched(IE_REG) & 0x1F != 0
+    }
+
+    /// Execute one instruction (or interrupt dispatch, or idle M-cycle while halted/stopped)
+    ///
+    /// The rest of the system is ticked through `MemoryAccess::tick` as the step progresses, one M-cycle
+    /// before every memory access. Internal M-cycles that do not access memory are ticked at the end.
+    /// Returns the number of T-cycles the step took.
+    pub fn step(&mut self, bus: &mut impl MemoryAccess) -> u8 {
+        self.ticked_cycles = 0;
+        // STOP may take longer than its table entry, when it consumes its second byte
+        let cycles = self.step_instruction(bus).max(self.ticked_cycles);
+        bus.tick(cycles - self.ticked_cycles);
+
+        cycles
+    }
+
+    fn step_instruction(&mut self, bus: &mut impl MemoryAccess) -> u8 {
+        // Handle STOP
+        if bus.is_stop_mode() {
+            // A pressed (and selected) key wakes the CPU up, regardless of IME/IE
+            if bus.read_byte_unwatched(0xFF00) & 0x0F != 0x0F {
+                bus.set_stop_mode(false);
+            } else {
+                return 4;
+            }
+        }
+
+        // Waiting for the clock to settle after a speed switch
+        if self.speed_switch_delay > 0 {
+            self.speed_switch_delay -= 1;
+            return 4;
+        }
 
         // Handle HALT
+        let mut wake_up_cycles: u8 = 0;
         if self.is_halted {
             if self.is_interrupt_pending(bus) {
-                // Interrupt exits halt
+                // Interrupt exits halt (even with IME disabled), which takes an extra M-cycle
                 self.is_halted = false;
-                //self.is_halt_bugged = false;
+                self.idle(bus);
+                wake_up_cycles = 4;
             } else {
                 // Still halted
                 return 4;
@@ -80,563 +131,483 @@ impl CPU {
         }
 
         // Handle interrupts or next step
-        self.log_state(bus);
         let cycles: u8 = match self.handle_interrupt(bus) {
             Some(x) => x,
-            None => self.execute_opcode(bus),
+            None => {
+                // An EI right before this instruction takes effect once it has been executed
+                let is_ei_delay_over = self.interrupt_action.is_some();
+
+                self.log_state(bus);
+                let cycles = self.execute_opcode(bus);
+
+                // Unless this instruction cancelled it (DI) or set it again (EI)
+                if is_ei_delay_over && self.interrupt_action == Some(true) {
+                    self.interrupt_action = None;
+                    self.interrupts_enabled = true;
+                }
+
+                cycles
+            }
         };
 
-        cycles
+        wake_up_cycles + cycles
     }
 
-    fn handle_interrupt(&mut self, bus: &mut Bus) -> Option<u8> {
-        if self.interrupts_enabled {
-            let ifr = bus.ram_read_byte(IF_REG) & 0x1F;
-            let ier = bus.ram_read_byte(IE_REG);
+    /// Dispatch the highest priority pending interrupt, if IME is set
+    ///
+    /// Takes 5 M-cycles: two wait states, pushing PC (high byte first) and jumping to the vector.
+    /// IE and IF are only sampled after the high byte has been pushed. If that push overwrote IE (SP = 0x0000)
+    /// and no enabled interrupt is left, the dispatch is cancelled and the CPU jumps to 0x0000 instead.
+    fn handle_interrupt(&mut self, bus: &mut impl MemoryAccess) -> Option<u8> {
+        if !self.interrupts_enabled || !self.is_interrupt_pending(bus) {
+            return None;
+        }
 
-            let triggers = ifr & ier;
-            if triggers != 0 {
-                // Some interrupt flag is enabled
-                self.interrupt_action = None;
-                self.interrupts_enabled = false;
-                // Disable interrupt until this one is completed (changed upon completion, in RETI call)
-                //self.is_halted = false;
-                //self.is_halt_bugged = false;
-                //println!("{:#X}", ifr);
-                //println!("{:#X}\n", ier);
-
-                // Handled in order of priority
-                // TODO - refactor into for loop
-                if triggers & 0x1 == 0x1 {
-                    // V Blank
-                    self.call(bus, 0x40);
-                    bus.ram_write_byte(IF_REG, ifr & !0x1);
-                } else if triggers & 0x2 == 0x2 {
-                    // LCD Status Triggers
-                    self.call(bus, 0x48);
-                    bus.ram_write_byte(IF_REG, ifr & !0x2);
-                } else if triggers & 0x4 == 0x4 {
-                    // Timer Overflow
-                    self.call(bus, 0x50);
-                    bus.ram_write_byte(IF_REG, ifr & !0x4);
-                } else if triggers & 0x8 == 0x8 {
-                    // Serial Link
-                    self.call(bus, 0x58);
-                    bus.ram_write_byte(IF_REG, ifr & !0x8);
-                } else if triggers & 0x10 == 0x10 {
-                    // Joypad Press
-                    self.call(bus, 0x60);
-                    bus.ram_write_byte(IF_REG, ifr & !0x10);
-                };
-
-                return Some(20); // 5-M cycles
-            };
+        self.interrupts_enabled = false;
+        self.interrupt_action = None;
+
+        // HALT bug right after EI (EI; HALT with an interrupt pending): the interrupt is dispatched before
+        // the byte after HALT is read twice, and returns to the HALT instead
+        if self.is_halt_bugged {
+            self.is_halt_bugged = false;
+            self.registers.pc = self.registers.pc.wrapping_sub(1);
+        }
+
+        // M1, M2 - Wait states
+        self.idle(bus);
+        self.idle(bus);
+
+        // M3 - Push the high byte of PC
+        let [pc_low, pc_high] = self.registers.pc.to_le_bytes();
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
+        self.write_memory(bus, self.registers.sp, pc_high);
+
+        // The interrupt is chosen now, the push above may have changed IE
+        let triggers = bus.read_byte_unwatched(IF_REG) & bus.read_byte_unwatched(IE_REG) & 0x1F;
+
+        // M4 - Push the low byte of PC
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
+        self.write_memory(bus, self.registers.sp, pc_low);
+
+        // M5 - Jump to the vector of the highest priority interrupt (lowest bit)
+        // V-Blank 0x40, LCD 0x48, Timer 0x50, Serial 0x58, Joypad 0x60
+        self.registers.pc = match (0..5).find(|bit| triggers & (1 << bit) != 0) {
+            Some(bit) => {
+                let ifr = bus.read_byte_unwatched(IF_REG);
+                bus.write_byte_unwatched(IF_REG, ifr & !(1 << bit));
+                0x40 + (bit as u16) * 8
+            }
+            None => 0x0000,
         };
 
-        return None;
+        Some(20) // 5-M cycles
     }
 
-    pub fn execute_opcode(&mut self, bus: &mut Bus) -> u8 {
-        let opcode = bus.ram_read_byte(self.registers.pc);
+    /// Fetch and execute one opcode, returns the T-cycles it took according to the opcode table
+    ///
+    /// The table also gives the immediate bytes to read and how far PC moves.
+    pub fn execute_opcode(&mut self, bus: &mut impl MemoryAccess) -> u8 {
+        let opcode = self.read_memory(bus, self.registers.pc);
 
         //println!("instruction {:#X}: {:#X}", self.registers.pc, opcode);
         self.registers.pc = self.registers.pc.wrapping_add(1);
         // HALT bug
         if self.is_halt_bugged {
-            self.registers.pc -= 1;
+            self.registers.pc = self.registers.pc.wrapping_sub(1);
             self.is_halt_bugged = false;
         }
 
-        let cycles: u8 = match opcode {
-            // FORMAT 0x00 => { statement ; clock_cycles }
-            0xCB => self.execute_opcode_cb(bus),
+        let info = &OPCODES[opcode as usize];
+        let immediate = self.read_immediate(bus, info);
+        self.registers.pc = self.registers.pc.wrapping_add(info.length as u16 - 1);
+
+        let mut is_branch_taken = false;
+        match opcode {
+            0xCB => return self.execute_opcode_cb(bus),
             //
             // 8-Bit Loads
             //
             // LD nn, n
             // A
             0x06 => {
-                self.registers.b = self.read_byte(bus);
-                8
+                self.registers.b = immediate as u8;
             }
             0x0E => {
-                self.registers.c = self.read_byte(bus);
-                8
+                self.registers.c = immediate as u8;
             }
             0x16 => {
-                self.registers.d = self.read_byte(bus);
-                8
+                self.registers.d = immediate as u8;
             }
             0x1E => {
-                self.registers.e = self.read_byte(bus);
-                8
+                self.registers.e = immediate as u8;
             }
             0x26 => {
-                self.registers.h = self.read_byte(bus);
-                8
+                self.registers.h = immediate as u8;
             }
             0x2E => {
-                self.registers.l = self.read_byte(bus);
-                8
+                self.registers.l = immediate as u8;
             }
             // LD r1, r2
             0x7F => {
                 self.registers.a = self.registers.a;
-                4
             }
             0x78 => {
                 self.registers.a = self.registers.b;
-                4
             }
             0x79 => {
                 self.registers.a = self.registers.c;
-                4
             }
             0x7A => {
                 self.registers.a = self.registers.d;
-                4
             }
             0x7B => {
                 self.registers.a = self.registers.e;
-                4
             }
             0x7C => {
                 self.registers.a = self.registers.h;
-                4
             }
             0x7D => {
                 self.registers.a = self.registers.l;
-                4
             }
             0x7E => {
                 self.registers.a = self.read_ram_at_hl(bus);
-                8
             }
             // B
             0x40 => {
                 self.registers.b = self.registers.b;
-                4
             }
             0x41 => {
                 self.registers.b = self.registers.c;
-                4
             }
             0x42 => {
                 self.registers.b = self.registers.d;
-                4
             }
             0x43 => {
                 self.registers.b = self.registers.e;
-                4
             }
             0x44 => {
                 self.registers.b = self.registers.h;
-                4
             }
             0x45 => {
                 self.registers.b = self.registers.l;
-                4
             }
             0x46 => {
                 self.registers.b = self.read_ram_at_hl(bus);
-                8
             }
             // C
             0x48 => {
                 self.registers.c = self.registers.b;
-                4
             }
             0x49 => {
                 self.registers.c = self.registers.c;
-                4
             }
             0x4A => {
                 self.registers.c = self.registers.d;
-                4
             }
             0x4B => {
                 self.registers.c = self.registers.e;
-                4
             }
             0x4C => {
                 self.registers.c = self.registers.h;
-                4
             }
             0x4D => {
                 self.registers.c = self.registers.l;
-                4
             }
             0x4E => {
                 self.registers.c = self.read_ram_at_hl(bus);
-                8
             }
             // D
             0x50 => {
                 self.registers.d = self.registers.b;
-                4
             }
             0x51 => {
                 self.registers.d = self.registers.c;
-                4
             }
             0x52 => {
                 self.registers.d = self.registers.d;
-                4
             }
             0x53 => {
                 self.registers.d = self.registers.e;
-                4
             }
             0x54 => {
                 self.registers.d = self.registers.h;
-                4
             }
             0x55 => {
                 self.registers.d = self.registers.l;
-                4
             }
             0x56 => {
                 self.registers.d = self.read_ram_at_hl(bus);
-                8
             }
             // E
             0x58 => {
                 self.registers.e = self.registers.b;
-                4
             }
             0x59 => {
                 self.registers.e = self.registers.c;
-                4
             }
             0x5A => {
                 self.registers.e = self.registers.d;
-                4
             }
             0x5B => {
                 self.registers.e = self.registers.e;
-                4
             }
             0x5C => {
                 self.registers.e = self.registers.h;
-                4
             }
             0x5D => {
                 self.registers.e = self.registers.l;
-                4
             }
             0x5E => {
                 self.registers.e = self.read_ram_at_hl(bus);
-                8
             }
             // H
             0x60 => {
                 self.registers.h = self.registers.b;
-                4
             }
             0x61 => {
                 self.registers.h = self.registers.c;
-                4
             }
             0x62 => {
                 self.registers.h = self.registers.d;
-                4
             }
             0x63 => {
                 self.registers.h = self.registers.e;
-                4
             }
             0x64 => {
                 self.registers.h = self.registers.h;
-                4
             }
             0x65 => {
                 self.registers.h = self.registers.l;
-                4
             }
             0x66 => {
                 self.registers.h = self.read_ram_at_hl(bus);
-                8
             }
             // L
             0x68 => {
                 self.registers.l = self.registers.b;
-                4
             }
             0x69 => {
                 self.registers.l = self.registers.c;
-                4
             }
             0x6A => {
                 self.registers.l = self.registers.d;
-                4
             }
             0x6B => {
                 self.registers.l = self.registers.e;
-                4
             }
             0x6C => {
                 self.registers.l = self.registers.h;
-                4
             }
             0x6D => {
                 self.registers.l = self.registers.l;
-                4
             }
             0x6E => {
                 self.registers.l = self.read_ram_at_hl(bus);
-                8
             }
             // (HL)
             0x70 => {
                 self.write_ram_at_hl(bus, self.registers.b);
-                8
             }
             0x71 => {
                 self.write_ram_at_hl(bus, self.registers.c);
-                8
             }
             0x72 => {
                 self.write_ram_at_hl(bus, self.registers.d);
-                8
             }
             0x73 => {
                 self.write_ram_at_hl(bus, self.registers.e);
-                8
             }
             0x74 => {
                 self.write_ram_at_hl(bus, self.registers.h);
-                8
             }
             0x75 => {
                 self.write_ram_at_hl(bus, self.registers.l);
-                8
             }
             0x36 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.write_ram_at_hl(bus, byte);
-                12
             }
             // LD A, n
             0x7F => {
                 self.registers.a = self.registers.a;
-                4
             }
             0x78 => {
                 self.registers.a = self.registers.b;
-                4
             }
             0x79 => {
                 self.registers.a = self.registers.c;
-                4
             }
             0x7A => {
                 self.registers.a = self.registers.d;
-                4
             }
             0x7B => {
                 self.registers.a = self.registers.e;
-                4
             }
             0x7C => {
                 self.registers.a = self.registers.h;
-                4
             }
             0x7D => {
                 self.registers.a = self.registers.l;
-                4
             }
             0x0A => {
-                self.registers.a = bus.ram_read_byte(self.registers.get_bc());
-                8
+                self.registers.a = self.read_memory(bus, self.registers.get_bc());
             }
             0x1A => {
-                self.registers.a = bus.ram_read_byte(self.registers.get_de());
-                8
+                self.registers.a = self.read_memory(bus, self.registers.get_de());
             }
             0x7E => {
-                self.registers.a = bus.ram_read_byte(self.registers.get_hl());
-                8
+                self.registers.a = self.read_memory(bus, self.registers.get_hl());
             }
             0xFA => {
-                self.registers.a = bus.ram_read_byte(self.read_word(bus));
-                16
+                let addr = immediate;
+                self.registers.a = self.read_memory(bus, addr);
             }
             0x3E => {
-                self.registers.a = self.read_byte(bus);
-                8
+                self.registers.a = immediate as u8;
             }
             // LD n, A
             0x7F => {
                 self.registers.a = self.registers.a;
-                4
             }
             0x47 => {
                 self.registers.b = self.registers.a;
-                4
             }
             0x4F => {
                 self.registers.c = self.registers.a;
-                4
             }
             0x57 => {
                 self.registers.d = self.registers.a;
-                4
             }
             0x5F => {
                 self.registers.e = self.registers.a;
-                4
             }
             0x67 => {
                 self.registers.h = self.registers.a;
-                4
             }
             0x6F => {
                 self.registers.l = self.registers.a;
-                4
             }
             0x02 => {
-                bus.ram_write_byte(self.registers.get_bc(), self.registers.a);
-                8
+                self.write_memory(bus, self.registers.get_bc(), self.registers.a);
             }
             0x12 => {
-                bus.ram_write_byte(self.registers.get_de(), self.registers.a);
-                8
+                self.write_memory(bus, self.registers.get_de(), self.registers.a);
             }
             0x77 => {
-                bus.ram_write_byte(self.registers.get_hl(), self.registers.a);
-                8
+                self.write_memory(bus, self.registers.get_hl(), self.registers.a);
             }
             0xEA => {
-                bus.ram_write_byte(self.read_word(bus), self.registers.a);
-                16
+                let addr = immediate;
+                self.write_memory(bus, addr, self.registers.a);
             }
             // LD A, (C)
             0xF2 => {
                 let addr = 0xFF00 | (self.registers.c as u16);
-                let byte = bus.ram_read_byte(addr);
+                let byte = self.read_memory(bus, addr);
                 self.registers.a = byte;
-                8
             }
             // LD (C), A
             0xE2 => {
                 let addr = 0xFF00 | (self.registers.c as u16);
-                bus.ram_write_byte(addr, self.registers.a);
-                8
+                self.write_memory(bus, addr, self.registers.a);
             }
             // LD A, (HLD) ; LD A, (HL-) ; LDD A, (HL) - 0x3A
             0x3A => {
-                let byte = bus.ram_read_byte(self.registers.hld());
+                let addr = self.registers.hld();
+                let byte = self.read_memory_idu(bus, addr);
                 self.registers.a = byte;
-                8
             }
             // LD (HLD), A ; LD (HL-), A ; LDD (HL), A - 0x32
             0x32 => {
                 let byte = self.registers.a;
-                bus.ram_write_byte(self.registers.hld(), byte);
-                8
+                let addr = self.registers.hld();
+                self.write_memory(bus, addr, byte);
             }
             // LD A, (HLI) ; LD A, (HL+) ; LDI A, (HL) - 0x2A
             0x2A => {
-                let byte = bus.ram_read_byte(self.registers.hli());
+                let addr = self.registers.hli();
+                let byte = self.read_memory_idu(bus, addr);
                 self.registers.a = byte;
-                8
             }
             // LD (HLI), A ; LD (HL+), A ; LDI (HL), A - 0x22
             0x22 => {
                 let byte = self.registers.a;
-                bus.ram_write_byte(self.registers.hli(), byte);
-                8
+                let addr = self.registers.hli();
+                self.write_memory(bus, addr, byte);
             }
             // LDH (n), A - 0xE0
             0xE0 => {
-                let byte = self.read_byte(bus);
-                bus.ram_write_byte(0xFF00 | byte as u16, self.registers.a);
-                12
+                let byte = immediate as u8;
+                self.write_memory(bus, 0xFF00 | byte as u16, self.registers.a);
             }
             // LDH A, (n)
             0xF0 => {
-                self.registers.a = bus.ram_read_byte(0xFF00 | self.read_byte(bus) as u16);
-                12
+                let addr = 0xFF00 | immediate;
+                self.registers.a = self.read_memory(bus, addr);
             }
             //
             // 16-Bit Loads
             //
             // LD n, nn
             0x01 => {
-                let word = self.read_word(bus);
-                self.registers.set_bc(word);
-                12
+                self.registers.set_bc(immediate);
             }
             0x11 => {
-                let word = self.read_word(bus);
-                self.registers.set_de(word);
-                12
+                self.registers.set_de(immediate);
             }
             0x21 => {
-                let word = self.read_word(bus);
-                self.registers.set_hl(word);
-                12
+                self.registers.set_hl(immediate);
             }
             0x31 => {
-                self.registers.sp = self.read_word(bus);
-                12
+                self.registers.sp = immediate;
             }
             // LD SP, HL
             0xF9 => {
                 self.registers.sp = self.registers.get_hl();
-                8
             }
             // LD HL, SP+n
             // LDHL SP, n
             0xF8 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 let result = self.alu_add_16_imm(self.registers.sp, byte);
                 self.registers.set_hl(result);
-                12
             }
             // LD (nn), SP - 0x08
             0x08 => {
-                let addr = self.read_word(bus);
-                bus.ram_write_word(addr, self.registers.sp);
-                20
+                let addr = immediate;
+                let [sp_low, sp_high] = self.registers.sp.to_le_bytes();
+                self.write_memory(bus, addr, sp_low);
+                self.write_memory(bus, addr.wrapping_add(1), sp_high);
             }
             // PUSH nn
             0xF5 => {
                 self.stack_push(bus, self.registers.get_af());
-                16
             }
             0xC5 => {
                 self.stack_push(bus, self.registers.get_bc());
-                16
             }
             0xD5 => {
                 self.stack_push(bus, self.registers.get_de());
-                16
             }
             0xE5 => {
                 self.stack_push(bus, self.registers.get_hl());
-                16
             }
             // POP nn
             0xF1 => {
                 let word = self.stack_pop(bus) & 0xFFF0;
                 self.registers.set_af(word);
-                12
             }
             0xC1 => {
                 let word = self.stack_pop(bus);
                 self.registers.set_bc(word);
-                12
             }
             0xD1 => {
                 let word = self.stack_pop(bus);
                 self.registers.set_de(word);
-                12
             }
             0xE1 => {
                 let word = self.stack_pop(bus);
                 self.registers.set_hl(word);
-                12
             }
             //
             //  8-Bit ALU
@@ -644,376 +615,296 @@ impl CPU {
             // ADD A, n
             0x87 => {
                 self.alu_add(self.registers.a, false);
-                4
             }
             0x80 => {
                 self.alu_add(self.registers.b, false);
-                4
             }
             0x81 => {
                 self.alu_add(self.registers.c, false);
-                4
             }
             0x82 => {
                 self.alu_add(self.registers.d, false);
-                4
             }
             0x83 => {
                 self.alu_add(self.registers.e, false);
-                4
             }
             0x84 => {
                 self.alu_add(self.registers.h, false);
-                4
             }
             0x85 => {
                 self.alu_add(self.registers.l, false);
-                4
             }
             0x86 => {
-                self.alu_add(self.read_ram_at_hl(bus), false);
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_add(byte, false);
             }
             0xC6 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_add(byte, false);
-                8
             }
             // ADC A, n
             0x8F => {
                 self.alu_add(self.registers.a, true);
-                4
             }
             0x88 => {
                 self.alu_add(self.registers.b, true);
-                4
             }
             0x89 => {
                 self.alu_add(self.registers.c, true);
-                4
             }
             0x8A => {
                 self.alu_add(self.registers.d, true);
-                4
             }
             0x8B => {
                 self.alu_add(self.registers.e, true);
-                4
             }
             0x8C => {
                 self.alu_add(self.registers.h, true);
-                4
             }
             0x8D => {
                 self.alu_add(self.registers.l, true);
-                4
             }
             0x8E => {
-                self.alu_add(self.read_ram_at_hl(bus), true);
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_add(byte, true);
             }
             0xCE => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_add(byte, true);
-                8
             }
             // SUB n
             0x97 => {
                 self.alu_sub(self.registers.a, false);
-                4
             }
             0x90 => {
                 self.alu_sub(self.registers.b, false);
-                4
             }
             0x91 => {
                 self.alu_sub(self.registers.c, false);
-                4
             }
             0x92 => {
                 self.alu_sub(self.registers.d, false);
-                4
             }
             0x93 => {
                 self.alu_sub(self.registers.e, false);
-                4
             }
             0x94 => {
                 self.alu_sub(self.registers.h, false);
-                4
             }
             0x95 => {
                 self.alu_sub(self.registers.l, false);
-                4
             }
             0x96 => {
-                self.alu_sub(self.read_ram_at_hl(bus), false);
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_sub(byte, false);
             }
             0xD6 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_sub(byte, false);
-                8
             }
             // SBC A, n
             0x9F => {
                 self.alu_sub(self.registers.a, true);
-                4
             }
             0x98 => {
                 self.alu_sub(self.registers.b, true);
-                4
             }
             0x99 => {
                 self.alu_sub(self.registers.c, true);
-                4
             }
             0x9A => {
                 self.alu_sub(self.registers.d, true);
-                4
             }
             0x9B => {
                 self.alu_sub(self.registers.e, true);
-                4
             }
             0x9C => {
                 self.alu_sub(self.registers.h, true);
-                4
             }
             0x9D => {
                 self.alu_sub(self.registers.l, true);
-                4
             }
             0x9E => {
-                self.alu_sub(self.read_ram_at_hl(bus), true);
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_sub(byte, true);
             }
             0xDE => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_sub(byte, true);
-                8
             }
             // AND n
             0xA7 => {
                 self.alu_and(self.registers.a);
-                4
             }
             0xA0 => {
                 self.alu_and(self.registers.b);
-                4
             }
             0xA1 => {
                 self.alu_and(self.registers.c);
-                4
             }
             0xA2 => {
                 self.alu_and(self.registers.d);
-                4
             }
             0xA3 => {
                 self.alu_and(self.registers.e);
-                4
             }
             0xA4 => {
                 self.alu_and(self.registers.h);
-                4
             }
             0xA5 => {
                 self.alu_and(self.registers.l);
-                4
             }
             0xA6 => {
-                self.alu_and(self.read_ram_at_hl(bus));
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_and(byte);
             }
             0xE6 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_and(byte);
-                8
             }
             // OR n
             0xB7 => {
                 self.alu_or(self.registers.a);
-                4
             }
             0xB0 => {
                 self.alu_or(self.registers.b);
-                4
             }
             0xB1 => {
                 self.alu_or(self.registers.c);
-                4
             }
             0xB2 => {
                 self.alu_or(self.registers.d);
-                4
             }
             0xB3 => {
                 self.alu_or(self.registers.e);
-                4
             }
             0xB4 => {
                 self.alu_or(self.registers.h);
-                4
             }
             0xB5 => {
                 self.alu_or(self.registers.l);
-                4
             }
             0xB6 => {
-                self.alu_or(self.read_ram_at_hl(bus));
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_or(byte);
             }
             0xF6 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_or(byte);
-                8
             }
             // XOR n
             0xAF => {
                 self.alu_xor(self.registers.a);
-                4
             }
             0xA8 => {
                 self.alu_xor(self.registers.b);
-                4
             }
             0xA9 => {
                 self.alu_xor(self.registers.c);
-                4
             }
             0xAA => {
                 self.alu_xor(self.registers.d);
-                4
             }
             0xAB => {
                 self.alu_xor(self.registers.e);
-                4
             }
             0xAC => {
                 self.alu_xor(self.registers.h);
-                4
             }
             0xAD => {
                 self.alu_xor(self.registers.l);
-                4
             }
             0xAE => {
-                self.alu_xor(self.read_ram_at_hl(bus));
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_xor(byte);
             }
             0xEE => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_xor(byte);
-                8
             }
             // CP n
             0xBF => {
                 self.alu_cp(self.registers.a);
-                4
             }
             0xB8 => {
                 self.alu_cp(self.registers.b);
-                4
             }
             0xB9 => {
                 self.alu_cp(self.registers.c);
-                4
             }
             0xBA => {
                 self.alu_cp(self.registers.d);
-                4
             }
             0xBB => {
                 self.alu_cp(self.registers.e);
-                4
             }
             0xBC => {
                 self.alu_cp(self.registers.h);
-                4
             }
             0xBD => {
                 self.alu_cp(self.registers.l);
-                4
             }
             0xBE => {
-                self.alu_cp(self.read_ram_at_hl(bus));
-                8
+                let byte = self.read_ram_at_hl(bus);
+                self.alu_cp(byte);
             }
             0xFE => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.alu_cp(byte);
-                8
             }
             // INC n
             0x3C => {
                 self.registers.a = self.inc(self.registers.a);
-                4
             }
             0x04 => {
                 self.registers.b = self.inc(self.registers.b);
-                4
             }
             0x0C => {
                 self.registers.c = self.inc(self.registers.c);
-                4
             }
             0x14 => {
                 self.registers.d = self.inc(self.registers.d);
-                4
             }
             0x1C => {
                 self.registers.e = self.inc(self.registers.e);
-                4
             }
             0x24 => {
                 self.registers.h = self.inc(self.registers.h);
-                4
             }
             0x2C => {
                 self.registers.l = self.inc(self.registers.l);
-                4
             }
             0x34 => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.inc(byte);
                 self.write_ram_at_hl(bus, result);
-                12
             }
             // DEC n
             0x3D => {
                 self.registers.a = self.dec(self.registers.a);
-                4
             }
             0x05 => {
                 self.registers.b = self.dec(self.registers.b);
-                4
             }
             0x0D => {
                 self.registers.c = self.dec(self.registers.c);
-                4
             }
             0x15 => {
                 self.registers.d = self.dec(self.registers.d);
-                4
             }
             0x1D => {
                 self.registers.e = self.dec(self.registers.e);
-                4
             }
             0x25 => {
                 self.registers.h = self.dec(self.registers.h);
-                4
             }
             0x2D => {
                 self.registers.l = self.dec(self.registers.l);
-                4
             }
             0x35 => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.dec(byte);
                 self.write_ram_at_hl(bus, result);
-                12
             }
             //
             // 16-Bit Arithmetic
@@ -1022,95 +913,86 @@ impl CPU {
             0x09 => {
                 let word = self.registers.get_bc();
                 self.alu_add_16(word);
-                8
             }
             0x19 => {
                 let word = self.registers.get_de();
                 self.alu_add_16(word);
-                8
             }
             0x29 => {
                 let word = self.registers.get_hl();
                 self.alu_add_16(word);
-                8
             }
             0x39 => {
                 self.alu_add_16(self.registers.sp);
-                8
             }
             // ADD SP, n
             0xE8 => {
-                let byte = self.read_byte(bus);
+                let byte = immediate as u8;
                 self.registers.sp = self.alu_add_16_imm(self.registers.sp, byte);
-                16
             }
             // INC nn
             0x03 => {
+                self.idu(bus, self.registers.get_bc());
                 self.registers
                     .set_bc(self.registers.get_bc().wrapping_add(1));
-                8
             }
             0x13 => {
+                self.idu(bus, self.registers.get_de());
                 self.registers
                     .set_de(self.registers.get_de().wrapping_add(1));
-                8
             }
             0x23 => {
+                self.idu(bus, self.registers.get_hl());
                 self.registers
                     .set_hl(self.registers.get_hl().wrapping_add(1));
-                8
             }
             0x33 => {
+                self.idu(bus, self.registers.sp);
                 self.registers.sp = self.registers.sp.wrapping_add(1);
-                8
             }
             // DEC nn
             0x0B => {
+                self.idu(bus, self.registers.get_bc());
                 self.registers
                     .set_bc(self.registers.get_bc().wrapping_sub(1));
-                8
             }
             0x1B => {
+                self.idu(bus, self.registers.get_de());
                 self.registers
                     .set_de(self.registers.get_de().wrapping_sub(1));
-                8
             }
             0x2B => {
+                self.idu(bus, self.registers.get_hl());
                 self.registers
                     .set_hl(self.registers.get_hl().wrapping_sub(1));
-                8
             }
             0x3B => {
+                self.idu(bus, self.registers.sp);
                 self.registers.sp = self.registers.sp.wrapping_sub(1);
-                8
             }
             // DAA
             0x27 => {
                 self.daa();
-                4
             }
             // CPL
             0x2F => {
                 self.registers.a = !self.registers.a;
                 self.registers.f.flag(Flag::N, true);
                 self.registers.f.flag(Flag::H, true);
-                4
             }
             // CCF
             0x3F => {
                 self.ccf();
-                4
             }
             // SCF
             0x37 => {
                 self.scf();
-                4
             }
             // NOP
-            0x00 => 4,
+            0x00 => {}
             // HALT
             0x76 => {
-                //let is_interrupt_pending: bool = bus.ram_read_byte(IF_REG) & bus.ram_read_byte(IE_REG) & 0x1F != 0;
+                //let is_interrupt_pending: bool = self.read_memory(bus, IF_REG) & self.read_memory(bus, IE_REG) & 0x1F != 0;
 
                 if self.interrupts_enabled {
                     // IME Enabled
@@ -1125,7 +1007,6 @@ impl CPU {
                         self.is_halted = true;
                     }
                 }
-                4
             }
             // STOP
             // DI
@@ -1136,117 +1017,94 @@ impl CPU {
             // RLCA
             0x07 => {
                 self.registers.a = self.rotate_circular(self.registers.a, true, false);
-                4
             }
             // RLA
             0x17 => {
                 self.registers.a = self.rotate(self.registers.a, true, false);
-                4
             }
             // RRCA
             0x0F => {
                 self.registers.a = self.rotate_circular(self.registers.a, false, false);
                 self.registers.f.flag(Flag::Z, false);
-                4
             }
             // RRA
             0x1F => {
                 self.registers.a = self.rotate(self.registers.a, false, false);
                 self.registers.f.flag(Flag::Z, false);
-                4
             }
             //
             // Jumps
             //
             // JP nn
             0xC3 => {
-                let addr = self.read_word(bus);
+                let addr = immediate;
                 self.jump_to(addr);
-                16
             }
             // JP cc, nn
             0xC2 => {
-                let addr = self.read_word(bus);
-                if !self.registers.f.zero {
+                let addr = immediate;
+                is_branch_taken = !self.registers.f.zero;
+                if is_branch_taken {
                     self.jump_to(addr);
-                    16
-                } else {
-                    12
                 }
             }
             0xCA => {
-                let addr = self.read_word(bus);
-                if self.registers.f.zero {
+                let addr = immediate;
+                is_branch_taken = self.registers.f.zero;
+                if is_branch_taken {
                     self.jump_to(addr);
-                    16
-                } else {
-                    12
                 }
             }
             0xD2 => {
-                let addr = self.read_word(bus);
-                if !self.registers.f.carry {
+                let addr = immediate;
+                is_branch_taken = !self.registers.f.carry;
+                if is_branch_taken {
                     self.jump_to(addr);
-                    16
-                } else {
-                    12
                 }
             }
             0xDA => {
-                let addr = self.read_word(bus);
-                if self.registers.f.carry {
+                let addr = immediate;
+                is_branch_taken = self.registers.f.carry;
+                if is_branch_taken {
                     self.jump_to(addr);
-                    16
-                } else {
-                    12
                 }
             }
             // JP (HL)
             0xE9 => {
                 self.jump_to(self.registers.get_hl());
-                4
             }
             // JR n
             0x18 => {
-                let offset = self.read_byte(bus) as i8;
+                let offset = immediate as i8;
                 self.jump_relative(offset);
-                12
             }
             // JR cc, n
             0x20 => {
-                let offset = self.read_byte(bus) as i8;
-                if !self.registers.f.zero {
+                let offset = immediate as i8;
+                is_branch_taken = !self.registers.f.zero;
+                if is_branch_taken {
                     self.jump_relative(offset);
-                    12
-                } else {
-                    8
                 }
             }
             0x28 => {
-                let offset = self.read_byte(bus) as i8;
-                if self.registers.f.zero {
+                let offset = immediate as i8;
+                is_branch_taken = self.registers.f.zero;
+                if is_branch_taken {
                     self.jump_relative(offset);
-                    12
-                } else {
-                    8
                 }
             }
             0x30 => {
-                let offset = self.read_byte(bus) as i8;
-                if !self.registers.f.carry {
+                let offset = immediate as i8;
+                is_branch_taken = !self.registers.f.carry;
+                if is_branch_taken {
                     self.jump_relative(offset);
-                    12
-                } else {
-                    8
                 }
             }
             0x38 => {
-                let offset = self.read_byte(bus) as i8;
-                if self.registers.f.carry {
+                let offset = immediate as i8;
+                is_branch_taken = self.registers.f.carry;
+                if is_branch_taken {
                     self.jump_relative(offset);
-                    12
-                } else {
-                    8
                 }
             }
             //
@@ -1254,45 +1112,36 @@ impl CPU {
             //
             // CALL nn
             0xCD => {
-                let addr = self.read_word(bus);
+                let addr = immediate;
                 self.call(bus, addr);
-                24
             }
             // CALL cc, nn
             0xC4 => {
-                let addr = self.read_word(bus);
-                if !self.registers.f.zero {
+                let addr = immediate;
+                is_branch_taken = !self.registers.f.zero;
+                if is_branch_taken {
                     self.call(bus, addr);
-                    24
-                } else {
-                    12
                 }
             }
             0xCC => {
-                let addr = self.read_word(bus);
-                if self.registers.f.zero {
+                let addr = immediate;
+                is_branch_taken = self.registers.f.zero;
+                if is_branch_taken {
                     self.call(bus, addr);
-                    24
-                } else {
-                    12
                 }
             }
             0xD4 => {
-                let addr = self.read_word(bus);
-                if !self.registers.f.carry {
+                let addr = immediate;
+                is_branch_taken = !self.registers.f.carry;
+                if is_branch_taken {
                     self.call(bus, addr);
-                    24
-                } else {
-                    12
                 }
             }
             0xDC => {
-                let addr = self.read_word(bus);
-                if self.registers.f.carry {
+                let addr = immediate;
+                is_branch_taken = self.registers.f.carry;
+                if is_branch_taken {
                     self.call(bus, addr);
-                    24
-                } else {
-                    12
                 }
             }
             //
@@ -1301,35 +1150,27 @@ impl CPU {
             // RST n
             0xC7 => {
                 self.call(bus, 0x00);
-                16
             }
             0xCF => {
                 self.call(bus, 0x08);
-                16
             }
             0xD7 => {
                 self.call(bus, 0x10);
-                16
             }
             0xDF => {
                 self.call(bus, 0x18);
-                16
             }
             0xE7 => {
                 self.call(bus, 0x20);
-                16
             }
             0xEF => {
                 self.call(bus, 0x28);
-                16
             }
             0xF7 => {
                 self.call(bus, 0x30);
-                16
             }
             0xFF => {
                 self.call(bus, 0x38);
-                16
             }
             //
             // Returns
@@ -1337,129 +1178,109 @@ impl CPU {
             // RET
             0xC9 => {
                 self.ret(bus);
-                16
             }
             // RET cc
             0xC0 => {
-                if !self.registers.f.zero {
+                self.idle(bus); // Condition check
+                is_branch_taken = !self.registers.f.zero;
+                if is_branch_taken {
                     self.ret(bus);
-                    20
-                } else {
-                    8
                 }
             }
             0xC8 => {
-                if self.registers.f.zero {
+                self.idle(bus); // Condition check
+                is_branch_taken = self.registers.f.zero;
+                if is_branch_taken {
                     self.ret(bus);
-                    20
-                } else {
-                    8
                 }
             }
             0xD0 => {
-                if !self.registers.f.carry {
+                self.idle(bus); // Condition check
+                is_branch_taken = !self.registers.f.carry;
+                if is_branch_taken {
                     self.ret(bus);
-                    20
-                } else {
-                    8
                 }
             }
             0xD8 => {
-                if self.registers.f.carry {
+                self.idle(bus); // Condition check
+                is_branch_taken = self.registers.f.carry;
+                if is_branch_taken {
                     self.ret(bus);
-                    20
-                } else {
-                    8
                 }
             }
             // RETI
             0xD9 => {
-                // TODO: ENABLE INTERRUPTS
-                // bus.ram_write_byte(0xFFFF, 1);
-                self.interrupt_action = Some(true);
+                // Unlike EI, IME is enabled right away
+                self.interrupt_action = None;
+                self.interrupts_enabled = true;
                 self.ret(bus);
-                16
             }
             //
             // Miscellaneous
             //
             // DI
             0xF3 => {
-                //println!("interupts disabled");
-                self.interrupt_action = Some(false);
-                4 // TODO
+                // Takes effect immediately, also cancels a pending EI
+                self.interrupt_action = None;
+                self.interrupts_enabled = false;
             }
             // EI
             0xFB => {
-                //println!("interupts enabled!");
+                // Delayed by one instruction
                 self.interrupt_action = Some(true);
-                4 // TODO
             }
             // STOP
-            0x10 => {
-                let _useless_byte = self.read_byte(bus);
-                println!("STOPPING @ {:#X}", self.registers.pc);
-                bus.timer.reset_div();
-                //self.is_halted = true;
-                4
-            }
+            0x10 => self.stop(bus),
             // NOT FOUND!
             _ => {
                 println!(
                     "Instruction {:#X} not supported: {:#X}",
-                    self.registers.pc - 1,
+                    self.registers.pc.wrapping_sub(1),
                     opcode
                 );
-                self.registers.pc -= 1;
+                self.registers.pc = self.registers.pc.wrapping_sub(1);
                 self.is_halted = true;
-                0
             }
-        };
+        }
 
-        cycles
+        info.cycles_for(is_branch_taken)
     }
 
-    fn execute_opcode_cb(&mut self, bus: &mut Bus) -> u8 {
-        let opcode = bus.ram_read_byte(self.registers.pc);
+    fn execute_opcode_cb(&mut self, bus: &mut impl MemoryAccess) -> u8 {
+        let opcode = self.read_memory(bus, self.registers.pc);
         //println!("CB instruction {:#X}: {:#X}", self.registers.pc, opcode);
-        self.registers.pc += 1;
+        let info = &CB_OPCODES[opcode as usize];
+        // The table's length includes the prefix, which was already consumed
+        self.registers.pc = self.registers.pc.wrapping_add(info.length as u16 - 1);
 
-        let cycles: u8 = match opcode {
+        match opcode {
             // MISC
             // SWAP n
             0x37 => {
                 self.registers.a = self.swap(self.registers.a);
-                8
             }
             0x30 => {
                 self.registers.b = self.swap(self.registers.b);
-                8
             }
             0x31 => {
                 self.registers.c = self.swap(self.registers.c);
-                8
             }
             0x32 => {
                 self.registers.d = self.swap(self.registers.d);
-                8
             }
             0x33 => {
                 self.registers.e = self.swap(self.registers.e);
-                8
             }
             0x34 => {
                 self.registers.h = self.swap(self.registers.h);
-                8
             }
             0x35 => {
                 self.registers.l = self.swap(self.registers.l);
-                8
             }
             0x36 => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.swap(byte);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             //
             // Shifts & Rotations
@@ -1467,1085 +1288,923 @@ impl CPU {
             // RLC n
             0x07 => {
                 self.registers.a = self.rotate_circular(self.registers.a, true, true);
-                8
             }
             0x00 => {
                 self.registers.b = self.rotate_circular(self.registers.b, true, true);
-                8
             }
             0x01 => {
                 self.registers.c = self.rotate_circular(self.registers.c, true, true);
-                8
             }
             0x02 => {
                 self.registers.d = self.rotate_circular(self.registers.d, true, true);
-                8
             }
             0x03 => {
                 self.registers.e = self.rotate_circular(self.registers.e, true, true);
-                8
             }
             0x04 => {
                 self.registers.h = self.rotate_circular(self.registers.h, true, true);
-                8
             }
             0x05 => {
                 self.registers.l = self.rotate_circular(self.registers.l, true, true);
-                8
             }
             0x06 => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.rotate_circular(byte, true, true);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // RL n
             0x17 => {
                 self.registers.a = self.rotate(self.registers.a, true, true);
-                8
             }
             0x10 => {
                 self.registers.b = self.rotate(self.registers.b, true, true);
-                8
             }
             0x11 => {
                 self.registers.c = self.rotate(self.registers.c, true, true);
-                8
             }
             0x12 => {
                 self.registers.d = self.rotate(self.registers.d, true, true);
-                8
             }
             0x13 => {
                 self.registers.e = self.rotate(self.registers.e, true, true);
-                8
             }
             0x14 => {
                 self.registers.h = self.rotate(self.registers.h, true, true);
-                8
             }
             0x15 => {
                 self.registers.l = self.rotate(self.registers.l, true, true);
-                8
             }
             0x16 => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.rotate(byte, true, true);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // RRC n
             0x0F => {
                 self.registers.a = self.rotate_circular(self.registers.a, false, true);
-                8
             }
             0x08 => {
                 self.registers.b = self.rotate_circular(self.registers.b, false, true);
-                8
             }
             0x09 => {
                 self.registers.c = self.rotate_circular(self.registers.c, false, true);
-                8
             }
             0x0A => {
                 self.registers.d = self.rotate_circular(self.registers.d, false, true);
-                8
             }
             0x0B => {
                 self.registers.e = self.rotate_circular(self.registers.e, false, true);
-                8
             }
             0x0C => {
                 self.registers.h = self.rotate_circular(self.registers.h, false, true);
-                8
             }
             0x0D => {
                 self.registers.l = self.rotate_circular(self.registers.l, false, true);
-                8
             }
             0x0E => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.rotate_circular(byte, false, true);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // RR n
             0x1F => {
                 self.registers.a = self.rotate(self.registers.a, false, true);
-                8
             }
             0x18 => {
                 self.registers.b = self.rotate(self.registers.b, false, true);
-                8
             }
             0x19 => {
                 self.registers.c = self.rotate(self.registers.c, false, true);
-                8
             }
             0x1A => {
                 self.registers.d = self.rotate(self.registers.d, false, true);
-                8
             }
             0x1B => {
                 self.registers.e = self.rotate(self.registers.e, false, true);
-                8
             }
             0x1C => {
                 self.registers.h = self.rotate(self.registers.h, false, true);
-                8
             }
             0x1D => {
                 self.registers.l = self.rotate(self.registers.l, false, true);
-                8
             }
             0x1E => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.rotate(byte, false, true);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // SLA n
             0x27 => {
                 self.registers.a = self.shift(self.registers.a, true, false);
-                8
             }
             0x20 => {
                 self.registers.b = self.shift(self.registers.b, true, false);
-                8
             }
             0x21 => {
                 self.registers.c = self.shift(self.registers.c, true, false);
-                8
             }
             0x22 => {
                 self.registers.d = self.shift(self.registers.d, true, false);
-                8
             }
             0x23 => {
                 self.registers.e = self.shift(self.registers.e, true, false);
-                8
             }
             0x24 => {
                 self.registers.h = self.shift(self.registers.h, true, false);
-                8
             }
             0x25 => {
                 self.registers.l = self.shift(self.registers.l, true, false);
-                8
             }
             0x26 => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.shift(byte, true, false);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // SRA n
             0x2F => {
                 self.registers.a = self.shift(self.registers.a, false, true);
-                8
             }
             0x28 => {
                 self.registers.b = self.shift(self.registers.b, false, true);
-                8
             }
             0x29 => {
                 self.registers.c = self.shift(self.registers.c, false, true);
-                8
             }
             0x2A => {
                 self.registers.d = self.shift(self.registers.d, false, true);
-                8
             }
             0x2B => {
                 self.registers.e = self.shift(self.registers.e, false, true);
-                8
             }
             0x2C => {
                 self.registers.h = self.shift(self.registers.h, false, true);
-                8
             }
             0x2D => {
                 self.registers.l = self.shift(self.registers.l, false, true);
-                8
             }
             0x2E => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.shift(byte, false, true);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // SRL n
             0x3F => {
                 self.registers.a = self.shift(self.registers.a, false, false);
-                8
             }
             0x38 => {
                 self.registers.b = self.shift(self.registers.b, false, false);
-                8
             }
             0x39 => {
                 self.registers.c = self.shift(self.registers.c, false, false);
-                8
             }
             0x3A => {
                 self.registers.d = self.shift(self.registers.d, false, false);
-                8
             }
             0x3B => {
                 self.registers.e = self.shift(self.registers.e, false, false);
-                8
             }
             0x3C => {
                 self.registers.h = self.shift(self.registers.h, false, false);
-                8
             }
             0x3D => {
                 self.registers.l = self.shift(self.registers.l, false, false);
-                8
             }
             0x3E => {
                 let byte = self.read_ram_at_hl(bus);
                 let result = self.shift(byte, false, false);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             // BIT b, r
             0x40 => {
                 self.test_bit(self.registers.b, 0);
-                8
             }
             0x41 => {
                 self.test_bit(self.registers.c, 0);
-                8
             }
             0x42 => {
                 self.test_bit(self.registers.d, 0);
-                8
             }
             0x43 => {
                 self.test_bit(self.registers.e, 0);
-                8
             }
             0x44 => {
                 self.test_bit(self.registers.h, 0);
-                8
             }
             0x45 => {
                 self.test_bit(self.registers.l, 0);
-                8
             }
             0x46 => {
-                self.test_bit(self.read_ram_at_hl(bus), 0);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 0);
             }
             0x47 => {
                 self.test_bit(self.registers.a, 0);
-                8
             }
             0x48 => {
                 self.test_bit(self.registers.b, 1);
-                8
             }
             0x49 => {
                 self.test_bit(self.registers.c, 1);
-                8
             }
             0x4A => {
                 self.test_bit(self.registers.d, 1);
-                8
             }
             0x4B => {
                 self.test_bit(self.registers.e, 1);
-                8
             }
             0x4C => {
                 self.test_bit(self.registers.h, 1);
-                8
             }
             0x4D => {
                 self.test_bit(self.registers.l, 1);
-                8
             }
             0x4E => {
-                self.test_bit(self.read_ram_at_hl(bus), 1);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 1);
             }
             0x4F => {
                 self.test_bit(self.registers.a, 1);
-                8
             }
             //
             0x50 => {
                 self.test_bit(self.registers.b, 2);
-                8
             }
             0x51 => {
                 self.test_bit(self.registers.c, 2);
-                8
             }
             0x52 => {
                 self.test_bit(self.registers.d, 2);
-                8
             }
             0x53 => {
                 self.test_bit(self.registers.e, 2);
-                8
             }
             0x54 => {
                 self.test_bit(self.registers.h, 2);
-                8
             }
             0x55 => {
                 self.test_bit(self.registers.l, 2);
-                8
             }
             0x56 => {
-                self.test_bit(self.read_ram_at_hl(bus), 2);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 2);
             }
             0x57 => {
                 self.test_bit(self.registers.a, 2);
-                8
             }
             0x58 => {
                 self.test_bit(self.registers.b, 3);
-                8
             }
             0x59 => {
                 self.test_bit(self.registers.c, 3);
-                8
             }
             0x5A => {
                 self.test_bit(self.registers.d, 3);
-                8
             }
             0x5B => {
                 self.test_bit(self.registers.e, 3);
-                8
             }
             0x5C => {
                 self.test_bit(self.registers.h, 3);
-                8
             }
             0x5D => {
                 self.test_bit(self.registers.l, 3);
-                8
             }
             0x5E => {
-                self.test_bit(self.read_ram_at_hl(bus), 3);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 3);
             }
             0x5F => {
                 self.test_bit(self.registers.a, 3);
-                8
             }
             //
             0x60 => {
                 self.test_bit(self.registers.b, 4);
-                8
             }
             0x61 => {
                 self.test_bit(self.registers.c, 4);
-                8
             }
             0x62 => {
                 self.test_bit(self.registers.d, 4);
-                8
             }
             0x63 => {
                 self.test_bit(self.registers.e, 4);
-                8
             }
             0x64 => {
                 self.test_bit(self.registers.h, 4);
-                8
             }
             0x65 => {
                 self.test_bit(self.registers.l, 4);
-                8
             }
             0x66 => {
-                self.test_bit(self.read_ram_at_hl(bus), 4);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 4);
             }
             0x67 => {
                 self.test_bit(self.registers.a, 4);
-                8
             }
             0x68 => {
                 self.test_bit(self.registers.b, 5);
-                8
             }
             0x69 => {
                 self.test_bit(self.registers.c, 5);
-                8
             }
             0x6A => {
                 self.test_bit(self.registers.d, 5);
-                8
             }
             0x6B => {
                 self.test_bit(self.registers.e, 5);
-                8
             }
             0x6C => {
                 self.test_bit(self.registers.h, 5);
-                8
             }
             0x6D => {
                 self.test_bit(self.registers.l, 5);
-                8
             }
             0x6E => {
-                self.test_bit(self.read_ram_at_hl(bus), 5);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 5);
             }
             0x6F => {
                 self.test_bit(self.registers.a, 5);
-                8
             }
             //
             0x70 => {
                 self.test_bit(self.registers.b, 6);
-                8
             }
             0x71 => {
                 self.test_bit(self.registers.c, 6);
-                8
             }
             0x72 => {
                 self.test_bit(self.registers.d, 6);
-                8
             }
             0x73 => {
                 self.test_bit(self.registers.e, 6);
-                8
             }
             0x74 => {
                 self.test_bit(self.registers.h, 6);
-                8
             }
             0x75 => {
                 self.test_bit(self.registers.l, 6);
-                8
             }
             0x76 => {
-                self.test_bit(self.read_ram_at_hl(bus), 6);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 6);
             }
             0x77 => {
                 self.test_bit(self.registers.a, 6);
-                8
             }
             0x78 => {
                 self.test_bit(self.registers.b, 7);
-                8
             }
             0x79 => {
                 self.test_bit(self.registers.c, 7);
-                8
             }
             0x7A => {
                 self.test_bit(self.registers.d, 7);
-                8
             }
             0x7B => {
                 self.test_bit(self.registers.e, 7);
-                8
             }
             0x7C => {
                 self.test_bit(self.registers.h, 7);
-                8
             }
             0x7D => {
                 self.test_bit(self.registers.l, 7);
-                8
             }
             0x7E => {
-                self.test_bit(self.read_ram_at_hl(bus), 7);
-                12
+                let byte = self.read_ram_at_hl(bus);
+                self.test_bit(byte, 7);
             }
             0x7F => {
                 self.test_bit(self.registers.a, 7);
-                8
             }
             // SET b, r
             0xC0 => {
                 self.registers.b = self.set_bit(self.registers.b, 0);
-                8
             }
             0xC1 => {
                 self.registers.c = self.set_bit(self.registers.c, 0);
-                8
             }
             0xC2 => {
                 self.registers.d = self.set_bit(self.registers.d, 0);
-                8
             }
             0xC3 => {
                 self.registers.e = self.set_bit(self.registers.e, 0);
-                8
             }
             0xC4 => {
                 self.registers.h = self.set_bit(self.registers.h, 0);
-                8
             }
             0xC5 => {
                 self.registers.l = self.set_bit(self.registers.l, 0);
-                8
             }
             0xC6 => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 0);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 0);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xC7 => {
                 self.registers.a = self.set_bit(self.registers.a, 0);
-                8
             }
             0xC8 => {
                 self.registers.b = self.set_bit(self.registers.b, 1);
-                8
             }
             0xC9 => {
                 self.registers.c = self.set_bit(self.registers.c, 1);
-                8
             }
             0xCA => {
                 self.registers.d = self.set_bit(self.registers.d, 1);
-                8
             }
             0xCB => {
                 self.registers.e = self.set_bit(self.registers.e, 1);
-                8
             }
             0xCC => {
                 self.registers.h = self.set_bit(self.registers.h, 1);
-                8
             }
             0xCD => {
                 self.registers.l = self.set_bit(self.registers.l, 1);
-                8
             }
             0xCE => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 1);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 1);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xCF => {
                 self.registers.a = self.set_bit(self.registers.a, 1);
-                8
             }
             //
             0xD0 => {
                 self.registers.b = self.set_bit(self.registers.b, 2);
-                8
             }
             0xD1 => {
                 self.registers.c = self.set_bit(self.registers.c, 2);
-                8
             }
             0xD2 => {
                 self.registers.d = self.set_bit(self.registers.d, 2);
-                8
             }
             0xD3 => {
                 self.registers.e = self.set_bit(self.registers.e, 2);
-                8
             }
             0xD4 => {
                 self.registers.h = self.set_bit(self.registers.h, 2);
-                8
             }
             0xD5 => {
                 self.registers.l = self.set_bit(self.registers.l, 2);
-                8
             }
             0xD6 => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 2);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 2);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xD7 => {
                 self.registers.a = self.set_bit(self.registers.a, 2);
-                8
             }
             0xD8 => {
                 self.registers.b = self.set_bit(self.registers.b, 3);
-                8
             }
             0xD9 => {
                 self.registers.c = self.set_bit(self.registers.c, 3);
-                8
             }
             0xDA => {
                 self.registers.d = self.set_bit(self.registers.d, 3);
-                8
             }
             0xDB => {
                 self.registers.e = self.set_bit(self.registers.e, 3);
-                8
             }
             0xDC => {
                 self.registers.h = self.set_bit(self.registers.h, 3);
-                8
             }
             0xDD => {
                 self.registers.l = self.set_bit(self.registers.l, 3);
-                8
             }
             0xDE => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 3);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 3);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xDF => {
                 self.registers.a = self.set_bit(self.registers.a, 3);
-                8
             }
             //
             0xE0 => {
                 self.registers.b = self.set_bit(self.registers.b, 4);
-                8
             }
             0xE1 => {
                 self.registers.c = self.set_bit(self.registers.c, 4);
-                8
             }
             0xE2 => {
                 self.registers.d = self.set_bit(self.registers.d, 4);
-                8
             }
             0xE3 => {
                 self.registers.e = self.set_bit(self.registers.e, 4);
-                8
             }
             0xE4 => {
                 self.registers.h = self.set_bit(self.registers.h, 4);
-                8
             }
             0xE5 => {
                 self.registers.l = self.set_bit(self.registers.l, 4);
-                8
             }
             0xE6 => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 4);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 4);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xE7 => {
                 self.registers.a = self.set_bit(self.registers.a, 4);
-                8
             }
             0xE8 => {
                 self.registers.b = self.set_bit(self.registers.b, 5);
-                8
             }
             0xE9 => {
                 self.registers.c = self.set_bit(self.registers.c, 5);
-                8
             }
             0xEA => {
                 self.registers.d = self.set_bit(self.registers.d, 5);
-                8
             }
             0xEB => {
                 self.registers.e = self.set_bit(self.registers.e, 5);
-                8
             }
             0xEC => {
                 self.registers.h = self.set_bit(self.registers.h, 5);
-                8
             }
             0xED => {
                 self.registers.l = self.set_bit(self.registers.l, 5);
-                8
             }
             0xEE => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 5);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 5);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xEF => {
                 self.registers.a = self.set_bit(self.registers.a, 5);
-                8
             }
             //
             0xF0 => {
                 self.registers.b = self.set_bit(self.registers.b, 6);
-                8
             }
             0xF1 => {
                 self.registers.c = self.set_bit(self.registers.c, 6);
-                8
             }
             0xF2 => {
                 self.registers.d = self.set_bit(self.registers.d, 6);
-                8
             }
             0xF3 => {
                 self.registers.e = self.set_bit(self.registers.e, 6);
-                8
             }
             0xF4 => {
                 self.registers.h = self.set_bit(self.registers.h, 6);
-                8
             }
             0xF5 => {
                 self.registers.l = self.set_bit(self.registers.l, 6);
-                8
             }
             0xF6 => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 6);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 6);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xF7 => {
                 self.registers.a = self.set_bit(self.registers.a, 6);
-                8
             }
             0xF8 => {
                 self.registers.b = self.set_bit(self.registers.b, 7);
-                8
             }
             0xF9 => {
                 self.registers.c = self.set_bit(self.registers.c, 7);
-                8
             }
             0xFA => {
                 self.registers.d = self.set_bit(self.registers.d, 7);
-                8
             }
             0xFB => {
                 self.registers.e = self.set_bit(self.registers.e, 7);
-                8
             }
             0xFC => {
                 self.registers.h = self.set_bit(self.registers.h, 7);
-                8
             }
             0xFD => {
                 self.registers.l = self.set_bit(self.registers.l, 7);
-                8
             }
             0xFE => {
-                let result = self.set_bit(self.read_ram_at_hl(bus), 7);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.set_bit(byte, 7);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xFF => {
                 self.registers.a = self.set_bit(self.registers.a, 7);
-                8
             }
             // RES b, r
             0x80 => {
                 self.registers.b = self.reset_bit(self.registers.b, 0);
-                8
             }
             0x81 => {
                 self.registers.c = self.reset_bit(self.registers.c, 0);
-                8
             }
             0x82 => {
                 self.registers.d = self.reset_bit(self.registers.d, 0);
-                8
             }
             0x83 => {
                 self.registers.e = self.reset_bit(self.registers.e, 0);
-                8
             }
             0x84 => {
                 self.registers.h = self.reset_bit(self.registers.h, 0);
-                8
             }
             0x85 => {
                 self.registers.l = self.reset_bit(self.registers.l, 0);
-                8
             }
             0x86 => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 0);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 0);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0x87 => {
                 self.registers.a = self.reset_bit(self.registers.a, 0);
-                8
             }
             0x88 => {
                 self.registers.b = self.reset_bit(self.registers.b, 1);
-                8
             }
             0x89 => {
                 self.registers.c = self.reset_bit(self.registers.c, 1);
-                8
             }
             0x8A => {
                 self.registers.d = self.reset_bit(self.registers.d, 1);
-                8
             }
             0x8B => {
                 self.registers.e = self.reset_bit(self.registers.e, 1);
-                8
             }
             0x8C => {
                 self.registers.h = self.reset_bit(self.registers.h, 1);
-                8
             }
             0x8D => {
                 self.registers.l = self.reset_bit(self.registers.l, 1);
-                8
             }
             0x8E => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 1);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 1);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0x8F => {
                 self.registers.a = self.reset_bit(self.registers.a, 1);
-                8
             }
             //
             0x90 => {
                 self.registers.b = self.reset_bit(self.registers.b, 2);
-                8
             }
             0x91 => {
                 self.registers.c = self.reset_bit(self.registers.c, 2);
-                8
             }
             0x92 => {
                 self.registers.d = self.reset_bit(self.registers.d, 2);
-                8
             }
             0x93 => {
                 self.registers.e = self.reset_bit(self.registers.e, 2);
-                8
             }
             0x94 => {
                 self.registers.h = self.reset_bit(self.registers.h, 2);
-                8
             }
             0x95 => {
                 self.registers.l = self.reset_bit(self.registers.l, 2);
-                8
             }
             0x96 => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 2);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 2);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0x97 => {
                 self.registers.a = self.reset_bit(self.registers.a, 2);
-                8
             }
             0x98 => {
                 self.registers.b = self.reset_bit(self.registers.b, 3);
-                8
             }
             0x99 => {
                 self.registers.c = self.reset_bit(self.registers.c, 3);
-                8
             }
             0x9A => {
                 self.registers.d = self.reset_bit(self.registers.d, 3);
-                8
             }
             0x9B => {
                 self.registers.e = self.reset_bit(self.registers.e, 3);
-                8
             }
             0x9C => {
                 self.registers.h = self.reset_bit(self.registers.h, 3);
-                8
             }
             0x9D => {
                 self.registers.l = self.reset_bit(self.registers.l, 3);
-                8
             }
             0x9E => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 3);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 3);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0x9F => {
                 self.registers.a = self.reset_bit(self.registers.a, 3);
-                8
             }
             //
             0xA0 => {
                 self.registers.b = self.reset_bit(self.registers.b, 4);
-                8
             }
             0xA1 => {
                 self.registers.c = self.reset_bit(self.registers.c, 4);
-                8
             }
             0xA2 => {
                 self.registers.d = self.reset_bit(self.registers.d, 4);
-                8
             }
             0xA3 => {
                 self.registers.e = self.reset_bit(self.registers.e, 4);
-                8
             }
             0xA4 => {
                 self.registers.h = self.reset_bit(self.registers.h, 4);
-                8
             }
             0xA5 => {
                 self.registers.l = self.reset_bit(self.registers.l, 4);
-                8
             }
             0xA6 => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 4);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 4);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xA7 => {
                 self.registers.a = self.reset_bit(self.registers.a, 4);
-                8
             }
             0xA8 => {
                 self.registers.b = self.reset_bit(self.registers.b, 5);
-                8
             }
             0xA9 => {
                 self.registers.c = self.reset_bit(self.registers.c, 5);
-                8
             }
             0xAA => {
                 self.registers.d = self.reset_bit(self.registers.d, 5);
-                8
             }
             0xAB => {
                 self.registers.e = self.reset_bit(self.registers.e, 5);
-                8
             }
             0xAC => {
                 self.registers.h = self.reset_bit(self.registers.h, 5);
-                8
             }
             0xAD => {
                 self.registers.l = self.reset_bit(self.registers.l, 5);
-                8
             }
             0xAE => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 5);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 5);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xAF => {
                 self.registers.a = self.reset_bit(self.registers.a, 5);
-                8
             }
             //
             0xB0 => {
                 self.registers.b = self.reset_bit(self.registers.b, 6);
-                8
             }
             0xB1 => {
                 self.registers.c = self.reset_bit(self.registers.c, 6);
-                8
             }
             0xB2 => {
                 self.registers.d = self.reset_bit(self.registers.d, 6);
-                8
             }
             0xB3 => {
                 self.registers.e = self.reset_bit(self.registers.e, 6);
-                8
             }
             0xB4 => {
                 self.registers.h = self.reset_bit(self.registers.h, 6);
-                8
             }
             0xB5 => {
                 self.registers.l = self.reset_bit(self.registers.l, 6);
-                8
             }
             0xB6 => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 6);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 6);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xB7 => {
                 self.registers.a = self.reset_bit(self.registers.a, 6);
-                8
             }
             0xB8 => {
                 self.registers.b = self.reset_bit(self.registers.b, 7);
-                8
             }
             0xB9 => {
                 self.registers.c = self.reset_bit(self.registers.c, 7);
-                8
             }
             0xBA => {
                 self.registers.d = self.reset_bit(self.registers.d, 7);
-                8
             }
             0xBB => {
                 self.registers.e = self.reset_bit(self.registers.e, 7);
-                8
             }
             0xBC => {
                 self.registers.h = self.reset_bit(self.registers.h, 7);
-                8
             }
             0xBD => {
                 self.registers.l = self.reset_bit(self.registers.l, 7);
-                8
             }
             0xBE => {
-                let result = self.reset_bit(self.read_ram_at_hl(bus), 7);
+                let byte = self.read_ram_at_hl(bus);
+                let result = self.reset_bit(byte, 7);
                 self.write_ram_at_hl(bus, result);
-                16
             }
             0xBF => {
                 self.registers.a = self.reset_bit(self.registers.a, 7);
-                8
             }
-        };
+        }
 
-        cycles
+        info.cycles
     }
 
     // Helper Methods
     fn form_16bit(a: u8, b: u8) -> u16 {
         ((a as u16) << 8) | (b as u16)
     }
-    fn read_byte(&mut self, bus: &Bus) -> u8 {
-        let imm1 = bus.ram_read_byte(self.registers.pc);
-        self.registers.pc += 1; // Consumed one byte
+    /// STOP - https://gbdev.io/pandocs/Reducing_Power_Consumption.html
+    ///
+    /// Normally a two byte opcode (0x10 0x00), but the second byte is not consumed when an interrupt is pending.
+    fn stop(&mut self, bus: &mut impl MemoryAccess) {
+        let is_key_held = bus.read_byte_unwatched(0xFF00) & 0x0F != 0x0F;
+        let is_interrupt_pending = self.is_interrupt_pending(bus);
+
+        // PC already moved past the second byte, which is only read when no interrupt is pending
+        let second_byte = self.registers.pc.wrapping_sub(1);
+        if is_interrupt_pending {
+            self.registers.pc = second_byte;
+        } else {
+            self.read_memory(bus, second_byte);
+        }
+
+        if is_key_held {
+            // STOP mode can't be entered while a key is held, it acts as HALT instead
+            self.is_halted = !is_interrupt_pending;
+            return;
+        }
+        bus.write_byte_unwatched(0xFF04, 0); // Writing to DIV resets it
 
-        imm1
+        if bus.speed_switch() {
+            // The CPU is paused while the clock settles, but does not enter STOP mode
+            self.speed_switch_delay = 2050;
+        } else {
+            bus.set_stop_mode(true);
+        }
     }
-    fn read_word(&mut self, bus: &Bus) -> u16 {
-        let imm1 = bus.ram_read_byte(self.registers.pc);
-        let imm2 = bus.ram_read_byte(self.registers.pc + 1);
-        let addr: u16 = CPU::form_16bit(imm2, imm1); // Reverse order due to Big Endian
-        self.registers.pc += 2; // Consumed two bytes
 
-        addr
+    // MEMORY
+    /// Let one M-cycle pass on the rest of the system without accessing memory
+    fn idle(&mut self, bus: &mut impl MemoryAccess) {
+        bus.tick(4);
+        self.ticked_cycles = self.ticked_cycles.wrapping_add(4);
+    }
+    /// Internal M-cycle in which the 16-bit incrementer/decrementer (IDU) works on `address`
+    fn idu(&mut self, bus: &mut impl MemoryAccess, address: u16) {
+        self.idle(bus);
+        bus.oam_bug(address, OamBugAccess::Write);
+    }
+    /// Read a byte, the access happens at the end of its M-cycle
+    fn read_memory(&mut self, bus: &mut impl MemoryAccess, address: u16) -> u8 {
+        self.idle(bus);
+        bus.oam_bug(address, OamBugAccess::Read);
+        bus.ram_read_byte(address)
     }
-    fn read_ram_at_hl(&self, bus: &Bus) -> u8 {
-        bus.ram_read_byte(self.registers.get_hl())
+    /// Read a byte while the IDU increments/decrements the address in the same M-cycle (LD A, (HL+), POP)
+    fn read_memory_idu(&mut self, bus: &mut impl MemoryAccess, address: u16) -> u8 {
+        self.idle(bus);
+        bus.oam_bug(address, OamBugAccess::ReadIdu);
+        bus.ram_read_byte(address)
     }
-    fn write_ram_at_hl(&self, bus: &mut Bus, byte: u8) {
-        bus.ram_write_byte(self.registers.get_hl(), byte);
+    /// Write a byte, the access happens at the end of its M-cycle
+    fn write_memory(&mut self, bus: &mut impl MemoryAccess, address: u16, byte: u8) {
+        self.idle(bus);
+        bus.oam_bug(address, OamBugAccess::Write);
+        bus.ram_write_byte(address, byte);
+    }
+
+    /// Read the immediate bytes after the opcode, as many as its operands take (little endian)
+    fn read_immediate(&mut self, bus: &mut impl MemoryAccess, info: &Opcode) -> u16 {
+        let mut immediate = 0;
+        for offset in 0..info.immediate_size() as u16 {
+            let byte = self.read_memory(bus, self.registers.pc.wrapping_add(offset));
+            immediate |= (byte as u16) << (8 * offset);
+        }
+        immediate
+    }
+    fn read_ram_at_hl(&mut self, bus: &mut impl MemoryAccess) -> u8 {
+        self.read_memory(bus, self.registers.get_hl())
+    }
+    fn write_ram_at_hl(&mut self, bus: &mut impl MemoryAccess, byte: u8) {
+        self.write_memory(bus, self.registers.get_hl(), byte);
     }
 
     // STACK
-    fn stack_push(&mut self, bus: &mut Bus, word: u16) {
-        self.registers.sp = self.registers.sp.wrapping_sub(2);
-        bus.ram_write_word(self.registers.sp, word);
+    /// Takes 3 M-cycles: an internal delay (SP is decremented), then the high byte and the low byte are written
+    fn stack_push(&mut self, bus: &mut impl MemoryAccess, word: u16) {
+        let [low, high] = word.to_le_bytes();
+        self.idu(bus, self.registers.sp);
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
+        self.write_memory(bus, self.registers.sp, high);
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
+        self.write_memory(bus, self.registers.sp, low);
     }
 
-    fn stack_pop(&mut self, bus: &mut Bus) -> u16 {
-        let word = bus.ram_read_word(self.registers.sp);
-        self.registers.sp = self.registers.sp.wrapping_add(2);
+    fn stack_pop(&mut self, bus: &mut impl MemoryAccess) -> u16 {
+        let low = self.read_memory_idu(bus, self.registers.sp);
+        self.registers.sp = self.registers.sp.wrapping_add(1);
+        let high = self.read_memory_idu(bus, self.registers.sp);
+        self.registers.sp = self.registers.sp.wrapping_add(1);
 
-        word
+        CPU::form_16bit(high, low)
     }
 
     // ALU
@@ -2824,51 +2483,14 @@ impl CPU {
     }
 
     // Calls
-    fn call(&mut self, bus: &mut Bus, word: u16) {
+    fn call(&mut self, bus: &mut impl MemoryAccess, word: u16) {
         self.stack_push(bus, self.registers.pc);
         self.jump_to(word);
     }
 
     // Returns
-    fn ret(&mut self, bus: &mut Bus) {
+    fn ret(&mut self, bus: &mut impl MemoryAccess) {
         let addr = self.stack_pop(bus);
         self.jump_to(addr);
     }
 }
-
-fn log(s: String) {
-    if !IS_DEBUGGING {
-        return;
-    };
-
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .append(true)
-        .open("gb-log")
-        .unwrap();
-
-    if let Err(e) = writeln!(file, "{}", s) {
-        eprintln!("Couldn't write to file: {}", e);
-    }
-}
-
-fn hex8(a: u8) -> String {
-    let result = format!("{:X}", a).to_string();
-    match result.len() {
-        0 => "00".to_string(),
-        1 => format!("0{}", result),
-        _ => result,
-    }
-}
-
-fn hex16(a: u16) -> String {
-    let result = format!("{:X}", a).to_string();
-    match result.len() {
-        0 => "0000".to_string(),
-        1 => format!("000{}", result),
-        2 => format!("00{}", result),
-        3 => format!("0{}", result),
-        _ => result,
-    }
-}
@@ -0,0 +1,491 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Write};
+
+use super::instruction::{BankedAddress, Instruction, Operand, Register, Symbols, Syntax};
+use super::opcode::Opcode;
+use super::symbols::SymbolTable;
+use super::CartridgeHeader;
+
+const BANK_SIZE: usize = 0x4000;
+const ENTRY_POINT: u16 = 0x0100;
+/// RST and interrupt vectors, with the names they get when no symbol is supplied
+const VECTORS: [(u16, &str); 13] = [
+    (0x00, "RST_00"),
+    (0x08, "RST_08"),
+    (0x10, "RST_10"),
+    (0x18, "RST_18"),
+    (0x20, "RST_20"),
+    (0x28, "RST_28"),
+    (0x30, "RST_30"),
+    (0x38, "RST_38"),
+    (0x40, "VBlankInterrupt"),
+    (0x48, "LCDInterrupt"),
+    (0x50, "TimerInterrupt"),
+    (0x58, "SerialInterrupt"),
+    (0x60, "JoypadInterrupt"),
+];
+/// Bytes per `db` line
+const DATA_BYTES_PER_LINE: usize = 16;
+
+/// Code/data log flags, one byte per ROM byte (as written by Mesen)
+const CDL_CODE: u8 = 0x01;
+const CDL_DATA: u8 = 0x02;
+const CDL_JUMP_TARGET: u8 = 0x04;
+const CDL_SUB_ENTRY_POINT: u8 = 0x08;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ByteKind {
+    /// Not reached by the analysis, written out as data
+    Unknown,
+    /// First byte of an instruction
+    Opcode,
+    /// Immediate (or CB opcode) byte of an instruction
+    Operand,
+}
+
+/// Static disassembler, turns a whole ROM into RGBDS source that assembles back to the same bytes
+///
+/// Code is found by following the control flow from the entry point and the RST and interrupt vectors
+/// (plus the symbols and code/data log, when supplied). Everything else is written out as data.
+pub struct Disassembler<'a> {
+    rom: &'a [u8],
+    header: CartridgeHeader,
+    kinds: Vec<ByteKind>,
+    /// ROM offsets that are branched to
+    labels: BTreeSet<usize>,
+    /// Bank of the target of branches to 0x4000-0x7FFF, by ROM offset of the branch
+    target_banks: HashMap<usize, u16>,
+    symbols: SymbolTable,
+    code_data_log: Option<&'a [u8]>,
+    /// Code left to trace: ROM offset, and ROM bank mapped at 0x4000-0x7FFF when it is known
+    queue: Vec<(usize, Option<u16>)>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(rom: &'a [u8]) -> Result<Self, String> {
+        if rom.len() < 2 * BANK_SIZE || !rom.len().is_multiple_of(BANK_SIZE) {
+            return Err(format!(
+                "ROM size ({} bytes) is not a multiple of 16 KiB, it can not be rebuilt as is",
+                rom.len()
+            ));
+        }
+
+        Ok(Disassembler {
+            rom,
+            header: CartridgeHeader::new(&rom[0x0100..0x0150]),
+            kinds: vec![ByteKind::Unknown; rom.len()],
+            labels: BTreeSet::new(),
+            target_banks: HashMap::new(),
+            symbols: SymbolTable::new(),
+            code_data_log: None,
+            queue: Vec::new(),
+        })
+    }
+
+    /// Name addresses after these symbols, the ones in ROM are also traced as code
+    pub fn set_symbols(&mut self, symbols: SymbolTable) {
+        self.symbols = symbols;
+    }
+
+    /// Bytes logged as code are traced, bytes only logged as data are never decoded
+    pub fn set_code_data_log(&mut self, log: &'a [u8]) -> Result<(), String> {
+        if log.len() != self.rom.len() {
+            return Err(format!(
+                "code/data log size ({} bytes) does not match the ROM size ({} bytes)",
+                log.len(),
+                self.rom.len()
+            ));
+        }
+        self.code_data_log = Some(log);
+        Ok(())
+    }
+
+    /// Find the code, from every entry point
+    ///
+    /// The vectors and the code/data log are traced first, so that symbols naming data (which are then
+    /// decoded as code) do not hide instructions that are really executed.
+    pub fn analyze(&mut self) {
+        // The MBC maps bank 1 at power on
+        self.queue.push((ENTRY_POINT as usize, Some(1)));
+        for (vector, _) in VECTORS {
+            self.queue.push((vector as usize, None));
+        }
+        self.trace_queue();
+
+        if let Some(log) = self.code_data_log {
+            for (offset, flags) in log.iter().enumerate() {
+                let is_entry = flags & (CDL_JUMP_TARGET | CDL_SUB_ENTRY_POINT) != 0;
+                let starts_code =
+                    flags & CDL_CODE != 0 && (offset == 0 || log[offset - 1] & CDL_CODE == 0);
+                if is_entry || starts_code {
+                    self.queue.push((offset, self.bank_at(offset)));
+                }
+            }
+        }
+
+        self.trace_queue();
+
+        let mut symbol_offsets: Vec<usize> = self
+            .symbols
+            .iter()
+            .filter_map(|(address, _)| self.offset_of(*address))
+            .collect();
+        symbol_offsets.sort_unstable();
+        for offset in symbol_offsets.into_iter().rev() {
+            self.queue.push((offset, self.bank_at(offset)));
+        }
+        self.trace_queue();
+    }
+
+    fn trace_queue(&mut self) {
+        while let Some((offset, mapped_bank)) = self.queue.pop() {
+            self.trace(offset, mapped_bank);
+        }
+    }
+
+    /// Bytes that were found to be code
+    pub fn code_size(&self) -> usize {
+        self.kinds
+            .iter()
+            .filter(|kind| **kind != ByteKind::Unknown)
+            .count()
+    }
+
+    /// Decode instructions from `offset` until the control flow stops or reaches known bytes
+    fn trace(&mut self, mut offset: usize, mut mapped_bank: Option<u16>) {
+        let bank_end = (offset / BANK_SIZE + 1) * BANK_SIZE;
+        // Value of A, when loaded with an immediate, to follow bank switches
+        let mut a: Option<u8> = None;
+
+        while offset < bank_end && self.kinds[offset] == ByteKind::Unknown {
+            if self.is_logged_data(offset) {
+                return;
+            }
+
+            let (instruction, info) = self.decode(offset);
+            let length = info.length as usize;
+            let end = offset + length;
+            if end > bank_end
+                || matches!(instruction, Instruction::Illegal(_))
+                || (offset + 1..end).any(|operand| self.kinds[operand] != ByteKind::Unknown)
+            {
+                return;
+            }
+
+            self.kinds[offset] = ByteKind::Opcode;
+            for operand in offset + 1..end {
+                self.kinds[operand] = ByteKind::Operand;
+            }
+
+            match instruction {
+                Instruction::Ld(Operand::Register(Register::A), Operand::Immediate8(value)) => {
+                    a = Some(value);
+                }
+                Instruction::Ld(Operand::Address(address), Operand::Register(Register::A))
+                    if (0x2000..=0x3FFF).contains(&address) =>
+                {
+                    mapped_bank = self.switch_bank(address, a, mapped_bank);
+                }
+                _ if writes_a(&instruction) => a = None,
+                _ => {}
+            }
+
+            if let Some(target) = instruction.target() {
+                if let Some(target_offset) = self.resolve(target, mapped_bank) {
+                    self.labels.insert(target_offset);
+                    let target_bank = if target >= 0x4000 {
+                        let bank = (target_offset / BANK_SIZE) as u16;
+                        self.target_banks.insert(offset, bank);
+                        Some(bank)
+                    } else {
+                        mapped_bank
+                    };
+                    self.queue.push((target_offset, target_bank));
+                }
+            }
+
+            if ends_flow(&instruction) {
+                return;
+            }
+            offset = end;
+        }
+    }
+
+    fn decode(&self, offset: usize) -> (Instruction, &'static Opcode) {
+        let address = self.address_at(offset).address;
+        Instruction::decode(
+            |next| {
+                let next_offset = offset + next.wrapping_sub(address) as usize;
+                self.rom.get(next_offset).copied().unwrap_or(0)
+            },
+            address,
+        )
+    }
+
+    fn is_logged_data(&self, offset: usize) -> bool {
+        self.code_data_log
+            .is_some_and(|log| log[offset] & (CDL_CODE | CDL_DATA) == CDL_DATA)
+    }
+
+    /// Bank selected by writing `value` to `address` (0x2000-0x3FFF), depending on the MBC
+    fn switch_bank(
+        &self,
+        address: u16,
+        value: Option<u8>,
+        mapped_bank: Option<u16>,
+    ) -> Option<u16> {
+        let bank_count = (self.rom.len() / BANK_SIZE) as u16;
+        let bank = match self.header.cartridge_type_code {
+            // MBC1, 5-bit bank number
+            0x01..=0x03 => value.map(|value| (value & 0x1F).max(1) as u16),
+            // MBC3, 7-bit bank number
+            0x0F..=0x13 => value.map(|value| (value & 0x7F).max(1) as u16),
+            // MBC5, low 8 bits of the bank number in 0x2000-0x2FFF (the 9th bit is assumed to stay 0)
+            0x19..=0x1E if address < 0x3000 => value.map(|value| value as u16),
+            // No MBC, or not a bank number register, the write is ignored
+            _ => return mapped_bank,
+        };
+        bank.map(|bank| bank % bank_count)
+    }
+
+    /// ROM offset of a branch target, when it is in ROM and its bank is known
+    fn resolve(&self, target: u16, mapped_bank: Option<u16>) -> Option<usize> {
+        match target {
+            0x0000..=0x3FFF => Some(target as usize),
+            0x4000..=0x7FFF => self.offset_of(BankedAddress::new(target, mapped_bank?)),
+            _ => None,
+        }
+    }
+
+    fn offset_of(&self, address: BankedAddress) -> Option<usize> {
+        let offset = match address.address {
+            0x0000..=0x3FFF => address.address as usize,
+            0x4000..=0x7FFF => {
+                address.bank as usize * BANK_SIZE + (address.address as usize - 0x4000)
+            }
+            _ => return None,
+        };
+        (offset < self.rom.len()).then_some(offset)
+    }
+
+    fn address_at(&self, offset: usize) -> BankedAddress {
+        let bank = offset / BANK_SIZE;
+        let address = if bank == 0 {
+            offset
+        } else {
+            0x4000 + offset % BANK_SIZE
+        };
+        BankedAddress::new(address as u16, bank as u16)
+    }
+
+    /// Bank mapped at 0x4000-0x7FFF while running code at `offset`, when it is known
+    fn bank_at(&self, offset: usize) -> Option<u16> {
+        match offset / BANK_SIZE {
+            0 => None,
+            bank => Some(bank as u16),
+        }
+    }
+
+    /// Write the whole ROM as RGBDS source, one section per bank
+    pub fn write_source(&self, w: &mut impl Write) -> fmt::Result {
+        let names = self.names();
+
+        writeln!(w, "; Disassembly of \"{}\"", self.header.title)?;
+        writeln!(w, "; Assemble with RGBDS 0.6 or later:")?;
+        writeln!(
+            w,
+            ";   rgbasm -o game.o game.asm && rgblink -o game.gb game.o"
+        )?;
+        writeln!(w)?;
+
+        // Symbols that are not placed as labels (outside ROM, in the middle of an instruction, or
+        // second names of an address)
+        let mut constants: Vec<(&BankedAddress, &String)> = self
+            .symbols
+            .iter()
+            .filter(|(address, name)| {
+                self.offset_of(**address).is_none()
+                    || names.symbol(**address) != Some(name.as_str())
+            })
+            .collect();
+        constants.sort();
+        for (address, name) in &constants {
+            writeln!(w, "DEF {} EQU ${:04X}", name, address.address)?;
+        }
+        if !constants.is_empty() {
+            writeln!(w)?;
+        }
+
+        for bank in 0..self.rom.len() / BANK_SIZE {
+            if bank == 0 {
+                writeln!(w, "SECTION \"ROM Bank $000\", ROM0[$0000]")?;
+            } else {
+                writeln!(
+                    w,
+                    "\nSECTION \"ROM Bank ${:03X}\", ROMX[$4000], BANK[${:03X}]",
+                    bank, bank
+                )?;
+            }
+            self.write_bank(w, bank, &names)?;
+        }
+
+        Ok(())
+    }
+
+    /// Names used in the source: the symbols outside ROM, then labels for the symbols, vectors and
+    /// branch targets that are not in the middle of an instruction
+    fn names(&self) -> SymbolTable {
+        let mut names = SymbolTable::new();
+
+        let mut sorted: Vec<(&BankedAddress, &String)> = self.symbols.iter().collect();
+        sorted.sort();
+        for (address, name) in sorted {
+            match self.offset_of(*address) {
+                Some(offset) if self.kinds[offset] == ByteKind::Operand => {}
+                _ => names.insert(*address, name.clone()),
+            }
+        }
+
+        let vectors = VECTORS
+            .iter()
+            .filter(|(vector, _)| self.kinds[*vector as usize] == ByteKind::Opcode)
+            .map(|(vector, name)| (*vector as usize, name.to_string()));
+        let entry_point = (ENTRY_POINT as usize, "EntryPoint".to_string());
+        let branches = self.labels.iter().map(|offset| {
+            let address = self.address_at(*offset);
+            let name = format!("Label_{:02X}_{:04X}", address.bank, address.address);
+            (*offset, name)
+        });
+        for (offset, name) in vectors.chain([entry_point]).chain(branches) {
+            let address = self.address_at(offset);
+            if self.kinds[offset] != ByteKind::Operand && names.symbol(address).is_none() {
+                names.insert(address, name);
+            }
+        }
+
+        names
+    }
+
+    fn write_bank(&self, w: &mut impl Write, bank: usize, names: &SymbolTable) -> fmt::Result {
+        let mut offset = bank * BANK_SIZE;
+        let end = offset + BANK_SIZE;
+
+        while offset < end {
+            let address = self.address_at(offset);
+            if let Some(name) = names.symbol(address) {
+                writeln!(w, "{}:", name)?;
+            }
+
+            if self.kinds[offset] == ByteKind::Opcode {
+                let (instruction, info) = self.decode(offset);
+                let bytes = &self.rom[offset..offset + info.length as usize];
+                let text = match self.source_text(&instruction, bytes, offset, names) {
+                    Some(text) => format!("    {}", text),
+                    None => format!("    {}", data_line(bytes)),
+                };
+                writeln!(w, "{:<40}; ${:04X}", text, address.address)?;
+                offset += bytes.len();
+            } else {
+                // Data, up to the next label or instruction
+                let mut data_end = offset + 1;
+                while data_end < end
+                    && data_end - offset < DATA_BYTES_PER_LINE
+                    && self.kinds[data_end] == ByteKind::Unknown
+                    && names.symbol(self.address_at(data_end)).is_none()
+                {
+                    data_end += 1;
+                }
+                let text = format!("    {}", data_line(&self.rom[offset..data_end]));
+                writeln!(w, "{:<40}; ${:04X}", text, address.address)?;
+                offset = data_end;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Source for an instruction, or None when RGBDS might not assemble it to the same bytes
+    fn source_text(
+        &self,
+        instruction: &Instruction,
+        bytes: &[u8],
+        offset: usize,
+        names: &SymbolTable,
+    ) -> Option<String> {
+        match instruction {
+            // Could be optimized into LDH
+            Instruction::Ld(Operand::Address(address), _)
+            | Instruction::Ld(_, Operand::Address(address))
+                if *address >= 0xFF00 =>
+            {
+                return None
+            }
+            // Always assembled as 0x10 0x00
+            Instruction::Stop if bytes[1] != 0x00 => return None,
+            _ => {}
+        }
+
+        // Addresses in 0x4000-0x7FFF are named after the bank the branch was resolved to, or the
+        // code's own bank, and not named when the bank is unknown
+        let rom_bank = self
+            .target_banks
+            .get(&offset)
+            .copied()
+            .or_else(|| self.bank_at(offset))
+            .unwrap_or(u16::MAX);
+        Some(instruction.format_as(Syntax::Rgbds, rom_bank, names))
+    }
+}
+
+fn data_line(bytes: &[u8]) -> String {
+    let values: Vec<String> = bytes.iter().map(|byte| format!("${:02X}", byte)).collect();
+    format!("db {}", values.join(", "))
+}
+
+/// The next instruction is not reached from this one
+fn ends_flow(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Jp(None, _)
+            | Instruction::JpHl
+            | Instruction::Jr(None, _)
+            | Instruction::Ret(None)
+            | Instruction::Reti
+    )
+}
+
+/// Whether A may have changed, calls included
+fn writes_a(instruction: &Instruction) -> bool {
+    let is_a = |operand: &Operand| *operand == Operand::Register(Register::A);
+    match instruction {
+        Instruction::Ld(to, _) | Instruction::Ldh(to, _) | Instruction::Add(to, _) => is_a(to),
+        Instruction::Inc(operand)
+        | Instruction::Dec(operand)
+        | Instruction::Rlc(operand)
+        | Instruction::Rrc(operand)
+        | Instruction::Rl(operand)
+        | Instruction::Rr(operand)
+        | Instruction::Sla(operand)
+        | Instruction::Sra(operand)
+        | Instruction::Swap(operand)
+        | Instruction::Srl(operand)
+        | Instruction::Res(_, operand)
+        | Instruction::Set(_, operand) => is_a(operand),
+        Instruction::Adc(_)
+        | Instruction::Sub(_)
+        | Instruction::Sbc(_)
+        | Instruction::And(_)
+        | Instruction::Xor(_)
+        | Instruction::Or(_)
+        | Instruction::Daa
+        | Instruction::Cpl
+        | Instruction::Rlca
+        | Instruction::Rla
+        | Instruction::Rrca
+        | Instruction::Rra
+        | Instruction::Pop(Register::AF)
+        | Instruction::Call(..)
+        | Instruction::Rst(_) => true,
+        _ => false,
+    }
+}
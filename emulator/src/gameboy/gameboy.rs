@@ -1,56 +1,141 @@
-use super::{joypad::JoypadInputKey, ppu::Pixel, Bus, CartridgeHeader, CPU, PPU};
+use super::{
+    cartridge::{MBC, MBC0},
+    joypad::JoypadInputKey,
+    ppu::Pixel,
+    BootSequence, Bus, CartridgeHeader, Model, Registers, WatchpointHit, CPU, PPU,
+};
+use crate::CYCLES_PER_FRAME;
+
+/// What happened during `GameBoy::run_frame`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameResult {
+    /// The PPU entered V-Blank, the display holds a new frame
+    pub is_frame_completed: bool,
+    /// T-cycles run
+    pub cycles: u32,
+    /// Stopped early because the CPU reached a breakpoint (or a watchpoint)
+    pub is_breakpoint_hit: bool,
+    /// Stopped early because of this memory access, after the instruction that made it
+    pub watchpoint_hit: Option<WatchpointHit>,
+}
 
 pub struct GameBoy {
     pub cpu: CPU,
     pub bus: Box<Bus>,
     pub cartridge_header: Option<CartridgeHeader>,
+    /// Hardware revision, determines the state the hardware starts in when the boot ROM is skipped
+    pub model: Model,
+    /// Built-in boot sequence, runs instead of the CPU while set
+    pub boot_sequence: Option<BootSequence>,
     //tile_map_screen: [[Pixel; 16 * 8]; 32 * 8],
 }
 
 impl GameBoy {
-    pub fn new() -> Self {
-        GameBoy {
+    /// Create a Game Boy of the supplied model, as if it had just finished running its boot ROM
+    pub fn new(model: Model) -> Self {
+        let mut gameboy = GameBoy {
             cpu: CPU::new(),
-            bus: Box::new(Bus::new()),
+            bus: Box::new(Bus::new(model)),
             cartridge_header: None,
-        }
+            model,
+            boot_sequence: None,
+        };
+        gameboy.load_post_boot_state(&[]);
+
+        gameboy
+    }
+
+    /// Skip the boot ROM: put the CPU and hardware into the state this model's boot ROM leaves them in
+    ///
+    /// `header` is the cartridge header (0x0100-0x014F), it affects some registers and the logo in VRAM.
+    fn load_post_boot_state(&mut self, header: &[u8]) {
+        self.cpu.registers = self.model.post_boot_registers(header);
+        self.bus.load_post_boot_state(self.model, header);
     }
 
     //
     // Stepping/Ticking the CPU
     //
 
-    /// Execute one tick's worth of opcodes
+    /// Execute one frame's worth of opcodes
     pub fn tick(&mut self) {
-        self.tick_bp(None);
+        self.run_frame();
     }
 
-    /// Execute one tick's worth of opcodes, returning early if a breakpoint is encountered
+    /// Execute one frame's worth of opcodes, returning early if a breakpoint is encountered
     ///
     /// Returns whether or not a breakpoint was hit
-    pub fn tick_bp(&mut self, _breakpoints: Option<&Vec<u16>>) -> bool {
-        let mut current_frame_cycles: f64 = 0f64;
+    pub fn tick_bp(&mut self, breakpoints: Option<&Vec<u16>>) -> bool {
+        self.run_frame_bp(breakpoints).is_breakpoint_hit
+    }
+
+    /// Run until the PPU enters V-Blank, i.e. a new frame is ready to be displayed
+    ///
+    /// While the LCD is off there are no frames, a frame's worth of cycles is run instead.
+    pub fn run_frame(&mut self) -> FrameResult {
+        self.run_frame_bp(None)
+    }
+
+    /// Run until the PPU enters V-Blank, returning early if a breakpoint is encountered
+    pub fn run_frame_bp(&mut self, breakpoints: Option<&Vec<u16>>) -> FrameResult {
+        // The CPU runs twice as many cycles per frame in double speed mode
+        let max_cycles = if self.bus.is_double_speed {
+            CYCLES_PER_FRAME * 2
+        } else {
+            CYCLES_PER_FRAME
+        };
+        let mut result = FrameResult::default();
 
-        let are_breakpoints_enabled: bool = _breakpoints.is_some();
+        self.bus.ppu.has_entered_vblank = false;
+        // Accesses made before (e.g. by single steps) do not stop this frame
+        self.bus.watchpoints.take_hit();
+        while result.cycles < max_cycles {
+            result.cycles += self.step() as u32;
+
+            if let Some(hit) = self.bus.watchpoints.take_hit() {
+                result.watchpoint_hit = Some(hit);
+                result.is_breakpoint_hit = true;
+                break;
+            }
 
-        while current_frame_cycles < self.bus.timer.get_clock_freq() {
-            current_frame_cycles += self.step() as f64;
-            if are_breakpoints_enabled && _breakpoints.unwrap().contains(&self.cpu.registers.pc) {
-                return true;
+            if self.bus.ppu.has_entered_vblank {
+                result.is_frame_completed = true;
+                break;
+            }
+            let pc = self.cpu.registers.pc;
+            if breakpoints.map_or(false, |breakpoints| breakpoints.contains(&pc)) {
+                result.is_breakpoint_hit = true;
+                break;
             }
         }
 
-        return false;
+        result
     }
 
     /// Execute a single opcode
     pub fn step(&mut self) -> u8 {
-        // Execute one CPU instruction
-        let _cycles: u8 = self.cpu.step(&mut self.bus);
-        // Tick appropriate components through the bus
-        self.bus.tick(_cycles);
+        if self.boot_sequence.is_some() {
+            return self.step_boot_sequence();
+        }
+
+        // Execute one CPU instruction, the CPU ticks the other components through the bus as it goes
+        self.cpu.step(self.bus.as_mut())
+    }
 
-        _cycles
+    /// Advance the built-in boot sequence by one M-cycle, handing over to the cartridge once it finishes
+    fn step_boot_sequence(&mut self) -> u8 {
+        let cycles: u8 = 4;
+        self.bus.tick(cycles);
+
+        if let Some(boot_sequence) = self.boot_sequence.as_mut() {
+            if boot_sequence.tick(self.bus.as_mut(), self.model) {
+                let header = std::mem::take(&mut boot_sequence.header);
+                self.boot_sequence = None;
+                self.load_post_boot_state(&header);
+            }
+        }
+
+        cycles
     }
 
     //
@@ -60,31 +145,78 @@ impl GameBoy {
     /// Load the supplied ROM's buffer
     ///
     /// It will parse the ROM's cartridge header and load the appropriate MBC
-    pub fn read_rom(&mut self, buffer: &Vec<u8>) {
-        self.cartridge_header = Some(CartridgeHeader::new(&buffer[0x0100..=0x014F]));
-        // CAUSES NINTENDO LOGO TO DISAPPEAR??
-
-        if let Some(c_h) = &self.cartridge_header {
-            match c_h.cartridge_type_code {
-                0x00 => self.bus.mbc = Box::new(super::cartridge::MBC0::new()),
-                0x01 | 0x02 | 0x03 => self.bus.mbc = Box::new(super::cartridge::MBC1::new(c_h)),
-                0x0F | 0x10 | 0x11 | 0x12 | 0x13 => {
-                    self.bus.mbc = Box::new(super::cartridge::MBC3::new(c_h))
-                }
-                _ => {
-                    panic!("Unsupported cartridge!\n\t{:#X}\n", c_h.cartridge_type_code);
-                }
-            }
+    pub fn read_rom(&mut self, buffer: &Vec<u8>) -> Result<(), String> {
+        if buffer.len() <= 0x014F {
+            return Err(format!(
+                "ROM is too small to contain a cartridge header ({} bytes)",
+                buffer.len()
+            ));
         }
 
+        let c_h = CartridgeHeader::new(&buffer[0x0100..=0x014F]);
+        if c_h.rom_size == 0 {
+            return Err(format!(
+                "Unsupported ROM size code: {:#X}",
+                c_h.rom_size_code
+            ));
+        }
+
+        let mbc: Box<dyn MBC> = match c_h.cartridge_type_code {
+            0x00 => Box::new(super::cartridge::MBC0::new()),
+            0x01 | 0x02 | 0x03 => Box::new(super::cartridge::MBC1::new(&c_h)),
+            0x0F | 0x10 | 0x11 | 0x12 | 0x13 => Box::new(super::cartridge::MBC3::new(&c_h)),
+            _ => {
+                return Err(format!(
+                    "Unsupported cartridge type: {:#X}",
+                    c_h.cartridge_type_code
+                ));
+            }
+        };
+
+        self.bus.mbc = mbc;
+        // The SGB functions are only unlocked by games that declare support for them
+        self.bus
+            .set_sgb_enabled(self.model.is_sgb() && c_h.is_sgb_supported);
+        self.cartridge_header = Some(c_h);
         self.bus.ram_load_rom(buffer, 0x0);
+
+        // Without a boot ROM, the cartridge header still affects the post-boot state
+        if !self.bus.is_boot_rom_mapped() && self.boot_sequence.is_none() {
+            self.load_post_boot_state(&buffer[0x0100..=0x014F]);
+        }
+
+        Ok(())
     }
 
     /// Load the Boot ROM into memory (0x0000-0x0100)
+    ///
+    /// The hardware is put back into its power-on state so that the boot ROM runs from 0x0000
+    /// and sets everything up itself. The loaded cartridge (if any) is kept.
     pub fn read_boot_rom(&mut self, buffer: &Vec<u8>) {
+        self.reset_to_power_on();
         self.bus.ram_load_boot_rom(buffer);
     }
 
+    /// Run the built-in boot sequence instead of a boot ROM
+    ///
+    /// Shows the cartridge's logo animation and checks its header, without needing Nintendo's boot ROM.
+    /// Like `read_boot_rom`, the cartridge can be loaded before or after calling this.
+    pub fn use_builtin_boot_rom(&mut self) {
+        self.reset_to_power_on();
+        self.boot_sequence = Some(BootSequence::new());
+    }
+
+    /// Put the hardware back into its power-on state, keeping the loaded cartridge
+    fn reset_to_power_on(&mut self) {
+        let mbc = std::mem::replace(&mut self.bus.mbc, Box::new(MBC0::new()));
+        let is_sgb_enabled = self.bus.sgb.is_some();
+        *self.bus = Bus::new(self.model);
+        self.bus.mbc = mbc;
+        self.bus.set_sgb_enabled(is_sgb_enabled);
+        self.cpu.registers = Registers::new();
+        self.boot_sequence = None;
+    }
+
     //
     // Public display methods
     //
@@ -50,6 +50,16 @@ pub struct Joypad {
     pub input_byte: u8,     // Start | Select | B | A | Down | Up | Left | Right
     pub selection_mask: u8, // 2 bits
     pub raise_interrupt: Option<Interrupt>,
+    /// Decode SGB command packets sent through P14/P15 pulses
+    pub is_sgb_enabled: bool,
+    /// SGB packet being received, and how many of its 128 bits were received (None when idle)
+    packet: [u8; 16],
+    packet_bit: Option<u8>,
+    /// Set once a full SGB packet was received, to be forwarded by the bus
+    pub sgb_packet: Option<[u8; 16]>,
+    /// SGB multiplayer (MLT_REQ): amount of controllers (1, 2 or 4) and the one currently being read
+    player_count: u8,
+    current_player: u8,
 }
 
 impl Joypad {
@@ -58,55 +68,119 @@ impl Joypad {
             input_byte: 0xFF, // Start as all unpressed
             selection_mask: 0,
             raise_interrupt: None,
+            is_sgb_enabled: false,
+            packet: [0; 16],
+            packet_bit: None,
+            sgb_packet: None,
+            player_count: 1,
+            current_player: 0,
         }
     }
 
     pub fn read_byte(&self) -> u8 {
         // Reading 0xFF00
-        let mut result: u8 = 0xF0;
+        // Both groups can be selected at once, a line then reads low if a key from either group is pressed
+        let mut lines: u8 = 0x0F;
+        // Only the first controller has keys mapped to it, the others read as released
+        let input_byte = if self.current_player == 0 {
+            self.input_byte
+        } else {
+            0xFF
+        };
 
-        if self.selection_mask == 0b10 {
+        if self.is_directions_selected() {
             // Read Directions; lower nibble
-            self.input_byte & 0xF | 0xF0;
-            result |= self.input_byte & 0x0F;
+            lines &= input_byte & 0x0F;
         }
-        if self.selection_mask == 0b01 {
+        if self.is_buttons_selected() {
             // Read Buttons; upper nible
-            result |= (self.input_byte & 0xF0) >> 4;
+            lines &= (input_byte & 0xF0) >> 4;
         }
-        if self.selection_mask == 0b11 {
-            // ...
-            result |= 0xF;
+        // SGB multiplayer: with neither group selected, the lines hold the ID of the current controller
+        if self.selection_mask == 0b11 && self.player_count > 1 {
+            lines = 0x0F - self.current_player;
         }
 
-        result
+        0xC0 | (self.selection_mask << 4) | lines
+    }
+
+    /// P14 (bit 4) is low
+    fn is_directions_selected(&self) -> bool {
+        self.selection_mask & 0b01 == 0
+    }
+
+    /// P15 (bit 5) is low
+    fn is_buttons_selected(&self) -> bool {
+        self.selection_mask & 0b10 == 0
     }
 
     pub fn write_byte(&mut self, byte: u8) {
         // Update reading mode
+        let previous_mask = self.selection_mask;
         self.selection_mask = (byte & 0x30) >> 4;
+
+        if self.is_sgb_enabled {
+            self.sgb_pulse(previous_mask);
+        }
+    }
+
+    /// Decode the SGB packet transfer protocol
+    ///
+    /// A packet starts with a reset pulse (P14 and P15 low), followed by 128 bits (LSB first) and a 0 stop bit.
+    /// A 0 bit is sent by pulling P14 low, a 1 bit by pulling P15 low, both lines go back high between bits.
+    ///
+    /// https://gbdev.io/pandocs/SGB_Command_Packet.html
+    fn sgb_pulse(&mut self, previous_mask: u8) {
+        match (previous_mask, self.selection_mask) {
+            // Reset pulse
+            (_, 0b00) => {
+                self.packet = [0; 16];
+                self.packet_bit = Some(0);
+            }
+            // P15 going back high selects the next controller (outside of a packet transfer)
+            (0b01, 0b11) if self.packet_bit.is_none() && self.player_count > 1 => {
+                self.current_player = (self.current_player + 1) % self.player_count;
+            }
+            // A bit is sent
+            (0b11, 0b01 | 0b10) => {
+                if let Some(bit) = self.packet_bit {
+                    if bit == 128 {
+                        // Stop bit
+                        self.sgb_packet = Some(self.packet);
+                        self.packet_bit = None;
+                    } else {
+                        if self.selection_mask == 0b01 {
+                            self.packet[bit as usize / 8] |= 1 << (bit % 8);
+                        }
+                        self.packet_bit = Some(bit + 1);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Set the amount of SGB controllers (MLT_REQ), the first controller is read next
+    pub fn set_player_count(&mut self, player_count: u8) {
+        self.player_count = player_count;
+        self.current_player = 0;
     }
 
     pub fn press_key(&mut self, joypad_key: JoypadInputKey) {
         let bit = joypad_key.input_byte_pos();
 
-        let prev_value = self.input_byte & bit;
+        let was_released = self.input_byte & bit != 0;
         self.input_byte &= !bit; // Clearing bit; 0 = pressed
 
-        // If this bit is currently monitored, trigger an interrupt
-        let should_trigger = {
-            if self.selection_mask == 0b10 {
-                // Directions
-                prev_value == 1 && bit < 0x10
-            } else if self.selection_mask == 0b01 {
-                // Buttons
-                prev_value == 1 && bit > 0xF
-            } else {
-                false
-            }
+        // If this bit is currently monitored, its line goes low: trigger an interrupt
+        // (this is also what wakes the CPU up from STOP)
+        let is_selected = if bit < 0x10 {
+            self.is_directions_selected()
+        } else {
+            self.is_buttons_selected()
         };
 
-        if should_trigger {
+        if was_released && is_selected {
             self.raise_interrupt = Some(Interrupt::Joypad);
         }
     }
@@ -0,0 +1,63 @@
+/// How the CPU used an address during an M-cycle, see `MemoryAccess::oam_bug`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OamBugAccess {
+    Read,
+    /// A write, or the IDU incrementing/decrementing a register (16-bit INC/DEC, SP during PUSH)
+    Write,
+    /// A read while the IDU increments/decrements the same register (LD A, (HL+), POP)
+    ReadIdu,
+}
+
+/// Anything the CPU can read from and write to over the address bus.
+///
+/// The CPU only ever talks to memory through this trait, so it can be driven by the real `Bus`
+/// or by a flat 64 KiB test bus (see the SM83 single-instruction tests).
+pub trait MemoryAccess {
+    fn ram_read_byte(&self, address: u16) -> u8;
+    fn ram_write_byte(&mut self, address: u16, byte: u8);
+
+    /// Access made by the CPU for itself (interrupt polling, traces) rather than by the program,
+    /// memory watchpoints do not see it
+    fn read_byte_unwatched(&self, address: u16) -> u8 {
+        self.ram_read_byte(address)
+    }
+
+    fn write_byte_unwatched(&mut self, address: u16, byte: u8) {
+        self.ram_write_byte(address, byte)
+    }
+
+    fn ram_read_word(&self, address: u16) -> u16 {
+        ((self.ram_read_byte(address.wrapping_add(1)) as u16) << 8)
+            | (self.ram_read_byte(address) as u16)
+    }
+
+    fn ram_write_word(&mut self, address: u16, word: u16) {
+        self.ram_write_byte(address, (word & 0xFF) as u8);
+        self.ram_write_byte(address.wrapping_add(1), (word >> 8) as u8);
+    }
+
+    /// Advance the rest of the system (timer, PPU, DMA, ...) by `cycles` T-cycles
+    ///
+    /// Called by the CPU between its memory accesses, so reads and writes see the hardware in the right state.
+    fn tick(&mut self, _cycles: u8) {}
+
+    /// The CPU put `address` on the bus or in its IDU this M-cycle
+    ///
+    /// On DMG hardware this corrupts OAM if the address is in 0xFE00-0xFEFF while the PPU is scanning OAM.
+    fn oam_bug(&mut self, _address: u16, _access: OamBugAccess) {}
+
+    /// STOP was executed: perform the CGB speed switch if one was armed through KEY1 (0xFF4D)
+    ///
+    /// Returns whether the speed was switched, in which case STOP mode is not entered
+    fn speed_switch(&mut self) -> bool {
+        false
+    }
+
+    /// The CPU entered (true) or left (false) STOP mode, during which the rest of the system is not clocked
+    fn set_stop_mode(&mut self, _is_stopped: bool) {}
+
+    /// Whether the CPU is in STOP mode, the bus keeps this state as it stops clocking the rest of the system
+    fn is_stop_mode(&self) -> bool {
+        false
+    }
+}
@@ -1,25 +1,53 @@
+pub mod assembler;
+pub mod boot;
 pub mod bus;
 pub mod cartridge;
 pub mod cpu;
+pub mod disassembler;
 pub mod flags_register;
 pub mod gameboy;
 pub mod instruction;
 pub mod interrupt;
 pub mod joypad;
 pub mod memory;
+pub mod memory_access;
+pub mod model;
+pub mod opcode;
 pub mod ppu;
 pub mod register;
+pub mod scheduler;
+pub mod sgb;
+pub mod symbols;
 pub mod timer;
+pub mod trace;
+pub mod watchpoint;
 
+pub use boot::BootSequence;
 pub use bus::Bus;
 pub use cartridge::CartridgeHeader;
 pub use cpu::CPU;
+pub use disassembler::Disassembler;
 pub use flags_register::Flag;
 pub use flags_register::FlagsRegister;
+pub use gameboy::FrameResult;
 pub use gameboy::GameBoy;
+pub use instruction::BankedAddress;
+pub use instruction::Instruction;
 pub use interrupt::Interrupt;
 pub use joypad::Joypad;
 pub use memory::Memory;
+pub use memory_access::MemoryAccess;
+pub use memory_access::OamBugAccess;
+pub use model::Model;
+pub use opcode::Opcode;
 pub use ppu::PPU;
 pub use register::Registers;
+pub use scheduler::Event;
+pub use scheduler::Scheduler;
+pub use sgb::Sgb;
+pub use symbols::SymbolTable;
 pub use timer::Timer;
+pub use trace::TraceSink;
+pub use watchpoint::Watchpoint;
+pub use watchpoint::WatchpointHit;
+pub use watchpoint::Watchpoints;
@@ -0,0 +1,267 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::{FlagsRegister, Registers};
+
+/// The Game Boy hardware revision being emulated
+///
+/// Only matters when the boot ROM is skipped: every model's boot ROM leaves the CPU registers,
+/// IO registers, DIV counter and VRAM in a slightly different state when it hands over control.
+///
+/// https://gbdev.io/pandocs/Power_Up_Sequence.html
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Model {
+    /// Original Game Boy, early boot ROM revision
+    DMG0,
+    /// Original Game Boy
+    #[default]
+    DMG,
+    /// Game Boy Pocket
+    MGB,
+    /// Super Game Boy
+    SGB,
+    /// Super Game Boy 2
+    SGB2,
+    /// Game Boy Color running a DMG cartridge (DMG compatibility mode)
+    CGB,
+}
+
+/// Values left in the IO registers by every boot ROM
+///
+/// Registers that differ between models (SC, DIV, NR52, STAT, LY, DMA) are handled in `post_boot_io`.
+/// OBP0/OBP1 are never initialized by the boot ROM, so they are left alone.
+const COMMON_POST_BOOT_IO: [(u16, u8); 29] = [
+    (0xFF01, 0x00), // SB
+    (0xFF05, 0x00), // TIMA
+    (0xFF06, 0x00), // TMA
+    (0xFF07, 0xF8), // TAC
+    (0xFF0F, 0xE1), // IF - V-Blank is still pending
+    (0xFF10, 0x80), // NR10
+    (0xFF11, 0xBF), // NR11
+    (0xFF12, 0xF3), // NR12
+    (0xFF13, 0xFF), // NR13
+    (0xFF14, 0xBF), // NR14
+    (0xFF16, 0x3F), // NR21
+    (0xFF17, 0x00), // NR22
+    (0xFF18, 0xFF), // NR23
+    (0xFF19, 0xBF), // NR24
+    (0xFF1A, 0x7F), // NR30
+    (0xFF1B, 0xFF), // NR31
+    (0xFF1C, 0x9F), // NR32
+    (0xFF1D, 0xFF), // NR33
+    (0xFF1E, 0xBF), // NR34
+    (0xFF20, 0xFF), // NR41
+    (0xFF21, 0x00), // NR42
+    (0xFF22, 0x00), // NR43
+    (0xFF23, 0xBF), // NR44
+    (0xFF24, 0x77), // NR50
+    (0xFF25, 0xF3), // NR51
+    (0xFF40, 0x91), // LCDC
+    (0xFF42, 0x00), // SCY
+    (0xFF43, 0x00), // SCX
+    (0xFF47, 0xFC), // BGP
+];
+
+/// The registered trademark symbol drawn next to the logo, stored in the DMG boot ROM (0x00D8)
+const REGISTERED_TILE: [u8; 8] = [0x3C, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x3C];
+
+impl Model {
+    pub const ALL: [Model; 6] = [
+        Model::DMG0,
+        Model::DMG,
+        Model::MGB,
+        Model::SGB,
+        Model::SGB2,
+        Model::CGB,
+    ];
+
+    /// Is this a Super Game Boy (the cartridge runs on an SNES)
+    pub fn is_sgb(&self) -> bool {
+        matches!(self, Model::SGB | Model::SGB2)
+    }
+
+    /// CPU registers at 0x0100, as left by this model's boot ROM
+    ///
+    /// `header` is the cartridge header (0x0100-0x014F), some boot ROMs derive flags/registers from it.
+    pub fn post_boot_registers(&self, header: &[u8]) -> Registers {
+        let header_byte = |address: u16| header_byte(header, address);
+        // DMG/MGB: H and C are left set unless the header checksum is 0x00
+        let checksum_flags: u8 = if header_byte(0x014D) == 0 { 0x80 } else { 0xB0 };
+
+        let (a, f, b, c, d, e, h, l) = match self {
+            Model::DMG0 => (0x01, 0x00, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03),
+            Model::DMG => (0x01, checksum_flags, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D),
+            Model::MGB => (0xFF, checksum_flags, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D),
+            Model::SGB => (0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60),
+            Model::SGB2 => (0xFF, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60),
+            Model::CGB => {
+                // B holds the title checksum for Nintendo published games, which is used to pick a palette
+                let b = if Model::is_nintendo_licensee(header) {
+                    (0x0134..=0x0143)
+                        .fold(0u8, |sum, address| sum.wrapping_add(header_byte(address)))
+                } else {
+                    0x00
+                };
+                // HL is left pointing at the tile map when the palette lookup hits these entries
+                let (h, l) = match b {
+                    0x43 | 0x58 => (0x99, 0x1A),
+                    _ => (0x00, 0x7C),
+                };
+
+                (0x11, 0x80, b, 0x00, 0x00, 0x08, h, l)
+            }
+        };
+
+        Registers {
+            a,
+            b,
+            c,
+            d,
+            e,
+            f: FlagsRegister::from(f),
+            h,
+            l,
+            pc: 0x0100,
+            sp: 0xFFFE,
+        }
+    }
+
+    /// IO registers (address, value) as left by this model's boot ROM
+    pub fn post_boot_io(&self) -> Vec<(u16, u8)> {
+        let mut io = COMMON_POST_BOOT_IO.to_vec();
+
+        io.push((0xFF02, if *self == Model::CGB { 0x7F } else { 0x7E })); // SC
+        io.push((0xFF26, if self.is_sgb() { 0xF0 } else { 0xF1 })); // NR52
+        io.push((0xFF41, if *self == Model::DMG0 { 0x81 } else { 0x85 })); // STAT
+        io.push((0xFF46, if *self == Model::CGB { 0x00 } else { 0xFF })); // DMA
+
+        io
+    }
+
+    /// LY at 0x0100
+    pub fn post_boot_ly(&self) -> u8 {
+        match self {
+            Model::DMG0 => 0x91,
+            _ => 0x00,
+        }
+    }
+
+    /// The full 16-bit system counter (DIV is its upper byte) at 0x0100
+    ///
+    /// The SGB and CGB boot ROMs take a variable amount of time (SNES communication, title lookup),
+    /// so there is no single correct value for them; they fall back to the DMG value.
+    pub fn post_boot_system_counter(&self) -> u16 {
+        match self {
+            Model::DMG0 => 0x1830,
+            _ => 0xABCC,
+        }
+    }
+
+    /// Does the CPU corrupt OAM when its 16-bit registers point into 0xFE00-0xFEFF during OAM scan
+    ///
+    /// Fixed in the CGB, all models with the original DMG CPU are affected.
+    pub fn has_oam_bug(&self) -> bool {
+        *self != Model::CGB
+    }
+
+    /// Does the boot ROM scroll the logo down and play the chime
+    ///
+    /// The SGB boot ROM shows nothing on the Game Boy side, the CGB has its own animation.
+    pub fn has_logo_animation(&self) -> bool {
+        matches!(self, Model::DMG0 | Model::DMG | Model::MGB)
+    }
+
+    /// Does the boot ROM leave the (DMG style) Nintendo logo in VRAM
+    ///
+    /// The CGB boot ROM uses its own, differently scaled, logo tiles.
+    pub fn has_dmg_logo(&self) -> bool {
+        *self != Model::CGB
+    }
+
+    /// VRAM bytes (address, value) written by the boot ROM while displaying the logo
+    ///
+    /// The logo is taken from the cartridge header (0x0104-0x0133), every pixel is doubled in size
+    /// and stored in tiles 1-24, followed by the ® in tile 25. The tile map shows them centered on screen.
+    pub fn post_boot_vram(&self, header: &[u8]) -> Vec<(u16, u8)> {
+        let mut vram = Vec::new();
+        if !self.has_dmg_logo() {
+            return vram;
+        }
+
+        // Tile data - only the low bit plane is used, so every other byte
+        let mut address: u16 = 0x8010;
+        for logo_address in 0x0104..=0x0133 {
+            let logo_byte = header_byte(header, logo_address);
+            for nibble in [logo_byte >> 4, logo_byte & 0xF] {
+                let mut doubled: u8 = 0;
+                for bit in (0..4).rev() {
+                    let pixel = (nibble >> bit) & 0x1;
+                    doubled = (doubled << 2) | (pixel << 1) | pixel;
+                }
+
+                // Each row is also doubled
+                vram.push((address, doubled));
+                vram.push((address + 2, doubled));
+                address += 4;
+            }
+        }
+        for byte in REGISTERED_TILE {
+            vram.push((address, byte));
+            address += 2;
+        }
+
+        // Tile map - two rows of 12 tiles, ® at the end of the first row
+        for (index, tile_id) in (0x01..=0x0C).enumerate() {
+            vram.push((0x9904 + index as u16, tile_id));
+            vram.push((0x9924 + index as u16, tile_id + 0x0C));
+        }
+        vram.push((0x9910, 0x19));
+
+        vram
+    }
+
+    /// Old licensee code 0x01, or 0x33 with new licensee code "01"
+    fn is_nintendo_licensee(header: &[u8]) -> bool {
+        match header_byte(header, 0x014B) {
+            0x01 => true,
+            0x33 => header_byte(header, 0x0144) == b'0' && header_byte(header, 0x0145) == b'1',
+            _ => false,
+        }
+    }
+}
+
+/// Read a byte of the cartridge header (0x0100-0x014F) by its address, missing bytes read as 0
+fn header_byte(header: &[u8], address: u16) -> u8 {
+    header
+        .get((address - 0x0100) as usize)
+        .copied()
+        .unwrap_or(0)
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Model::DMG0 => "DMG0",
+            Model::DMG => "DMG",
+            Model::MGB => "MGB",
+            Model::SGB => "SGB",
+            Model::SGB2 => "SGB2",
+            Model::CGB => "CGB",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Model::ALL
+            .iter()
+            .find(|model| model.to_string().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("Unknown model: {}", s))
+    }
+}
@@ -0,0 +1,707 @@
+//! SM83 opcode table
+//!
+//! Describes every opcode (and every CB-prefixed opcode) once: mnemonic, operands, length, timing and flag effects.
+//! The CPU takes its cycle counts, lengths and immediate operands from here and the disassembler its text,
+//! so that the two can not disagree.
+
+use OperandKind::*;
+
+/// Branch condition of JR/JP/CALL/RET
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    NZ,
+    Z,
+    NC,
+    C,
+}
+
+/// What an operand of an instruction refers to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperandKind {
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+    /// (BC)
+    AddressBC,
+    /// (DE)
+    AddressDE,
+    /// (HL)
+    AddressHL,
+    /// (HL+), HL is incremented after the access
+    AddressHLIncrement,
+    /// (HL-), HL is decremented after the access
+    AddressHLDecrement,
+    /// (C), in the 0xFF00 page
+    HighAddressC,
+    /// 8-bit immediate
+    Immediate8,
+    /// 16-bit immediate
+    Immediate16,
+    /// Signed 8-bit immediate (ADD SP, e8)
+    Signed8,
+    /// SP plus a signed 8-bit immediate (LD HL, SP + e8)
+    SPPlusSigned8,
+    /// Signed 8-bit offset from the address of the next instruction (JR)
+    Relative8,
+    /// (a8), an 8-bit immediate address in the 0xFF00 page
+    HighAddress8,
+    /// (a16), a 16-bit immediate address
+    Address16,
+    Condition(Condition),
+    /// Bit index of BIT/RES/SET
+    Bit(u8),
+    /// Target of RST
+    Vector(u8),
+}
+
+impl OperandKind {
+    /// Number of immediate bytes the operand takes after the opcode
+    pub const fn size(&self) -> u8 {
+        match self {
+            Immediate8 | Signed8 | SPPlusSigned8 | Relative8 | HighAddress8 => 1,
+            Immediate16 | Address16 => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// How an instruction changes one flag
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlagEffect {
+    Unaffected,
+    Reset,
+    Set,
+    /// Depends on the result
+    Affected,
+}
+
+/// Effect of an instruction on the Z, N, H and C flags
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlagEffects {
+    pub zero: FlagEffect,
+    pub subtract: FlagEffect,
+    pub half_carry: FlagEffect,
+    pub carry: FlagEffect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub operands: &'static [OperandKind],
+    /// Bytes, including the opcode (and the CB prefix)
+    pub length: u8,
+    /// T-cycles, when the branch is not taken for conditional instructions
+    pub cycles: u8,
+    /// T-cycles when the branch is taken (same as `cycles` for unconditional instructions)
+    pub branch_cycles: u8,
+    pub flags: FlagEffects,
+}
+
+impl Opcode {
+    pub fn cycles_for(&self, is_branch_taken: bool) -> u8 {
+        if is_branch_taken {
+            self.branch_cycles
+        } else {
+            self.cycles
+        }
+    }
+
+    /// Number of immediate bytes after the opcode
+    pub fn immediate_size(&self) -> u8 {
+        immediate_size(self.operands)
+    }
+
+    pub fn is_conditional(&self) -> bool {
+        self.cycles != self.branch_cycles
+    }
+
+    /// The 11 unused opcodes, which lock up the CPU
+    pub fn is_illegal(&self) -> bool {
+        self.mnemonic == "ILLEGAL"
+    }
+}
+
+/// Get the description of an opcode, `cb_opcode` is the byte following a 0xCB prefix
+pub fn lookup(opcode: u8, cb_opcode: u8) -> &'static Opcode {
+    if opcode == 0xCB {
+        &CB_OPCODES[cb_opcode as usize]
+    } else {
+        &OPCODES[opcode as usize]
+    }
+}
+
+/// Flags are described in the usual "ZNHC" notation: the flag's letter when it depends on the result,
+/// 0/1 when it is reset/set and - when it is left alone
+const fn op(
+    mnemonic: &'static str,
+    operands: &'static [OperandKind],
+    length: u8,
+    cycles: u8,
+    branch_cycles: u8,
+    flags: &'static str,
+) -> Opcode {
+    // The opcode (after the CB prefix, or followed by STOP's second byte) and then the immediate bytes
+    let immediate_size = immediate_size(operands);
+    assert!(length == 1 + immediate_size || (length == 2 && immediate_size == 0));
+
+    let flags = flags.as_bytes();
+    Opcode {
+        mnemonic,
+        operands,
+        length,
+        cycles,
+        branch_cycles,
+        flags: FlagEffects {
+            zero: flag_effect(flags[0]),
+            subtract: flag_effect(flags[1]),
+            half_carry: flag_effect(flags[2]),
+            carry: flag_effect(flags[3]),
+        },
+    }
+}
+
+const fn immediate_size(operands: &[OperandKind]) -> u8 {
+    let mut size = 0;
+    let mut index = 0;
+    while index < operands.len() {
+        size += operands[index].size();
+        index += 1;
+    }
+    size
+}
+
+const fn flag_effect(notation: u8) -> FlagEffect {
+    match notation {
+        b'-' => FlagEffect::Unaffected,
+        b'0' => FlagEffect::Reset,
+        b'1' => FlagEffect::Set,
+        _ => FlagEffect::Affected,
+    }
+}
+
+pub static OPCODES: [Opcode; 256] = [
+    op("NOP", &[], 1, 4, 4, "----"), // 0x00
+    op("LD", &[BC, Immediate16], 3, 12, 12, "----"), // 0x01
+    op("LD", &[AddressBC, A], 1, 8, 8, "----"), // 0x02
+    op("INC", &[BC], 1, 8, 8, "----"), // 0x03
+    op("INC", &[B], 1, 4, 4, "Z0H-"), // 0x04
+    op("DEC", &[B], 1, 4, 4, "Z1H-"), // 0x05
+    op("LD", &[B, Immediate8], 2, 8, 8, "----"), // 0x06
+    op("RLCA", &[], 1, 4, 4, "000C"), // 0x07
+    op("LD", &[Address16, SP], 3, 20, 20, "----"), // 0x08
+    op("ADD", &[HL, BC], 1, 8, 8, "-0HC"), // 0x09
+    op("LD", &[A, AddressBC], 1, 8, 8, "----"), // 0x0A
+    op("DEC", &[BC], 1, 8, 8, "----"), // 0x0B
+    op("INC", &[C], 1, 4, 4, "Z0H-"), // 0x0C
+    op("DEC", &[C], 1, 4, 4, "Z1H-"), // 0x0D
+    op("LD", &[C, Immediate8], 2, 8, 8, "----"), // 0x0E
+    op("RRCA", &[], 1, 4, 4, "000C"), // 0x0F
+    op("STOP", &[], 2, 4, 4, "----"), // 0x10
+    op("LD", &[DE, Immediate16], 3, 12, 12, "----"), // 0x11
+    op("LD", &[AddressDE, A], 1, 8, 8, "----"), // 0x12
+    op("INC", &[DE], 1, 8, 8, "----"), // 0x13
+    op("INC", &[D], 1, 4, 4, "Z0H-"), // 0x14
+    op("DEC", &[D], 1, 4, 4, "Z1H-"), // 0x15
+    op("LD", &[D, Immediate8], 2, 8, 8, "----"), // 0x16
+    op("RLA", &[], 1, 4, 4, "000C"), // 0x17
+    op("JR", &[Relative8], 2, 12, 12, "----"), // 0x18
+    op("ADD", &[HL, DE], 1, 8, 8, "-0HC"), // 0x19
+    op("LD", &[A, AddressDE], 1, 8, 8, "----"), // 0x1A
+    op("DEC", &[DE], 1, 8, 8, "----"), // 0x1B
+    op("INC", &[E], 1, 4, 4, "Z0H-"), // 0x1C
+    op("DEC", &[E], 1, 4, 4, "Z1H-"), // 0x1D
+    op("LD", &[E, Immediate8], 2, 8, 8, "----"), // 0x1E
+    op("RRA", &[], 1, 4, 4, "000C"), // 0x1F
+    op("JR", &[Condition(Condition::NZ), Relative8], 2, 8, 12, "----"), // 0x20
+    op("LD", &[HL, Immediate16], 3, 12, 12, "----"), // 0x21
+    op("LD", &[AddressHLIncrement, A], 1, 8, 8, "----"), // 0x22
+    op("INC", &[HL], 1, 8, 8, "----"), // 0x23
+    op("INC", &[H], 1, 4, 4, "Z0H-"), // 0x24
+    op("DEC", &[H], 1, 4, 4, "Z1H-"), // 0x25
+    op("LD", &[H, Immediate8], 2, 8, 8, "----"), // 0x26
+    op("DAA", &[], 1, 4, 4, "Z-0C"), // 0x27
+    op("JR", &[Condition(Condition::Z), Relative8], 2, 8, 12, "----"), // 0x28
+    op("ADD", &[HL, HL], 1, 8, 8, "-0HC"), // 0x29
+    op("LD", &[A, AddressHLIncrement], 1, 8, 8, "----"), // 0x2A
+    op("DEC", &[HL], 1, 8, 8, "----"), // 0x2B
+    op("INC", &[L], 1, 4, 4, "Z0H-"), // 0x2C
+    op("DEC", &[L], 1, 4, 4, "Z1H-"), // 0x2D
+    op("LD", &[L, Immediate8], 2, 8, 8, "----"), // 0x2E
+    op("CPL", &[], 1, 4, 4, "-11-"), // 0x2F
+    op("JR", &[Condition(Condition::NC), Relative8], 2, 8, 12, "----"), // 0x30
+    op("LD", &[SP, Immediate16], 3, 12, 12, "----"), // 0x31
+    op("LD", &[AddressHLDecrement, A], 1, 8, 8, "----"), // 0x32
+    op("INC", &[SP], 1, 8, 8, "----"), // 0x33
+    op("INC", &[AddressHL], 1, 12, 12, "Z0H-"), // 0x34
+    op("DEC", &[AddressHL], 1, 12, 12, "Z1H-"), // 0x35
+    op("LD", &[AddressHL, Immediate8], 2, 12, 12, "----"), // 0x36
+    op("SCF", &[], 1, 4, 4, "-001"), // 0x37
+    op("JR", &[Condition(Condition::C), Relative8], 2, 8, 12, "----"), // 0x38
+    op("ADD", &[HL, SP], 1, 8, 8, "-0HC"), // 0x39
+    op("LD", &[A, AddressHLDecrement], 1, 8, 8, "----"), // 0x3A
+    op("DEC", &[SP], 1, 8, 8, "----"), // 0x3B
+    op("INC", &[A], 1, 4, 4, "Z0H-"), // 0x3C
+    op("DEC", &[A], 1, 4, 4, "Z1H-"), // 0x3D
+    op("LD", &[A, Immediate8], 2, 8, 8, "----"), // 0x3E
+    op("CCF", &[], 1, 4, 4, "-00C"), // 0x3F
+    op("LD", &[B, B], 1, 4, 4, "----"), // 0x40
+    op("LD", &[B, C], 1, 4, 4, "----"), // 0x41
+    op("LD", &[B, D], 1, 4, 4, "----"), // 0x42
+    op("LD", &[B, E], 1, 4, 4, "----"), // 0x43
+    op("LD", &[B, H], 1, 4, 4, "----"), // 0x44
+    op("LD", &[B, L], 1, 4, 4, "----"), // 0x45
+    op("LD", &[B, AddressHL], 1, 8, 8, "----"), // 0x46
+    op("LD", &[B, A], 1, 4, 4, "----"), // 0x47
+    op("LD", &[C, B], 1, 4, 4, "----"), // 0x48
+    op("LD", &[C, C], 1, 4, 4, "----"), // 0x49
+    op("LD", &[C, D], 1, 4, 4, "----"), // 0x4A
+    op("LD", &[C, E], 1, 4, 4, "----"), // 0x4B
+    op("LD", &[C, H], 1, 4, 4, "----"), // 0x4C
+    op("LD", &[C, L], 1, 4, 4, "----"), // 0x4D
+    op("LD", &[C, AddressHL], 1, 8, 8, "----"), // 0x4E
+    op("LD", &[C, A], 1, 4, 4, "----"), // 0x4F
+    op("LD", &[D, B], 1, 4, 4, "----"), // 0x50
+    op("LD", &[D, C], 1, 4, 4, "----"), // 0x51
+    op("LD", &[D, D], 1, 4, 4, "----"), // 0x52
+    op("LD", &[D, E], 1, 4, 4, "----"), // 0x53
+    op("LD", &[D, H], 1, 4, 4, "----"), // 0x54
+    op("LD", &[D, L], 1, 4, 4, "----"), // 0x55
+    op("LD", &[D, AddressHL], 1, 8, 8, "----"), // 0x56
+    op("LD", &[D, A], 1, 4, 4, "----"), // 0x57
+    op("LD", &[E, B], 1, 4, 4, "----"), // 0x58
+    op("LD", &[E, C], 1, 4, 4, "----"), // 0x59
+    op("LD", &[E, D], 1, 4, 4, "----"), // 0x5A
+    op("LD", &[E, E], 1, 4, 4, "----"), // 0x5B
+    op("LD", &[E, H], 1, 4, 4, "----"), // 0x5C
+    op("LD", &[E, L], 1, 4, 4, "----"), // 0x5D
+    op("LD", &[E, AddressHL], 1, 8, 8, "----"), // 0x5E
+    op("LD", &[E, A], 1, 4, 4, "----"), // 0x5F
+    op("LD", &[H, B], 1, 4, 4, "----"), // 0x60
+    op("LD", &[H, C], 1, 4, 4, "----"), // 0x61
+    op("LD", &[H, D], 1, 4, 4, "----"), // 0x62
+    op("LD", &[H, E], 1, 4, 4, "----"), // 0x63
+    op("LD", &[H, H], 1, 4, 4, "----"), // 0x64
+    op("LD", &[H, L], 1, 4, 4, "----"), // 0x65
+    op("LD", &[H, AddressHL], 1, 8, 8, "----"), // 0x66
+    op("LD", &[H, A], 1, 4, 4, "----"), // 0x67
+    op("LD", &[L, B], 1, 4, 4, "----"), // 0x68
+    op("LD", &[L, C], 1, 4, 4, "----"), // 0x69
+    op("LD", &[L, D], 1, 4, 4, "----"), // 0x6A
+    op("LD", &[L, E], 1, 4, 4, "----"), // 0x6B
+    op("LD", &[L, H], 1, 4, 4, "----"), // 0x6C
+    op("LD", &[L, L], 1, 4, 4, "----"), // 0x6D
+    op("LD", &[L, AddressHL], 1, 8, 8, "----"), // 0x6E
+    op("LD", &[L, A], 1, 4, 4, "----"), // 0x6F
+    op("LD", &[AddressHL, B], 1, 8, 8, "----"), // 0x70
+    op("LD", &[AddressHL, C], 1, 8, 8, "----"), // 0x71
+    op("LD", &[AddressHL, D], 1, 8, 8, "----"), // 0x72
+    op("LD", &[AddressHL, E], 1, 8, 8, "----"), // 0x73
+    op("LD", &[AddressHL, H], 1, 8, 8, "----"), // 0x74
+    op("LD", &[AddressHL, L], 1, 8, 8, "----"), // 0x75
+    op("HALT", &[], 1, 4, 4, "----"), // 0x76
+    op("LD", &[AddressHL, A], 1, 8, 8, "----"), // 0x77
+    op("LD", &[A, B], 1, 4, 4, "----"), // 0x78
+    op("LD", &[A, C], 1, 4, 4, "----"), // 0x79
+    op("LD", &[A, D], 1, 4, 4, "----"), // 0x7A
+    op("LD", &[A, E], 1, 4, 4, "----"), // 0x7B
+    op("LD", &[A, H], 1, 4, 4, "----"), // 0x7C
+    op("LD", &[A, L], 1, 4, 4, "----"), // 0x7D
+    op("LD", &[A, AddressHL], 1, 8, 8, "----"), // 0x7E
+    op("LD", &[A, A], 1, 4, 4, "----"), // 0x7F
+    op("ADD", &[A, B], 1, 4, 4, "Z0HC"), // 0x80
+    op("ADD", &[A, C], 1, 4, 4, "Z0HC"), // 0x81
+    op("ADD", &[A, D], 1, 4, 4, "Z0HC"), // 0x82
+    op("ADD", &[A, E], 1, 4, 4, "Z0HC"), // 0x83
+    op("ADD", &[A, H], 1, 4, 4, "Z0HC"), // 0x84
+    op("ADD", &[A, L], 1, 4, 4, "Z0HC"), // 0x85
+    op("ADD", &[A, AddressHL], 1, 8, 8, "Z0HC"), // 0x86
+    op("ADD", &[A, A], 1, 4, 4, "Z0HC"), // 0x87
+    op("ADC", &[A, B], 1, 4, 4, "Z0HC"), // 0x88
+    op("ADC", &[A, C], 1, 4, 4, "Z0HC"), // 0x89
+    op("ADC", &[A, D], 1, 4, 4, "Z0HC"), // 0x8A
+    op("ADC", &[A, E], 1, 4, 4, "Z0HC"), // 0x8B
+    op("ADC", &[A, H], 1, 4, 4, "Z0HC"), // 0x8C
+    op("ADC", &[A, L], 1, 4, 4, "Z0HC"), // 0x8D
+    op("ADC", &[A, AddressHL], 1, 8, 8, "Z0HC"), // 0x8E
+    op("ADC", &[A, A], 1, 4, 4, "Z0HC"), // 0x8F
+    op("SUB", &[B], 1, 4, 4, "Z1HC"), // 0x90
+    op("SUB", &[C], 1, 4, 4, "Z1HC"), // 0x91
+    op("SUB", &[D], 1, 4, 4, "Z1HC"), // 0x92
+    op("SUB", &[E], 1, 4, 4, "Z1HC"), // 0x93
+    op("SUB", &[H], 1, 4, 4, "Z1HC"), // 0x94
+    op("SUB", &[L], 1, 4, 4, "Z1HC"), // 0x95
+    op("SUB", &[AddressHL], 1, 8, 8, "Z1HC"), // 0x96
+    op("SUB", &[A], 1, 4, 4, "Z1HC"), // 0x97
+    op("SBC", &[A, B], 1, 4, 4, "Z1HC"), // 0x98
+    op("SBC", &[A, C], 1, 4, 4, "Z1HC"), // 0x99
+    op("SBC", &[A, D], 1, 4, 4, "Z1HC"), // 0x9A
+    op("SBC", &[A, E], 1, 4, 4, "Z1HC"), // 0x9B
+    op("SBC", &[A, H], 1, 4, 4, "Z1HC"), // 0x9C
+    op("SBC", &[A, L], 1, 4, 4, "Z1HC"), // 0x9D
+    op("SBC", &[A, AddressHL], 1, 8, 8, "Z1HC"), // 0x9E
+    op("SBC", &[A, A], 1, 4, 4, "Z1HC"), // 0x9F
+    op("AND", &[B], 1, 4, 4, "Z010"), // 0xA0
+    op("AND", &[C], 1, 4, 4, "Z010"), // 0xA1
+    op("AND", &[D], 1, 4, 4, "Z010"), // 0xA2
+    op("AND", &[E], 1, 4, 4, "Z010"), // 0xA3
+    op("AND", &[H], 1, 4, 4, "Z010"), // 0xA4
+    op("AND", &[L], 1, 4, 4, "Z010"), // 0xA5
+    op("AND", &[AddressHL], 1, 8, 8, "Z010"), // 0xA6
+    op("AND", &[A], 1, 4, 4, "Z010"), // 0xA7
+    op("XOR", &[B], 1, 4, 4, "Z000"), // 0xA8
+    op("XOR", &[C], 1, 4, 4, "Z000"), // 0xA9
+    op("XOR", &[D], 1, 4, 4, "Z000"), // 0xAA
+    op("XOR", &[E], 1, 4, 4, "Z000"), // 0xAB
+    op("XOR", &[H], 1, 4, 4, "Z000"), // 0xAC
+    op("XOR", &[L], 1, 4, 4, "Z000"), // 0xAD
+    op("XOR", &[AddressHL], 1, 8, 8, "Z000"), // 0xAE
+    op("XOR", &[A], 1, 4, 4, "Z000"), // 0xAF
+    op("OR", &[B], 1, 4, 4, "Z000"), // 0xB0
+    op("OR", &[C], 1, 4, 4, "Z000"), // 0xB1
+    op("OR", &[D], 1, 4, 4, "Z000"), // 0xB2
+    op("OR", &[E], 1, 4, 4, "Z000"), // 0xB3
+    op("OR", &[H], 1, 4, 4, "Z000"), // 0xB4
+    op("OR", &[L], 1, 4, 4, "Z000"), // 0xB5
+    op("OR", &[AddressHL], 1, 8, 8, "Z000"), // 0xB6
+    op("OR", &[A], 1, 4, 4, "Z000"), // 0xB7
+    op("CP", &[B], 1, 4, 4, "Z1HC"), // 0xB8
+    op("CP", &[C], 1, 4, 4, "Z1HC"), // 0xB9
+    op("CP", &[D], 1, 4, 4, "Z1HC"), // 0xBA
+    op("CP", &[E], 1, 4, 4, "Z1HC"), // 0xBB
+    op("CP", &[H], 1, 4, 4, "Z1HC"), // 0xBC
+    op("CP", &[L], 1, 4, 4, "Z1HC"), // 0xBD
+    op("CP", &[AddressHL], 1, 8, 8, "Z1HC"), // 0xBE
+    op("CP", &[A], 1, 4, 4, "Z1HC"), // 0xBF
+    op("RET", &[Condition(Condition::NZ)], 1, 8, 20, "----"), // 0xC0
+    op("POP", &[BC], 1, 12, 12, "----"), // 0xC1
+    op("JP", &[Condition(Condition::NZ), Immediate16], 3, 12, 16, "----"), // 0xC2
+    op("JP", &[Immediate16], 3, 16, 16, "----"), // 0xC3
+    op("CALL", &[Condition(Condition::NZ), Immediate16], 3, 12, 24, "----"), // 0xC4
+    op("PUSH", &[BC], 1, 16, 16, "----"), // 0xC5
+    op("ADD", &[A, Immediate8], 2, 8, 8, "Z0HC"), // 0xC6
+    op("RST", &[Vector(0x00)], 1, 16, 16, "----"), // 0xC7
+    op("RET", &[Condition(Condition::Z)], 1, 8, 20, "----"), // 0xC8
+    op("RET", &[], 1, 16, 16, "----"), // 0xC9
+    op("JP", &[Condition(Condition::Z), Immediate16], 3, 12, 16, "----"), // 0xCA
+    op("PREFIX", &[], 1, 4, 4, "----"), // 0xCB
+    op("CALL", &[Condition(Condition::Z), Immediate16], 3, 12, 24, "----"), // 0xCC
+    op("CALL", &[Immediate16], 3, 24, 24, "----"), // 0xCD
+    op("ADC", &[A, Immediate8], 2, 8, 8, "Z0HC"), // 0xCE
+    op("RST", &[Vector(0x08)], 1, 16, 16, "----"), // 0xCF
+    op("RET", &[Condition(Condition::NC)], 1, 8, 20, "----"), // 0xD0
+    op("POP", &[DE], 1, 12, 12, "----"), // 0xD1
+    op("JP", &[Condition(Condition::NC), Immediate16], 3, 12, 16, "----"), // 0xD2
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xD3
+    op("CALL", &[Condition(Condition::NC), Immediate16], 3, 12, 24, "----"), // 0xD4
+    op("PUSH", &[DE], 1, 16, 16, "----"), // 0xD5
+    op("SUB", &[Immediate8], 2, 8, 8, "Z1HC"), // 0xD6
+    op("RST", &[Vector(0x10)], 1, 16, 16, "----"), // 0xD7
+    op("RET", &[Condition(Condition::C)], 1, 8, 20, "----"), // 0xD8
+    op("RETI", &[], 1, 16, 16, "----"), // 0xD9
+    op("JP", &[Condition(Condition::C), Immediate16], 3, 12, 16, "----"), // 0xDA
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xDB
+    op("CALL", &[Condition(Condition::C), Immediate16], 3, 12, 24, "----"), // 0xDC
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xDD
+    op("SBC", &[A, Immediate8], 2, 8, 8, "Z1HC"), // 0xDE
+    op("RST", &[Vector(0x18)], 1, 16, 16, "----"), // 0xDF
+    op("LDH", &[HighAddress8, A], 2, 12, 12, "----"), // 0xE0
+    op("POP", &[HL], 1, 12, 12, "----"), // 0xE1
+    op("LD", &[HighAddressC, A], 1, 8, 8, "----"), // 0xE2
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xE3
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xE4
+    op("PUSH", &[HL], 1, 16, 16, "----"), // 0xE5
+    op("AND", &[Immediate8], 2, 8, 8, "Z010"), // 0xE6
+    op("RST", &[Vector(0x20)], 1, 16, 16, "----"), // 0xE7
+    op("ADD", &[SP, Signed8], 2, 16, 16, "00HC"), // 0xE8
+    op("JP", &[HL], 1, 4, 4, "----"), // 0xE9
+    op("LD", &[Address16, A], 3, 16, 16, "----"), // 0xEA
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xEB
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xEC
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xED
+    op("XOR", &[Immediate8], 2, 8, 8, "Z000"), // 0xEE
+    op("RST", &[Vector(0x28)], 1, 16, 16, "----"), // 0xEF
+    op("LDH", &[A, HighAddress8], 2, 12, 12, "----"), // 0xF0
+    op("POP", &[AF], 1, 12, 12, "ZNHC"), // 0xF1
+    op("LD", &[A, HighAddressC], 1, 8, 8, "----"), // 0xF2
+    op("DI", &[], 1, 4, 4, "----"), // 0xF3
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xF4
+    op("PUSH", &[AF], 1, 16, 16, "----"), // 0xF5
+    op("OR", &[Immediate8], 2, 8, 8, "Z000"), // 0xF6
+    op("RST", &[Vector(0x30)], 1, 16, 16, "----"), // 0xF7
+    op("LD", &[HL, SPPlusSigned8], 2, 12, 12, "00HC"), // 0xF8
+    op("LD", &[SP, HL], 1, 8, 8, "----"), // 0xF9
+    op("LD", &[A, Address16], 3, 16, 16, "----"), // 0xFA
+    op("EI", &[], 1, 4, 4, "----"), // 0xFB
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xFC
+    op("ILLEGAL", &[], 1, 4, 4, "----"), // 0xFD
+    op("CP", &[Immediate8], 2, 8, 8, "Z1HC"), // 0xFE
+    op("RST", &[Vector(0x38)], 1, 16, 16, "----"), // 0xFF
+];
+
+pub static CB_OPCODES: [Opcode; 256] = [
+    op("RLC", &[B], 2, 8, 8, "Z00C"), // 0x00
+    op("RLC", &[C], 2, 8, 8, "Z00C"), // 0x01
+    op("RLC", &[D], 2, 8, 8, "Z00C"), // 0x02
+    op("RLC", &[E], 2, 8, 8, "Z00C"), // 0x03
+    op("RLC", &[H], 2, 8, 8, "Z00C"), // 0x04
+    op("RLC", &[L], 2, 8, 8, "Z00C"), // 0x05
+    op("RLC", &[AddressHL], 2, 16, 16, "Z00C"), // 0x06
+    op("RLC", &[A], 2, 8, 8, "Z00C"), // 0x07
+    op("RRC", &[B], 2, 8, 8, "Z00C"), // 0x08
+    op("RRC", &[C], 2, 8, 8, "Z00C"), // 0x09
+    op("RRC", &[D], 2, 8, 8, "Z00C"), // 0x0A
+    op("RRC", &[E], 2, 8, 8, "Z00C"), // 0x0B
+    op("RRC", &[H], 2, 8, 8, "Z00C"), // 0x0C
+    op("RRC", &[L], 2, 8, 8, "Z00C"), // 0x0D
+    op("RRC", &[AddressHL], 2, 16, 16, "Z00C"), // 0x0E
+    op("RRC", &[A], 2, 8, 8, "Z00C"), // 0x0F
+    op("RL", &[B], 2, 8, 8, "Z00C"), // 0x10
+    op("RL", &[C], 2, 8, 8, "Z00C"), // 0x11
+    op("RL", &[D], 2, 8, 8, "Z00C"), // 0x12
+    op("RL", &[E], 2, 8, 8, "Z00C"), // 0x13
+    op("RL", &[H], 2, 8, 8, "Z00C"), // 0x14
+    op("RL", &[L], 2, 8, 8, "Z00C"), // 0x15
+    op("RL", &[AddressHL], 2, 16, 16, "Z00C"), // 0x16
+    op("RL", &[A], 2, 8, 8, "Z00C"), // 0x17
+    op("RR", &[B], 2, 8, 8, "Z00C"), // 0x18
+    op("RR", &[C], 2, 8, 8, "Z00C"), // 0x19
+    op("RR", &[D], 2, 8, 8, "Z00C"), // 0x1A
+    op("RR", &[E], 2, 8, 8, "Z00C"), // 0x1B
+    op("RR", &[H], 2, 8, 8, "Z00C"), // 0x1C
+    op("RR", &[L], 2, 8, 8, "Z00C"), // 0x1D
+    op("RR", &[AddressHL], 2, 16, 16, "Z00C"), // 0x1E
+    op("RR", &[A], 2, 8, 8, "Z00C"), // 0x1F
+    op("SLA", &[B], 2, 8, 8, "Z00C"), // 0x20
+    op("SLA", &[C], 2, 8, 8, "Z00C"), // 0x21
+    op("SLA", &[D], 2, 8, 8, "Z00C"), // 0x22
+    op("SLA", &[E], 2, 8, 8, "Z00C"), // 0x23
+    op("SLA", &[H], 2, 8, 8, "Z00C"), // 0x24
+    op("SLA", &[L], 2, 8, 8, "Z00C"), // 0x25
+    op("SLA", &[AddressHL], 2, 16, 16, "Z00C"), // 0x26
+    op("SLA", &[A], 2, 8, 8, "Z00C"), // 0x27
+    op("SRA", &[B], 2, 8, 8, "Z00C"), // 0x28
+    op("SRA", &[C], 2, 8, 8, "Z00C"), // 0x29
+    op("SRA", &[D], 2, 8, 8, "Z00C"), // 0x2A
+    op("SRA", &[E], 2, 8, 8, "Z00C"), // 0x2B
+    op("SRA", &[H], 2, 8, 8, "Z00C"), // 0x2C
+    op("SRA", &[L], 2, 8, 8, "Z00C"), // 0x2D
+    op("SRA", &[AddressHL], 2, 16, 16, "Z00C"), // 0x2E
+    op("SRA", &[A], 2, 8, 8, "Z00C"), // 0x2F
+    op("SWAP", &[B], 2, 8, 8, "Z000"), // 0x30
+    op("SWAP", &[C], 2, 8, 8, "Z000"), // 0x31
+    op("SWAP", &[D], 2, 8, 8, "Z000"), // 0x32
+    op("SWAP", &[E], 2, 8, 8, "Z000"), // 0x33
+    op("SWAP", &[H], 2, 8, 8, "Z000"), // 0x34
+    op("SWAP", &[L], 2, 8, 8, "Z000"), // 0x35
+    op("SWAP", &[AddressHL], 2, 16, 16, "Z000"), // 0x36
+    op("SWAP", &[A], 2, 8, 8, "Z000"), // 0x37
+    op("SRL", &[B], 2, 8, 8, "Z00C"), // 0x38
+    op("SRL", &[C], 2, 8, 8, "Z00C"), // 0x39
+    op("SRL", &[D], 2, 8, 8, "Z00C"), // 0x3A
+    op("SRL", &[E], 2, 8, 8, "Z00C"), // 0x3B
+    op("SRL", &[H], 2, 8, 8, "Z00C"), // 0x3C
+    op("SRL", &[L], 2, 8, 8, "Z00C"), // 0x3D
+    op("SRL", &[AddressHL], 2, 16, 16, "Z00C"), // 0x3E
+    op("SRL", &[A], 2, 8, 8, "Z00C"), // 0x3F
+    op("BIT", &[Bit(0), B], 2, 8, 8, "Z01-"), // 0x40
+    op("BIT", &[Bit(0), C], 2, 8, 8, "Z01-"), // 0x41
+    op("BIT", &[Bit(0), D], 2, 8, 8, "Z01-"), // 0x42
+    op("BIT", &[Bit(0), E], 2, 8, 8, "Z01-"), // 0x43
+    op("BIT", &[Bit(0), H], 2, 8, 8, "Z01-"), // 0x44
+    op("BIT", &[Bit(0), L], 2, 8, 8, "Z01-"), // 0x45
+    op("BIT", &[Bit(0), AddressHL], 2, 12, 12, "Z01-"), // 0x46
+    op("BIT", &[Bit(0), A], 2, 8, 8, "Z01-"), // 0x47
+    op("BIT", &[Bit(1), B], 2, 8, 8, "Z01-"), // 0x48
+    op("BIT", &[Bit(1), C], 2, 8, 8, "Z01-"), // 0x49
+    op("BIT", &[Bit(1), D], 2, 8, 8, "Z01-"), // 0x4A
+    op("BIT", &[Bit(1), E], 2, 8, 8, "Z01-"), // 0x4B
+    op("BIT", &[Bit(1), H], 2, 8, 8, "Z01-"), // 0x4C
+    op("BIT", &[Bit(1), L], 2, 8, 8, "Z01-"), // 0x4D
+    op("BIT", &[Bit(1), AddressHL], 2, 12, 12, "Z01-"), // 0x4E
+    op("BIT", &[Bit(1), A], 2, 8, 8, "Z01-"), // 0x4F
+    op("BIT", &[Bit(2), B], 2, 8, 8, "Z01-"), // 0x50
+    op("BIT", &[Bit(2), C], 2, 8, 8, "Z01-"), // 0x51
+    op("BIT", &[Bit(2), D], 2, 8, 8, "Z01-"), // 0x52
+    op("BIT", &[Bit(2), E], 2, 8, 8, "Z01-"), // 0x53
+    op("BIT", &[Bit(2), H], 2, 8, 8, "Z01-"), // 0x54
+    op("BIT", &[Bit(2), L], 2, 8, 8, "Z01-"), // 0x55
+    op("BIT", &[Bit(2), AddressHL], 2, 12, 12, "Z01-"), // 0x56
+    op("BIT", &[Bit(2), A], 2, 8, 8, "Z01-"), // 0x57
+    op("BIT", &[Bit(3), B], 2, 8, 8, "Z01-"), // 0x58
+    op("BIT", &[Bit(3), C], 2, 8, 8, "Z01-"), // 0x59
+    op("BIT", &[Bit(3), D], 2, 8, 8, "Z01-"), // 0x5A
+    op("BIT", &[Bit(3), E], 2, 8, 8, "Z01-"), // 0x5B
+    op("BIT", &[Bit(3), H], 2, 8, 8, "Z01-"), // 0x5C
+    op("BIT", &[Bit(3), L], 2, 8, 8, "Z01-"), // 0x5D
+    op("BIT", &[Bit(3), AddressHL], 2, 12, 12, "Z01-"), // 0x5E
+    op("BIT", &[Bit(3), A], 2, 8, 8, "Z01-"), // 0x5F
+    op("BIT", &[Bit(4), B], 2, 8, 8, "Z01-"), // 0x60
+    op("BIT", &[Bit(4), C], 2, 8, 8, "Z01-"), // 0x61
+    op("BIT", &[Bit(4), D], 2, 8, 8, "Z01-"), // 0x62
+    op("BIT", &[Bit(4), E], 2, 8, 8, "Z01-"), // 0x63
+    op("BIT", &[Bit(4), H], 2, 8, 8, "Z01-"), // 0x64
+    op("BIT", &[Bit(4), L], 2, 8, 8, "Z01-"), // 0x65
+    op("BIT", &[Bit(4), AddressHL], 2, 12, 12, "Z01-"), // 0x66
+    op("BIT", &[Bit(4), A], 2, 8, 8, "Z01-"), // 0x67
+    op("BIT", &[Bit(5), B], 2, 8, 8, "Z01-"), // 0x68
+    op("BIT", &[Bit(5), C], 2, 8, 8, "Z01-"), // 0x69
+    op("BIT", &[Bit(5), D], 2, 8, 8, "Z01-"), // 0x6A
+    op("BIT", &[Bit(5), E], 2, 8, 8, "Z01-"), // 0x6B
+    op("BIT", &[Bit(5), H], 2, 8, 8, "Z01-"), // 0x6C
+    op("BIT", &[Bit(5), L], 2, 8, 8, "Z01-"), // 0x6D
+    op("BIT", &[Bit(5), AddressHL], 2, 12, 12, "Z01-"), // 0x6E
+    op("BIT", &[Bit(5), A], 2, 8, 8, "Z01-"), // 0x6F
+    op("BIT", &[Bit(6), B], 2, 8, 8, "Z01-"), // 0x70
+    op("BIT", &[Bit(6), C], 2, 8, 8, "Z01-"), // 0x71
+    op("BIT", &[Bit(6), D], 2, 8, 8, "Z01-"), // 0x72
+    op("BIT", &[Bit(6), E], 2, 8, 8, "Z01-"), // 0x73
+    op("BIT", &[Bit(6), H], 2, 8, 8, "Z01-"), // 0x74
+    op("BIT", &[Bit(6), L], 2, 8, 8, "Z01-"), // 0x75
+    op("BIT", &[Bit(6), AddressHL], 2, 12, 12, "Z01-"), // 0x76
+    op("BIT", &[Bit(6), A], 2, 8, 8, "Z01-"), // 0x77
+    op("BIT", &[Bit(7), B], 2, 8, 8, "Z01-"), // 0x78
+    op("BIT", &[Bit(7), C], 2, 8, 8, "Z01-"), // 0x79
+    op("BIT", &[Bit(7), D], 2, 8, 8, "Z01-"), // 0x7A
+    op("BIT", &[Bit(7), E], 2, 8, 8, "Z01-"), // 0x7B
+    op("BIT", &[Bit(7), H], 2, 8, 8, "Z01-"), // 0x7C
+    op("BIT", &[Bit(7), L], 2, 8, 8, "Z01-"), // 0x7D
+    op("BIT", &[Bit(7), AddressHL], 2, 12, 12, "Z01-"), // 0x7E
+    op("BIT", &[Bit(7), A], 2, 8, 8, "Z01-"), // 0x7F
+    op("RES", &[Bit(0), B], 2, 8, 8, "----"), // 0x80
+    op("RES", &[Bit(0), C], 2, 8, 8, "----"), // 0x81
+    op("RES", &[Bit(0), D], 2, 8, 8, "----"), // 0x82
+    op("RES", &[Bit(0), E], 2, 8, 8, "----"), // 0x83
+    op("RES", &[Bit(0), H], 2, 8, 8, "----"), // 0x84
+    op("RES", &[Bit(0), L], 2, 8, 8, "----"), // 0x85
+    op("RES", &[Bit(0), AddressHL], 2, 16, 16, "----"), // 0x86
+    op("RES", &[Bit(0), A], 2, 8, 8, "----"), // 0x87
+    op("RES", &[Bit(1), B], 2, 8, 8, "----"), // 0x88
+    op("RES", &[Bit(1), C], 2, 8, 8, "----"), // 0x89
+    op("RES", &[Bit(1), D], 2, 8, 8, "----"), // 0x8A
+    op("RES", &[Bit(1), E], 2, 8, 8, "----"), // 0x8B
+    op("RES", &[Bit(1), H], 2, 8, 8, "----"), // 0x8C
+    op("RES", &[Bit(1), L], 2, 8, 8, "----"), // 0x8D
+    op("RES", &[Bit(1), AddressHL], 2, 16, 16, "----"), // 0x8E
+    op("RES", &[Bit(1), A], 2, 8, 8, "----"), // 0x8F
+    op("RES", &[Bit(2), B], 2, 8, 8, "----"), // 0x90
+    op("RES", &[Bit(2), C], 2, 8, 8, "----"), // 0x91
+    op("RES", &[Bit(2), D], 2, 8, 8, "----"), // 0x92
+    op("RES", &[Bit(2), E], 2, 8, 8, "----"), // 0x93
+    op("RES", &[Bit(2), H], 2, 8, 8, "----"), // 0x94
+    op("RES", &[Bit(2), L], 2, 8, 8, "----"), // 0x95
+    op("RES", &[Bit(2), AddressHL], 2, 16, 16, "----"), // 0x96
+    op("RES", &[Bit(2), A], 2, 8, 8, "----"), // 0x97
+    op("RES", &[Bit(3), B], 2, 8, 8, "----"), // 0x98
+    op("RES", &[Bit(3), C], 2, 8, 8, "----"), // 0x99
+    op("RES", &[Bit(3), D], 2, 8, 8, "----"), // 0x9A
+    op("RES", &[Bit(3), E], 2, 8, 8, "----"), // 0x9B
+    op("RES", &[Bit(3), H], 2, 8, 8, "----"), // 0x9C
+    op("RES", &[Bit(3), L], 2, 8, 8, "----"), // 0x9D
+    op("RES", &[Bit(3), AddressHL], 2, 16, 16, "----"), // 0x9E
+    op("RES", &[Bit(3), A], 2, 8, 8, "----"), // 0x9F
+    op("RES", &[Bit(4), B], 2, 8, 8, "----"), // 0xA0
+    op("RES", &[Bit(4), C], 2, 8, 8, "----"), // 0xA1
+    op("RES", &[Bit(4), D], 2, 8, 8, "----"), // 0xA2
+    op("RES", &[Bit(4), E], 2, 8, 8, "----"), // 0xA3
+    op("RES", &[Bit(4), H], 2, 8, 8, "----"), // 0xA4
+    op("RES", &[Bit(4), L], 2, 8, 8, "----"), // 0xA5
+    op("RES", &[Bit(4), AddressHL], 2, 16, 16, "----"), // 0xA6
+    op("RES", &[Bit(4), A], 2, 8, 8, "----"), // 0xA7
+    op("RES", &[Bit(5), B], 2, 8, 8, "----"), // 0xA8
+    op("RES", &[Bit(5), C], 2, 8, 8, "----"), // 0xA9
+    op("RES", &[Bit(5), D], 2, 8, 8, "----"), // 0xAA
+    op("RES", &[Bit(5), E], 2, 8, 8, "----"), // 0xAB
+    op("RES", &[Bit(5), H], 2, 8, 8, "----"), // 0xAC
+    op("RES", &[Bit(5), L], 2, 8, 8, "----"), // 0xAD
+    op("RES", &[Bit(5), AddressHL], 2, 16, 16, "----"), // 0xAE
+    op("RES", &[Bit(5), A], 2, 8, 8, "----"), // 0xAF
+    op("RES", &[Bit(6), B], 2, 8, 8, "----"), // 0xB0
+    op("RES", &[Bit(6), C], 2, 8, 8, "----"), // 0xB1
+    op("RES", &[Bit(6), D], 2, 8, 8, "----"), // 0xB2
+    op("RES", &[Bit(6), E], 2, 8, 8, "----"), // 0xB3
+    op("RES", &[Bit(6), H], 2, 8, 8, "----"), // 0xB4
+    op("RES", &[Bit(6), L], 2, 8, 8, "----"), // 0xB5
+    op("RES", &[Bit(6), AddressHL], 2, 16, 16, "----"), // 0xB6
+    op("RES", &[Bit(6), A], 2, 8, 8, "----"), // 0xB7
+    op("RES", &[Bit(7), B], 2, 8, 8, "----"), // 0xB8
+    op("RES", &[Bit(7), C], 2, 8, 8, "----"), // 0xB9
+    op("RES", &[Bit(7), D], 2, 8, 8, "----"), // 0xBA
+    op("RES", &[Bit(7), E], 2, 8, 8, "----"), // 0xBB
+    op("RES", &[Bit(7), H], 2, 8, 8, "----"), // 0xBC
+    op("RES", &[Bit(7), L], 2, 8, 8, "----"), // 0xBD
+    op("RES", &[Bit(7), AddressHL], 2, 16, 16, "----"), // 0xBE
+    op("RES", &[Bit(7), A], 2, 8, 8, "----"), // 0xBF
+    op("SET", &[Bit(0), B], 2, 8, 8, "----"), // 0xC0
+    op("SET", &[Bit(0), C], 2, 8, 8, "----"), // 0xC1
+    op("SET", &[Bit(0), D], 2, 8, 8, "----"), // 0xC2
+    op("SET", &[Bit(0), E], 2, 8, 8, "----"), // 0xC3
+    op("SET", &[Bit(0), H], 2, 8, 8, "----"), // 0xC4
+    op("SET", &[Bit(0), L], 2, 8, 8, "----"), // 0xC5
+    op("SET", &[Bit(0), AddressHL], 2, 16, 16, "----"), // 0xC6
+    op("SET", &[Bit(0), A], 2, 8, 8, "----"), // 0xC7
+    op("SET", &[Bit(1), B], 2, 8, 8, "----"), // 0xC8
+    op("SET", &[Bit(1), C], 2, 8, 8, "----"), // 0xC9
+    op("SET", &[Bit(1), D], 2, 8, 8, "----"), // 0xCA
+    op("SET", &[Bit(1), E], 2, 8, 8, "----"), // 0xCB
+    op("SET", &[Bit(1), H], 2, 8, 8, "----"), // 0xCC
+    op("SET", &[Bit(1), L], 2, 8, 8, "----"), // 0xCD
+    op("SET", &[Bit(1), AddressHL], 2, 16, 16, "----"), // 0xCE
+    op("SET", &[Bit(1), A], 2, 8, 8, "----"), // 0xCF
+    op("SET", &[Bit(2), B], 2, 8, 8, "----"), // 0xD0
+    op("SET", &[Bit(2), C], 2, 8, 8, "----"), // 0xD1
+    op("SET", &[Bit(2), D], 2, 8, 8, "----"), // 0xD2
+    op("SET", &[Bit(2), E], 2, 8, 8, "----"), // 0xD3
+    op("SET", &[Bit(2), H], 2, 8, 8, "----"), // 0xD4
+    op("SET", &[Bit(2), L], 2, 8, 8, "----"), // 0xD5
+    op("SET", &[Bit(2), AddressHL], 2, 16, 16, "----"), // 0xD6
+    op("SET", &[Bit(2), A], 2, 8, 8, "----"), // 0xD7
+    op("SET", &[Bit(3), B], 2, 8, 8, "----"), // 0xD8
+    op("SET", &[Bit(3), C], 2, 8, 8, "----"), // 0xD9
+    op("SET", &[Bit(3), D], 2, 8, 8, "----"), // 0xDA
+    op("SET", &[Bit(3), E], 2, 8, 8, "----"), // 0xDB
+    op("SET", &[Bit(3), H], 2, 8, 8, "----"), // 0xDC
+    op("SET", &[Bit(3), L], 2, 8, 8, "----"), // 0xDD
+    op("SET", &[Bit(3), AddressHL], 2, 16, 16, "----"), // 0xDE
+    op("SET", &[Bit(3), A], 2, 8, 8, "----"), // 0xDF
+    op("SET", &[Bit(4), B], 2, 8, 8, "----"), // 0xE0
+    op("SET", &[Bit(4), C], 2, 8, 8, "----"), // 0xE1
+    op("SET", &[Bit(4), D], 2, 8, 8, "----"), // 0xE2
+    op("SET", &[Bit(4), E], 2, 8, 8, "----"), // 0xE3
+    op("SET", &[Bit(4), H], 2, 8, 8, "----"), // 0xE4
+    op("SET", &[Bit(4), L], 2, 8, 8, "----"), // 0xE5
+    op("SET", &[Bit(4), AddressHL], 2, 16, 16, "----"), // 0xE6
+    op("SET", &[Bit(4), A], 2, 8, 8, "----"), // 0xE7
+    op("SET", &[Bit(5), B], 2, 8, 8, "----"), // 0xE8
+    op("SET", &[Bit(5), C], 2, 8, 8, "----"), // 0xE9
+    op("SET", &[Bit(5), D], 2, 8, 8, "----"), // 0xEA
+    op("SET", &[Bit(5), E], 2, 8, 8, "----"), // 0xEB
+    op("SET", &[Bit(5), H], 2, 8, 8, "----"), // 0xEC
+    op("SET", &[Bit(5), L], 2, 8, 8, "----"), // 0xED
+    op("SET", &[Bit(5), AddressHL], 2, 16, 16, "----"), // 0xEE
+    op("SET", &[Bit(5), A], 2, 8, 8, "----"), // 0xEF
+    op("SET", &[Bit(6), B], 2, 8, 8, "----"), // 0xF0
+    op("SET", &[Bit(6), C], 2, 8, 8, "----"), // 0xF1
+    op("SET", &[Bit(6), D], 2, 8, 8, "----"), // 0xF2
+    op("SET", &[Bit(6), E], 2, 8, 8, "----"), // 0xF3
+    op("SET", &[Bit(6), H], 2, 8, 8, "----"), // 0xF4
+    op("SET", &[Bit(6), L], 2, 8, 8, "----"), // 0xF5
+    op("SET", &[Bit(6), AddressHL], 2, 16, 16, "----"), // 0xF6
+    op("SET", &[Bit(6), A], 2, 8, 8, "----"), // 0xF7
+    op("SET", &[Bit(7), B], 2, 8, 8, "----"), // 0xF8
+    op("SET", &[Bit(7), C], 2, 8, 8, "----"), // 0xF9
+    op("SET", &[Bit(7), D], 2, 8, 8, "----"), // 0xFA
+    op("SET", &[Bit(7), E], 2, 8, 8, "----"), // 0xFB
+    op("SET", &[Bit(7), H], 2, 8, 8, "----"), // 0xFC
+    op("SET", &[Bit(7), L], 2, 8, 8, "----"), // 0xFD
+    op("SET", &[Bit(7), AddressHL], 2, 16, 16, "----"), // 0xFE
+    op("SET", &[Bit(7), A], 2, 8, 8, "----"), // 0xFF
+];
@@ -1,5 +1,4 @@
 use std::{
-    collections::HashSet,
     fmt,
     fs::OpenOptions,
     io::{empty, prelude::*},
@@ -7,7 +6,7 @@ use std::{
 
 use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
-use super::{Bus, Interrupt};
+use super::{Bus, Interrupt, OamBugAccess};
 
 const VRAM_SIZE: usize = (0x9FFF - 0x8000) + (0xFE9F - 0xFE00);
 
@@ -22,20 +21,39 @@ pub struct PPU {
     pub oam: [Sprite; 40],   // [[u8; 4]; 40]
     // IO Registers 0xFF40-0xFF4B
     lcdc: u8,           // PPU control register - 0xFF40
-    stat: u8,           // PPU status register - 0xFF41
-    scy: u8,            // Vertical scroll register - 0xFF42
-    scx: u8,            // Horizontal scroll register - 0xFF43
-    pub ly: u8,         // Scanline register - 0xFF44
-    lyc: u8,            // LY Compare - 0xFF45
+    stat: u8, // PPU status register - 0xFF41 (only the interrupt source bits 3-6 are stored)
+    scy: u8,  // Vertical scroll register - 0xFF42
+    scx: u8,  // Horizontal scroll register - 0xFF43
+    pub ly: u8, // Scanline register - 0xFF44
+    lyc: u8,  // LY Compare - 0xFF45
     pub bg_palette: u8, // Background color palette - 0xFF47
-    ob_palette_1: u8,   // Object color palette 1 - 0xFF48
-    ob_palette_2: u8,   // Object color palette 2 - 0xFF49
-    wy: u8,             // Window Y position - 0xFF4A
-    wx: u8,             // Window X position - 0xFF4B
+    ob_palette_1: u8, // Object color palette 1 - 0xFF48
+    ob_palette_2: u8, // Object color palette 2 - 0xFF49
+    wy: u8,   // Window Y position - 0xFF4A
+    wx: u8,   // Window X position - 0xFF4B
+    /// Block CPU access to VRAM during mode 3 and to OAM during modes 2 and 3, like the hardware does.
+    /// Debugging tools can turn this off to inspect and edit memory at any time.
+    pub is_access_restricted: bool,
+    /// DMG quirk: writing to STAT enables every STAT interrupt source for one cycle
+    pub has_stat_write_bug: bool,
+    /// Interrupts requested since the bus last collected them (IF bits)
+    interrupt_requests: u8,
     // Internal data structures
+    mode: Mode,
+    /// Dots (T-cycles) into the current line, 0-455
     mode_cycles: u16,
+    /// The STAT interrupt line, the OR of all enabled STAT sources. An interrupt is only raised on its rising edge.
+    stat_line: bool,
+    /// The first frame after the LCD is turned on is not displayed, the screen stays blank
+    is_first_frame: bool,
+    /// Set when the PPU enters V-Blank (a frame was completed), cleared by whoever waits for the frame
+    pub has_entered_vblank: bool,
     pub scanline_sprite_cache: Vec<Sprite>,
     window_lc: u8,
+    /// Set once LY matched WY at the start of a line this frame, the window can only be shown after that
+    window_y_triggered: bool,
+    /// WX was 166 on the previous line, the window covers the entire current line
+    is_window_wrapping: bool,
     line_scanned: bool,
     // Display
     screen_buffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4], // RGBA
@@ -256,20 +274,48 @@ impl PPU {
             wy: 0,
             wx: 0,
             screen_buffer: [0; SCREEN_WIDTH * SCREEN_HEIGHT * 4],
+            is_access_restricted: true,
+            has_stat_write_bug: true,
+            interrupt_requests: 0,
+            mode: Mode::HBlank,
             mode_cycles: 0,
+            stat_line: false,
+            is_first_frame: false,
+            has_entered_vblank: false,
             scanline_sprite_cache: Vec::with_capacity(10),
             window_lc: 0,
+            window_y_triggered: false,
+            is_window_wrapping: false,
             line_scanned: false,
         }
     }
 
+    /// CPU read, VRAM and OAM read 0xFF while the PPU is using them
     pub fn read_byte(&self, index: usize, real_addr: usize) -> u8 {
+        match real_addr {
+            0x8000..=0x9FFF if !self.is_vram_accessible() => 0xFF,
+            0xFE00..=0xFE9F if !self.is_oam_accessible() => 0xFF,
+            _ => self.peek_byte(real_addr),
+        }
+    }
+
+    /// CPU write, writes to VRAM and OAM are dropped while the PPU is using them
+    pub fn write_byte(&mut self, index: usize, real_addr: usize, value: u8) {
+        match real_addr {
+            0x8000..=0x9FFF if !self.is_vram_accessible() => (),
+            0xFE00..=0xFE9F if !self.is_oam_accessible() => (),
+            _ => self.poke_byte(real_addr, value),
+        }
+    }
+
+    /// Read regardless of the PPU mode, without side effects (debuggers, OAM DMA)
+    pub fn peek_byte(&self, real_addr: usize) -> u8 {
         match real_addr {
             0xFF40 => self.lcdc,
-            0xFF41 => self.stat,
+            0xFF41 => self.read_stat(),
             0xFF42 => self.scy,
             0xFF43 => self.scx,
-            0xFF44 => self.ly,
+            0xFF44 => self.get_ly(),
             0xFF45 => self.lyc,
             0xFF47 => self.bg_palette,
             0xFF48 => self.ob_palette_1,
@@ -286,13 +332,26 @@ impl PPU {
         }
     }
 
-    pub fn write_byte(&mut self, index: usize, real_addr: usize, value: u8) {
+    /// Write regardless of the PPU mode (debuggers, OAM DMA, boot ROM state)
+    pub fn poke_byte(&mut self, real_addr: usize, value: u8) {
         match real_addr {
             0xFF40 => {
+                let was_enabled = self.is_lcd_enabled();
                 self.lcdc = value;
+                match (was_enabled, self.is_lcd_enabled()) {
+                    (true, false) => self.disable_lcd(),
+                    (false, true) => self.enable_lcd(),
+                    _ => (),
+                }
             }
             0xFF41 => {
-                self.stat = value;
+                if self.has_stat_write_bug {
+                    // Behaves as if 0xFF was written for a cycle, before the actual value
+                    self.stat = 0b0111_1000;
+                    self.update_stat_interrupt();
+                }
+                self.stat = value & 0b0111_1000;
+                self.update_stat_interrupt();
             }
             0xFF42 => {
                 self.scy = value;
@@ -305,6 +364,7 @@ impl PPU {
             }
             0xFF45 => {
                 self.lyc = value;
+                self.update_stat_interrupt();
             }
             0xFF47 => {
                 self.bg_palette = value;
@@ -378,42 +438,204 @@ impl PPU {
         self.oam[oam_index][byte_pos] = byte;
     }
 
+    //
+    //  OAM corruption bug
+    //
+
+    /// The CPU accessed 0xFE00-0xFEFF (or had it in its IDU), which corrupts OAM during OAM scan (mode 2)
+    ///
+    /// OAM is made of 20 rows of 8 bytes (4 words), the PPU reads one row per M-cycle. The row being read is
+    /// overwritten by a mix of itself and the preceding row, the first row is never corrupted.
+    /// https://gbdev.io/pandocs/OAM_Corruption_Bug.html
+    pub fn corrupt_oam(&mut self, access: OamBugAccess) {
+        if !self.is_lcd_enabled() || self.get_mode() != Mode::OAM {
+            return;
+        }
+
+        let row = (self.mode_cycles / 4) as usize;
+        if row == 0 || row >= 20 {
+            return;
+        }
+
+        match access {
+            OamBugAccess::Write => {
+                let (a, b, c) = (
+                    self.oam_word(row, 0),
+                    self.oam_word(row - 1, 0),
+                    self.oam_word(row - 1, 2),
+                );
+                self.set_oam_word(row, 0, ((a ^ c) & (b ^ c)) ^ c);
+                self.copy_oam_words(row - 1, row);
+            }
+            OamBugAccess::Read => self.corrupt_oam_read(row),
+            OamBugAccess::ReadIdu => {
+                // Rows 4-18 first get the preceding row mixed with the two rows around it
+                if (4..19).contains(&row) {
+                    let a = self.oam_word(row - 2, 0);
+                    let b = self.oam_word(row - 1, 0);
+                    let c = self.oam_word(row, 0);
+                    let d = self.oam_word(row - 1, 2);
+                    self.set_oam_word(row - 1, 0, (b & (a | c | d)) | (a & c & d));
+                    for word in 0..4 {
+                        let value = self.oam_word(row - 1, word);
+                        self.set_oam_word(row, word, value);
+                        self.set_oam_word(row - 2, word, value);
+                    }
+                }
+                self.corrupt_oam_read(row);
+            }
+        }
+    }
+
+    fn corrupt_oam_read(&mut self, row: usize) {
+        let (a, b, c) = (
+            self.oam_word(row, 0),
+            self.oam_word(row - 1, 0),
+            self.oam_word(row - 1, 2),
+        );
+        self.set_oam_word(row, 0, b | (a & c));
+        self.copy_oam_words(row - 1, row);
+    }
+
+    fn oam_word(&self, row: usize, word: usize) -> u16 {
+        let index = row * 8 + word * 2;
+        u16::from_le_bytes([self.raw_oam[index], self.raw_oam[index + 1]])
+    }
+
+    fn set_oam_word(&mut self, row: usize, word: usize, value: u16) {
+        let index = row * 8 + word * 2;
+        let [low, high] = value.to_le_bytes();
+        self.write_oam_data(index, low);
+        self.write_oam_data(index + 1, high);
+    }
+
+    /// Copy the last three words of a row, the first word is corrupted separately
+    fn copy_oam_words(&mut self, from_row: usize, to_row: usize) {
+        for word in 1..4 {
+            let value = self.oam_word(from_row, word);
+            self.set_oam_word(to_row, word, value);
+        }
+    }
+
     //
     //  Modes
     //
     fn get_mode(&self) -> Mode {
-        let lower = self.stat & 0b11;
-        Mode::from(lower)
+        self.mode
     }
 
-    fn set_mode(&mut self, new_mode: Mode) -> Vec<Interrupt> {
-        let mut interrupts: HashSet<Interrupt> = HashSet::new();
+    /// LY as seen by the CPU: on line 153 it already reads 0 after the first M-cycle
+    pub fn get_ly(&self) -> u8 {
+        if self.ly == 153 && self.mode_cycles >= 4 {
+            0
+        } else {
+            self.ly
+        }
+    }
 
-        self.stat &= !0b11; // Clear mode bits
-        self.stat |= u8::from(new_mode); // Set mode bits
+    /// STAT with the current mode and LY=LYC coincidence flag, bit 7 is unused and always set
+    fn read_stat(&self) -> u8 {
+        let coincidence = if self.get_ly() == self.lyc { 0b100 } else { 0 };
+        let mode = if self.is_lcd_enabled() {
+            u8::from(self.mode)
+        } else {
+            0
+        };
 
-        match new_mode {
-            Mode::Drawing => (),
-            Mode::HBlank => {
-                if self.stat & 0b100 != 0 {
-                    interrupts.insert(Interrupt::LCD);
-                }
-            }
-            Mode::VBlank => {
-                interrupts.insert(Interrupt::VBlank);
+        0b1000_0000 | self.stat | coincidence | mode
+    }
 
-                if self.stat & 0b1000 != 0 {
-                    interrupts.insert(Interrupt::LCD);
-                }
-            }
-            Mode::OAM => {
-                if self.stat & 0b1_0000 != 0 {
-                    interrupts.insert(Interrupt::LCD);
-                }
-            }
+    /// Place the PPU on the given V-Blank line, as left by the boot ROM
+    ///
+    /// An LY of 0 means the boot ROM handed over on line 153, after LY already wrapped around.
+    pub fn set_vblank_line(&mut self, ly: u8) {
+        self.mode = Mode::VBlank;
+        (self.ly, self.mode_cycles) = match ly {
+            0 => (153, 4),
+            _ => (ly, 0),
         };
+        self.stat_line = self.is_stat_line_high();
+        self.is_first_frame = false;
+    }
+
+    /// LCDC bit 7 was cleared: the PPU stops at LY 0 in mode 0 and the screen turns white
+    fn disable_lcd(&mut self) {
+        self.ly = 0;
+        self.mode_cycles = 0;
+        self.set_mode(Mode::HBlank);
+        self.stat_line = false;
+        self.blank_screen();
+    }
 
-        interrupts.iter().map(|i| *i).collect()
+    /// LCDC bit 7 was set: start over from line 0
+    ///
+    /// Line 0 is special: there is no OAM scan (mode 0 is reported instead) and it is 4 dots shorter.
+    /// The frame that follows is not shown on the screen.
+    fn enable_lcd(&mut self) {
+        self.ly = 0;
+        self.mode_cycles = 4;
+        self.set_mode(Mode::HBlank);
+        self.reset_window();
+        self.check_window_y();
+        self.line_scanned = false;
+        self.is_first_frame = true;
+        self.build_sprite_cache();
+        self.update_stat_interrupt();
+    }
+
+    /// VRAM is locked while pixels are being drawn (mode 3)
+    fn is_vram_accessible(&self) -> bool {
+        !self.is_access_restricted || !self.is_lcd_enabled() || self.get_mode() != Mode::Drawing
+    }
+
+    /// OAM is locked while sprites are being scanned (mode 2) and drawn (mode 3)
+    pub fn is_oam_accessible(&self) -> bool {
+        !self.is_access_restricted
+            || !self.is_lcd_enabled()
+            || !matches!(self.get_mode(), Mode::OAM | Mode::Drawing)
+    }
+
+    fn set_mode(&mut self, new_mode: Mode) {
+        self.mode = new_mode;
+    }
+
+    /// Is any enabled STAT interrupt source currently active
+    ///
+    /// The OAM source also fires at the start of line 144, together with the V-Blank source.
+    fn is_stat_line_high(&self) -> bool {
+        let mode = self.get_mode();
+        let is_oam = mode == Mode::OAM || (self.ly == 144 && self.mode_cycles < 4);
+
+        (self.stat & 0b100_0000 != 0 && self.get_ly() == self.lyc)
+            || (self.stat & 0b10_0000 != 0 && is_oam)
+            || (self.stat & 0b1_0000 != 0 && mode == Mode::VBlank)
+            || (self.stat & 0b1000 != 0 && mode == Mode::HBlank)
+    }
+
+    /// Re-evaluate the STAT interrupt line, returns whether it rose (an LCD interrupt is requested)
+    ///
+    /// Sources that become active while the line is already high do not raise another interrupt ("STAT blocking").
+    fn update_stat_line(&mut self) -> bool {
+        let was_high = self.stat_line;
+        self.stat_line = self.is_lcd_enabled() && self.is_stat_line_high();
+
+        !was_high && self.stat_line
+    }
+
+    /// Re-evaluate the STAT interrupt line after a register write
+    fn update_stat_interrupt(&mut self) {
+        if self.update_stat_line() {
+            self.request_interrupt(Interrupt::LCD);
+        }
+    }
+
+    fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_requests |= interrupt.get_flag_mask();
+    }
+
+    /// Interrupts requested since the last call (IF bits), to be forwarded by the bus
+    pub fn take_interrupt_requests(&mut self) -> u8 {
+        std::mem::take(&mut self.interrupt_requests)
     }
 
     //
@@ -449,6 +671,13 @@ impl PPU {
         }
     }
 
+    /// Blank the LCD (white), e.g. while the CPU is in STOP mode
+    pub fn blank_screen(&mut self) {
+        for index in (0..self.screen_buffer.len()).step_by(4) {
+            PPU::push_pixel(&mut self.screen_buffer, index, &Pixel::Zero);
+        }
+    }
+
     pub fn get_display(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4] {
         &self.screen_buffer
     }
@@ -559,26 +788,13 @@ impl PPU {
             return;
         }
 
-        // Check all cached sprites (max 10) that occur on this scanline (self.ly)
-        // The cache is already sorted by sprites' x-positions, so we can draw and overwrite sprites in the REVERSE order of this vector
-        for sprite in self.scanline_sprite_cache.iter().rev() {
-            // Sprite data
-            let y_position = sprite[0] as usize;
-            let x_position = sprite[1] as usize;
-            let tile_index = sprite[2] as usize;
-            let attributes = sprite[3];
+        // Check all cached sprites (max 10) that occur on this scanline (self.ly), which are in OAM order
+        // The sprite with the lowest x-position is drawn on top, ties are won by the sprite that comes first in OAM.
+        // Sorting is stable, so sprites are drawn in priority order and only onto pixels no other sprite has claimed.
+        let mut sprites: Vec<&Sprite> = self.scanline_sprite_cache.iter().collect();
+        sprites.sort_by_key(|sprite| sprite[1]);
 
-            // Might not be necessary to check this if the drawing is handled correctly
-            // Check horizontal alignment
-            if x_position == 0 || x_position >= 168 {
-                continue; // Completely off the screen horizontally
-            }
-            // Check vertical alignment
-            if y_position == 0 || y_position >= 160 {
-                continue; // Completely off the screen vertically
-            }
-
-            // Attempt to draw to row_buffer
+        for sprite in sprites {
             self.draw_scanline_sprite(row_buffer, sprite);
         }
     }
@@ -597,41 +813,29 @@ impl PPU {
         let mut tile_index = sprite[2] as usize;
         let attributes = sprite[3];
 
-        // Determine which row of the tile to draw
-        let flip_y: bool = attributes & 0b0100_0000 != 0;
-        let mut row_index = self.ly + 16 - y_position as u8;
+        // Determine which row of the sprite to draw
+        let sprite_height = self.get_sprite_height();
+        let mut row_index = (self.ly + 16).wrapping_sub(y_position as u8);
 
-        if row_index >= self.get_sprite_height() {
-            //println!("{:?}\t({:?})", row_index, self.get_sprite_height());
-            //panic!("aaa");
-            // TODO: RESOLVE THIS BANDAID!
+        // The sprite height may have changed since the OAM scan
+        if row_index >= sprite_height {
             return;
         }
 
-        // Handle 16-bit sprites - Determine which tile of the two to draw
-        if self.get_sprite_height() == 16 {
-            let mut is_first_tile = row_index < 8;
-            if flip_y {
-                is_first_tile = !is_first_tile;
-            }
-
-            if is_first_tile {
-                tile_index &= !0b1; // Enforce pointing to the first tile (of two) in the 8x16 sprite
-            } else {
-                tile_index |= 0b1; // Enforce pointing to the second tile (of two) in the 8x16 sprite
-            }
-
-            row_index = row_index % 8;
+        // Y Flip - flips the whole sprite, so both tiles of an 8x16 sprite
+        if attributes & 0b0100_0000 != 0 {
+            row_index = sprite_height - 1 - row_index;
         }
 
-        // Y Flip
-        if flip_y {
-            row_index = 7 - row_index;
+        // 8x16 sprites ignore bit 0 of the tile index: the top tile is even, the bottom tile odd
+        if sprite_height == 16 {
+            tile_index &= !0b1;
         }
+        tile_index += (row_index / 8) as usize;
 
         // Load sprite tile
         let tile_data: &Tile = &self.tile_set[tile_index]; // Always uses the $8000 method (unsigned => usize)
-        let mut row_data = tile_data[row_index as usize];
+        let mut row_data = tile_data[(row_index % 8) as usize];
 
         // X Flip
         if attributes & 0b0010_0000 != 0 {
@@ -648,26 +852,37 @@ impl PPU {
         let priority = attributes & 0b1000_0000 != 0;
 
         for dx in 0..8 {
-            // Off the left side of the screen
-            if x_position + dx <= 8 {
+            // Off the left or right side of the screen (X is offset by 8)
+            if x_position + dx < 8 || x_position + dx >= SCREEN_WIDTH + 8 {
                 continue;
             }
-            // Off the right side of the screen
-            if x_position + dx >= 160 {
+
+            // A higher priority sprite already has an opaque pixel here
+            let screen_x = x_position + dx - 8;
+            if row_buffer[screen_x].is_some() {
                 continue;
             }
 
             let pixel = self.apply_pixel_pallete_sprite(row_data[dx], palette);
             if let Some(pixel) = pixel {
-                row_buffer[x_position + dx - 8] = Some((pixel, priority));
+                row_buffer[screen_x] = Some((pixel, priority));
             }
         }
     }
 
     /// Draw all window data of the current LY onto the specified row_buffer
+    ///
+    /// Matches the DMG for the edge cases:
+    /// > The window only appears once LY has been equal to WY at the start of a line this frame (WY is latched)
+    /// > WX 0-6 start the window off the left edge of the screen, WX 0 is additionally shifted by SCX % 8
+    /// > WX 166 shows a single window pixel, and the window then covers the entire next line
+    /// > The window line counter only advances on lines the window was drawn, so re-enabling it mid-frame
+    ///   continues where it left off
     fn scanline_window(&mut self, row_buffer: &mut [Option<Pixel>; SCREEN_WIDTH]) {
         *row_buffer = [None; SCREEN_WIDTH];
 
+        let is_wrapping = std::mem::take(&mut self.is_window_wrapping);
+
         // Window disabled - do not render
         if !self.is_window_enabled() {
             return;
@@ -680,27 +895,34 @@ impl PPU {
         }
 
         // Not currently in window (vertically)
-        if self.ly < self.wy {
+        if !self.window_y_triggered {
             return;
         }
 
         // Off the screen window (horizontally)
-        if self.wx >= 168 || self.wx == 0 {
+        if self.wx > 166 && !is_wrapping {
             return;
         }
 
+        // First screen x of the window, and how many window pixels are skipped (off the left edge)
+        let (start_x, skipped) = match self.wx {
+            _ if is_wrapping => (0, 0),
+            0 => (0, 7 + (self.scx as usize % 8)),
+            1..=6 => (0, 7 - self.wx as usize),
+            _ => (self.wx as usize - 7, 0),
+        };
+        if self.wx == 166 {
+            self.is_window_wrapping = true;
+        }
+
         let window_map: &[u8; 0x3FF + 1] = self.get_window_map();
 
         // Render window when inside of it
         let window_y = self.window_lc as usize;
         let tile_row = window_y % 8;
 
-        for x in 0..SCREEN_WIDTH {
-            if x + 8 <= self.wx as usize {
-                continue; // Have not reached the window yet
-            }
-
-            let window_x = x + 7 - (self.wx as usize);
+        for x in start_x..SCREEN_WIDTH {
+            let window_x = x - start_x + skipped;
 
             let window_index = (window_y / 8 * 32 + window_x / 8) % (32 * 32);
             let tile_index = window_map[window_index] as usize;
@@ -713,6 +935,20 @@ impl PPU {
         self.window_lc += 1;
     }
 
+    /// Latch the WY condition, checked at the start of every visible line
+    fn check_window_y(&mut self) {
+        if self.ly == self.wy {
+            self.window_y_triggered = true;
+        }
+    }
+
+    /// Start of a new frame, the window starts over from its first line
+    fn reset_window(&mut self) {
+        self.window_lc = 0; // Reset internal window line counter
+        self.window_y_triggered = false;
+        self.is_window_wrapping = false;
+    }
+
     /// Render an entire row's worth of pixels into the internal buffer (`self.screen_buffer`)
     fn scanline_render(&mut self) {
         if self.line_scanned {
@@ -739,7 +975,7 @@ impl PPU {
         // Merge scanlines into final result to be displayed
         for (index, bg_pixel) in background.iter().enumerate() {
             // Merge background and window
-            let mut output_pixel = {
+            let bg_color = {
                 if let Some(window_pixel) = window_buffer[index] {
                     window_pixel
                 } else {
@@ -747,13 +983,14 @@ impl PPU {
                 }
             };
             // Apply background color pallete (same pallete for both background & window)
-            output_pixel = self.apply_pixel_pallete(output_pixel, &self.bg_palette);
+            let mut output_pixel = self.apply_pixel_pallete(bg_color, &self.bg_palette);
 
             // Layer sprite, taking into account it's priority
             // Note: sprite transparency has already been handled. If the pixel is transparent, sprite_tuple will be None.
+            // A sprite behind the background (priority set) only shows through background color 0, regardless of the palette.
             let sprite_tuple = sprite_buffer[index];
             if let Some((sprite_pixel, priority)) = sprite_tuple {
-                if !priority || (priority && output_pixel == Pixel::Zero) {
+                if !priority || bg_color == Pixel::Zero {
                     output_pixel = sprite_pixel;
                 }
             }
@@ -773,9 +1010,10 @@ impl PPU {
         buffer[index + 3] = 0xFF;
     }
 
-    /// Cache the last 10 sprites that occur on the current row (LY) by their x-position
+    /// Cache the first 10 sprites (in OAM order) that occur on the current row (LY)
     ///
     /// These are the only sprites that will end up being rendered during the Drawing mode.
+    /// Sprites off the screen horizontally (e.g. X = 0) still count towards the limit of 10.
     /// This function should be called during the OAM Scan mode.
     fn build_sprite_cache(&mut self) {
         self.scanline_sprite_cache = Vec::with_capacity(10);
@@ -783,103 +1021,86 @@ impl PPU {
 
         for sprite in self.oam.iter() {
             let y_position = sprite[0];
-            let x_position = sprite[1];
 
-            if x_position > 0
-                && self.ly + 16 >= y_position
-                && self.ly + 16 < y_position + sprite_height
+            if (self.ly as u16) + 16 >= (y_position as u16)
+                && (self.ly as u16) + 16 < (y_position as u16) + (sprite_height as u16)
                 && self.scanline_sprite_cache.len() < 10
             {
                 self.scanline_sprite_cache.push(*sprite);
             }
         }
-
-        // Sort by x-position
-        self.scanline_sprite_cache.sort_by(|a, b| a[1].cmp(&b[1]));
     }
 
-    pub fn tick(&mut self, _cycles: u16) -> Vec<Interrupt> {
-        let mut raised_interrupts: Vec<Interrupt> = Vec::new();
+    /// Run the PPU for the supplied amount of dots, jumping from one event (mode change, new line) to the next
+    ///
+    /// Raised interrupts are collected with `take_interrupt_requests`.
+    pub fn tick(&mut self, dots: u64) {
+        let mut dots = dots;
+
+        // The PPU is not clocked at all while the LCD is off
+        while let Some(dots_until_event) = self.dots_until_next_event() {
+            if dots < dots_until_event {
+                self.mode_cycles += dots as u16;
+                break;
+            }
 
-        if !self.is_lcd_enabled() {
-            //return raised_interrupts;
+            dots -= dots_until_event;
+            self.mode_cycles += dots_until_event as u16;
+            self.handle_event();
         }
+    }
 
-        self.mode_cycles += _cycles;
-        let mut cycles_left = _cycles;
-
-        while cycles_left > 0 {
-            // Expend them
-            let ticks_consumed = if cycles_left > 80 { 80 } else { cycles_left }; // Consume at most 80 dots per cycle
-            self.mode_cycles += ticks_consumed;
-            cycles_left -= ticks_consumed;
-
-            // Process
-
-            if self.ly < 144 {
-                // Normal
-                if self.mode_cycles < 80 {
-                    // OAM
-                    self.build_sprite_cache();
-                    raised_interrupts.append(&mut self.set_mode(Mode::OAM));
-                } else if self.mode_cycles < (80 + 172) {
-                    // DRAWING
-                    raised_interrupts.append(&mut self.set_mode(Mode::Drawing));
-                } else if self.mode_cycles < 456 {
-                    // HBLANK
-                    if let Some(interrupt) = self.check_lyc_interrupt() {
-                        raised_interrupts.push(interrupt);
-                    }
+    /// Dots until the next dot on which something happens, None while the LCD is off
+    pub fn dots_until_next_event(&self) -> Option<u64> {
+        if !self.is_lcd_enabled() {
+            return None;
+        }
 
-                    raised_interrupts.append(&mut self.set_mode(Mode::HBlank));
-                } else {
-                    // VLBANK
-                    if self.is_lcd_enabled() {
-                        self.scanline_render();
-                        self.line_scanned = false;
-                    }
-                    self.ly = (self.ly + 1) % 154;
-                    raised_interrupts.append(&mut self.set_mode(Mode::OAM));
+        let event_dot = match (self.ly, self.mode_cycles) {
+            (0..=143, 0..=79) => 80,
+            (0..=143, 80..=251) => 252,
+            // The OAM STAT source ends on line 144, LY switches to 0 on line 153
+            (144 | 153, 0..=3) => 4,
+            _ => 456,
+        };
+        Some((event_dot - self.mode_cycles) as u64)
+    }
 
-                    if let Some(interrupt) = self.check_lyc_interrupt() {
-                        raised_interrupts.push(interrupt);
-                    }
-                    self.mode_cycles = self.mode_cycles % 456;
+    /// The PPU reached one of the dots returned by `dots_until_next_event`
+    fn handle_event(&mut self) {
+        match (self.ly, self.mode_cycles) {
+            // Visible lines: OAM scan (80 dots), drawing (172 dots), H-Blank (rest of the 456 dots)
+            (0..=143, 80) => self.set_mode(Mode::Drawing),
+            (0..=143, 252) => {
+                if !self.is_first_frame {
+                    self.scanline_render();
                 }
-            } else {
-                // VBlank Lines
-
-                if self.mode_cycles > 456 {
-                    // Increment LY
-                    self.ly = (self.ly + 1) % 154;
-
-                    if self.ly < 144 {
-                        self.set_mode(Mode::OAM);
-                    } else {
-                        if self.get_mode() != Mode::VBlank {
-                            raised_interrupts.append(&mut self.set_mode(Mode::VBlank));
-                        }
-                        self.window_lc = 0; // Reset internal window line counter
-                    }
+                self.set_mode(Mode::HBlank);
+            }
+            (_, 456) => {
+                // Next line
+                self.mode_cycles = 0;
+                self.ly = (self.ly + 1) % 154;
+                self.line_scanned = false;
 
-                    self.mode_cycles = self.mode_cycles % 456;
+                if self.ly < 144 {
+                    self.build_sprite_cache();
+                    self.check_window_y();
+                    self.set_mode(Mode::OAM);
+                } else if self.ly == 144 {
+                    self.request_interrupt(Interrupt::VBlank);
+                    self.has_entered_vblank = true;
+                    self.set_mode(Mode::VBlank);
+                    self.reset_window();
+                    self.is_first_frame = false;
                 }
             }
+            // The STAT line is re-evaluated below: the OAM source ends on line 144, LY reads 0 on line 153
+            (144 | 153, 4) => (),
+            _ => (),
         }
 
-        // Return possible (STAT/LCD) interrupt
-        raised_interrupts
-    }
-
-    /// If an LCD interrupt should occur, return it
-    fn check_lyc_interrupt(&mut self) -> Option<Interrupt> {
-        if self.ly == self.lyc && self.stat & 0b100_0000 != 0 {
-            self.stat |= 0b100;
-            Some(Interrupt::LCD)
-        } else {
-            self.stat &= !0b100;
-            None
-        }
+        self.update_stat_interrupt();
     }
 
     /// Change the color of the pixel according to the pallete specified
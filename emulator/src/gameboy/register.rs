@@ -105,14 +105,14 @@ impl Registers {
     // Modifiers
     pub fn hli(&mut self) -> u16 {
         let value = self.get_hl();
-        self.set_hl(value + 1);
+        self.set_hl(value.wrapping_add(1));
 
         value
     }
 
     pub fn hld(&mut self) -> u16 {
         let value = self.get_hl();
-        self.set_hl(value - 1);
+        self.set_hl(value.wrapping_sub(1));
 
         value
     }
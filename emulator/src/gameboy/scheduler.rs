@@ -0,0 +1,115 @@
+/// Something that has to happen at a given point in time
+///
+/// Every kind of event is scheduled at most once, rescheduling it replaces the previous time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// The PPU reaches its next mode transition (or line 153's early LY change)
+    Ppu,
+    /// The timer requests its interrupt (TIMA overflowed and was reloaded)
+    TimerInterrupt,
+    /// The next bit of a serial transfer is shifted (internal clock)
+    SerialBit,
+    /// The next byte of an OAM DMA transfer is copied
+    DmaByte,
+    /// One second of the cartridge's real time clock went by
+    RtcTick,
+}
+
+impl Event {
+    const COUNT: usize = 5;
+
+    const ALL: [Event; Event::COUNT] = [
+        Event::Ppu,
+        Event::TimerInterrupt,
+        Event::SerialBit,
+        Event::DmaByte,
+        Event::RtcTick,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Keeps track of time and of when each component next needs to run
+///
+/// Time is counted in T-cycles of the CPU clock since power on. Components are not ticked every cycle,
+/// they catch up on the elapsed time when one of their events is due (or one of their registers is accessed).
+pub struct Scheduler {
+    now: u64,
+    /// When each event is due, if it is scheduled
+    events: [Option<u64>; Event::COUNT],
+    /// Earliest scheduled event, so that advancing time stays cheap when nothing is due
+    next_event_at: u64,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler {
+            now: 0,
+            events: [None; Event::COUNT],
+            next_event_at: u64::MAX,
+        }
+    }
+
+    /// T-cycles since power on
+    #[inline]
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    #[inline]
+    pub fn advance(&mut self, cycles: u64) {
+        self.now += cycles;
+    }
+
+    /// Schedule an event `cycles` T-cycles from now
+    pub fn schedule_in(&mut self, event: Event, cycles: u64) {
+        self.events[event.index()] = Some(self.now + cycles);
+        self.update_next_event();
+    }
+
+    /// Make a scheduled event due `cycles` T-cycles earlier, for events that keep going while time stands still
+    pub fn bring_forward(&mut self, event: Event, cycles: u64) {
+        if let Some(time) = self.events[event.index()].as_mut() {
+            *time = time.saturating_sub(cycles);
+            self.update_next_event();
+        }
+    }
+
+    pub fn cancel(&mut self, event: Event) {
+        self.events[event.index()] = None;
+        self.update_next_event();
+    }
+
+    /// Remove and return the earliest event that is due, if any
+    #[inline]
+    pub fn pop_due(&mut self) -> Option<Event> {
+        if self.next_event_at > self.now {
+            return None;
+        }
+        self.pop_earliest()
+    }
+
+    fn pop_earliest(&mut self) -> Option<Event> {
+        let event = Event::ALL
+            .iter()
+            .filter_map(|event| self.events[event.index()].map(|time| (time, *event)))
+            .min_by_key(|(time, _)| *time)
+            .filter(|(time, _)| *time <= self.now)
+            .map(|(_, event)| event)?;
+
+        self.cancel(event);
+        Some(event)
+    }
+
+    fn update_next_event(&mut self) {
+        self.next_event_at = self
+            .events
+            .iter()
+            .flatten()
+            .copied()
+            .min()
+            .unwrap_or(u64::MAX);
+    }
+}
@@ -0,0 +1,542 @@
+use std::cmp::Ordering;
+
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Size of the SNES picture: the Game Boy screen surrounded by the border
+pub const SGB_SCREEN_WIDTH: usize = 256;
+pub const SGB_SCREEN_HEIGHT: usize = 224;
+
+/// Position of the Game Boy screen inside the border
+const SCREEN_X: usize = 48;
+const SCREEN_Y: usize = 40;
+
+/// The attribute map holds a palette for every 8x8 cell of the Game Boy screen
+const ATTR_WIDTH: usize = SCREEN_WIDTH / 8;
+const ATTR_HEIGHT: usize = SCREEN_HEIGHT / 8;
+/// ATTR_TRN loads 45 attribute files, each cell takes 2 bits
+const ATTR_FILE_SIZE: usize = ATTR_WIDTH * ATTR_HEIGHT / 4;
+const ATTR_FILE_COUNT: usize = 45;
+
+/// A VRAM transfer sends 4KB, the first 256 tiles of the screen
+const TRANSFER_SIZE: usize = 0x1000;
+
+/// The border is a 32x28 map of SNES 4bpp tiles (32 bytes each)
+const BORDER_MAP_WIDTH: usize = SGB_SCREEN_WIDTH / 8;
+const BORDER_MAP_HEIGHT: usize = SGB_SCREEN_HEIGHT / 8;
+const BORDER_TILE_SIZE: usize = 32;
+
+/// Palette the SGB starts with, before the game sends its own (BGR555)
+const DEFAULT_PALETTE: [u16; 4] = [0x67BF, 0x265B, 0x10B5, 0x2866];
+
+/// MASK_EN: what is shown instead of the Game Boy screen while the game prepares a VRAM transfer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MaskMode {
+    /// The Game Boy screen is shown
+    #[default]
+    Cancel,
+    /// The last frame is kept on screen
+    Freeze,
+    Black,
+    /// Filled with the shared color 0
+    Color0,
+}
+
+impl std::convert::From<u8> for MaskMode {
+    fn from(byte: u8) -> MaskMode {
+        match byte & 0x03 {
+            0x01 => MaskMode::Freeze,
+            0x02 => MaskMode::Black,
+            0x03 => MaskMode::Color0,
+            _ => MaskMode::Cancel,
+        }
+    }
+}
+
+/// Data sent through the Game Boy screen by the *_TRN commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Transfer {
+    /// PAL_TRN: the 512 system palettes
+    Palettes,
+    /// CHR_TRN: border tiles 0x00-0x7F, or 0x80-0xFF
+    Tiles { is_upper_half: bool },
+    /// PCT_TRN: the border tile map and palettes 4-7
+    Border,
+    /// ATTR_TRN: the attribute files
+    Attributes,
+}
+
+/// Super Game Boy: colors the Game Boy screen and surrounds it with a border
+///
+/// The game sends commands as packets through the joypad register, bulk data (palettes, border tiles)
+/// is transferred by displaying it on the Game Boy screen.
+///
+/// https://gbdev.io/pandocs/SGB_Functions.html
+pub struct Sgb {
+    /// Packets received for the current command, a command can be up to 7 packets long
+    command: Vec<u8>,
+    /// Palettes 0-3 used to color the Game Boy screen (BGR555), color 0 is shared by all of them
+    pub palettes: [[u16; 4]; 4],
+    /// System palettes loaded by PAL_TRN, picked from by PAL_SET
+    system_palettes: Vec<[u16; 4]>,
+    /// Palette (0-3) of every 8x8 cell of the Game Boy screen
+    pub attributes: [u8; ATTR_WIDTH * ATTR_HEIGHT],
+    /// Attribute files loaded by ATTR_TRN, applied by ATTR_SET/PAL_SET
+    attribute_files: Vec<u8>,
+    /// 256 SNES 4bpp border tiles loaded by CHR_TRN
+    border_tiles: Vec<u8>,
+    /// Border tile map loaded by PCT_TRN: tile (bits 0-9), palette (10-12), X flip (14), Y flip (15)
+    border_map: Vec<u16>,
+    /// Border palettes 4-7 loaded by PCT_TRN, color 0 is transparent
+    border_palettes: [[u16; 16]; 4],
+    pub mask: MaskMode,
+    /// VRAM transfer waiting for the next full frame, and whether that frame has started
+    pending_transfer: Option<(Transfer, bool)>,
+    /// Set when MLT_REQ changed the amount of controllers, to be forwarded by the bus to the joypad
+    pub multiplayer_request: Option<u8>,
+    /// Colored Game Boy screen, kept as is while the mask freezes it
+    game_screen: Vec<u8>,
+    /// Game Boy screen and border, composited at every V-Blank
+    screen_buffer: Vec<u8>, // RGBA
+}
+
+impl Sgb {
+    pub fn new() -> Self {
+        let mut sgb = Sgb {
+            command: Vec::with_capacity(7 * 16),
+            palettes: [DEFAULT_PALETTE; 4],
+            system_palettes: vec![[0; 4]; 512],
+            attributes: [0; ATTR_WIDTH * ATTR_HEIGHT],
+            attribute_files: vec![0; ATTR_FILE_COUNT * ATTR_FILE_SIZE],
+            border_tiles: vec![0; 256 * BORDER_TILE_SIZE],
+            border_map: vec![0; BORDER_MAP_WIDTH * BORDER_MAP_HEIGHT],
+            border_palettes: [[0; 16]; 4],
+            mask: MaskMode::Cancel,
+            pending_transfer: None,
+            multiplayer_request: None,
+            game_screen: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT * 4],
+            screen_buffer: vec![0; SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT * 4],
+        };
+        sgb.composite(&[0xFF; SCREEN_WIDTH * SCREEN_HEIGHT * 4]);
+
+        sgb
+    }
+
+    /// Handle a packet received through the joypad register, running the command once all of its packets arrived
+    ///
+    /// The lower 3 bits of the first byte hold the amount of packets, the upper 5 bits the command.
+    pub fn receive_packet(&mut self, packet: &[u8; 16]) {
+        self.command.extend_from_slice(packet);
+
+        let packet_count = (self.command[0] & 0x07).max(1) as usize;
+        if self.command.len() >= packet_count * 16 {
+            let command = std::mem::take(&mut self.command);
+            self.execute(&command);
+        }
+    }
+
+    fn execute(&mut self, data: &[u8]) {
+        match data[0] >> 3 {
+            0x00 => self.set_palette_pair(data, 0, 1), // PAL01
+            0x01 => self.set_palette_pair(data, 2, 3), // PAL23
+            0x02 => self.set_palette_pair(data, 0, 3), // PAL03
+            0x03 => self.set_palette_pair(data, 1, 2), // PAL12
+            0x04 => self.attr_blk(data),
+            0x05 => self.attr_lin(data),
+            0x06 => self.attr_div(data),
+            0x07 => self.attr_chr(data),
+            0x0A => self.pal_set(data),
+            0x0B => self.request_transfer(Transfer::Palettes), // PAL_TRN
+            0x11 => {
+                // MLT_REQ
+                let player_count = match data[1] & 0x03 {
+                    0x01 => 2,
+                    0x03 => 4,
+                    _ => 1,
+                };
+                self.multiplayer_request = Some(player_count);
+            }
+            0x13 => self.request_transfer(Transfer::Tiles {
+                is_upper_half: data[1] & 0x01 != 0,
+            }), // CHR_TRN
+            0x14 => self.request_transfer(Transfer::Border), // PCT_TRN
+            0x15 => self.request_transfer(Transfer::Attributes), // ATTR_TRN
+            0x16 => self.attr_set(data[1]),
+            0x17 => self.mask = MaskMode::from(data[1]), // MASK_EN
+            // Sound, SNES program and system commands are not supported
+            _ => (),
+        }
+    }
+
+    //
+    // Palettes
+    //
+
+    /// PAL01/PAL23/PAL03/PAL12: set colors 1-3 of two palettes, and the shared color 0
+    fn set_palette_pair(&mut self, data: &[u8], first: usize, second: usize) {
+        let color = |index: usize| u16::from_le_bytes([data[1 + index * 2], data[2 + index * 2]]);
+
+        for palette in self.palettes.iter_mut() {
+            palette[0] = color(0);
+        }
+        for index in 1..4 {
+            self.palettes[first][index] = color(index);
+            self.palettes[second][index] = color(index + 3);
+        }
+    }
+
+    /// PAL_SET: copy 4 system palettes into palettes 0-3, optionally applying an attribute file
+    fn pal_set(&mut self, data: &[u8]) {
+        for index in 0..4 {
+            let id = u16::from_le_bytes([data[1 + index * 2], data[2 + index * 2]]) & 0x1FF;
+            self.palettes[index] = self.system_palettes[id as usize];
+        }
+        // Color 0 of the first palette is used by all of them
+        let color_0 = self.palettes[0][0];
+        for palette in self.palettes.iter_mut() {
+            palette[0] = color_0;
+        }
+
+        let flags = data[9];
+        if flags & 0x80 != 0 {
+            self.apply_attribute_file(flags & 0x3F);
+        }
+        if flags & 0x40 != 0 {
+            self.mask = MaskMode::Cancel;
+        }
+    }
+
+    //
+    // Attributes
+    //
+
+    fn set_attribute(&mut self, x: usize, y: usize, palette: u8) {
+        if x < ATTR_WIDTH && y < ATTR_HEIGHT {
+            self.attributes[y * ATTR_WIDTH + x] = palette & 0x03;
+        }
+    }
+
+    /// ATTR_BLK: color the inside, border and/or outside of up to 18 rectangles
+    fn attr_blk(&mut self, data: &[u8]) {
+        let count = data[1] as usize;
+
+        for set in data[2..].chunks_exact(6).take(count) {
+            let control = set[0];
+            let inside = set[1] & 0x03;
+            let border = (set[1] >> 2) & 0x03;
+            let outside = (set[1] >> 4) & 0x03;
+            let (x1, y1, x2, y2) = (
+                set[2] as usize,
+                set[3] as usize,
+                set[4] as usize,
+                set[5] as usize,
+            );
+
+            // Only changing the inside or the outside also changes the border
+            let border = match control & 0x07 {
+                0x01 => Some(inside),
+                0x04 => Some(outside),
+                _ if control & 0x02 != 0 => Some(border),
+                _ => None,
+            };
+            let inside = (control & 0x01 != 0).then_some(inside);
+            let outside = (control & 0x04 != 0).then_some(outside);
+
+            for y in 0..ATTR_HEIGHT {
+                for x in 0..ATTR_WIDTH {
+                    let palette = if x > x1 && x < x2 && y > y1 && y < y2 {
+                        inside
+                    } else if x >= x1 && x <= x2 && y >= y1 && y <= y2 {
+                        border
+                    } else {
+                        outside
+                    };
+
+                    if let Some(palette) = palette {
+                        self.set_attribute(x, y, palette);
+                    }
+                }
+            }
+        }
+    }
+
+    /// ATTR_LIN: color entire rows or columns
+    fn attr_lin(&mut self, data: &[u8]) {
+        let count = data[1] as usize;
+
+        for &line in data[2..].iter().take(count) {
+            let number = (line & 0x1F) as usize;
+            let palette = (line >> 5) & 0x03;
+
+            if line & 0x80 != 0 {
+                // Horizontal line
+                for x in 0..ATTR_WIDTH {
+                    self.set_attribute(x, number, palette);
+                }
+            } else {
+                // Vertical line
+                for y in 0..ATTR_HEIGHT {
+                    self.set_attribute(number, y, palette);
+                }
+            }
+        }
+    }
+
+    /// ATTR_DIV: split the screen in two along a row or column, the line itself can have its own palette
+    fn attr_div(&mut self, data: &[u8]) {
+        let below = data[1] & 0x03; // Or right of the line
+        let above = (data[1] >> 2) & 0x03; // Or left of the line
+        let on_line = (data[1] >> 4) & 0x03;
+        let is_horizontal = data[1] & 0x40 != 0;
+        let coordinate = data[2] as usize;
+
+        for y in 0..ATTR_HEIGHT {
+            for x in 0..ATTR_WIDTH {
+                let position = if is_horizontal { y } else { x };
+                let palette = match position.cmp(&coordinate) {
+                    Ordering::Less => above,
+                    Ordering::Equal => on_line,
+                    Ordering::Greater => below,
+                };
+                self.set_attribute(x, y, palette);
+            }
+        }
+    }
+
+    /// ATTR_CHR: set the palette of consecutive cells, 4 cells per byte (upper bits first)
+    fn attr_chr(&mut self, data: &[u8]) {
+        let (mut x, mut y) = (data[1] as usize, data[2] as usize);
+        let count = u16::from_le_bytes([data[3], data[4]]) as usize;
+        let is_vertical = data[5] & 0x01 != 0;
+
+        for index in 0..count.min(ATTR_WIDTH * ATTR_HEIGHT) {
+            if x >= ATTR_WIDTH || y >= ATTR_HEIGHT {
+                break;
+            }
+            let byte = match data.get(6 + index / 4) {
+                Some(byte) => *byte,
+                None => break,
+            };
+            self.set_attribute(x, y, byte >> (6 - (index % 4) * 2));
+
+            // Wrap to the next column/row
+            if is_vertical {
+                y += 1;
+                if y == ATTR_HEIGHT {
+                    y = 0;
+                    x += 1;
+                }
+            } else {
+                x += 1;
+                if x == ATTR_WIDTH {
+                    x = 0;
+                    y += 1;
+                }
+            }
+        }
+    }
+
+    /// ATTR_SET: apply an attribute file (bits 0-5), bit 6 also cancels the mask
+    fn attr_set(&mut self, byte: u8) {
+        self.apply_attribute_file(byte & 0x3F);
+        if byte & 0x40 != 0 {
+            self.mask = MaskMode::Cancel;
+        }
+    }
+
+    fn apply_attribute_file(&mut self, file: u8) {
+        let file = file as usize;
+        if file >= ATTR_FILE_COUNT {
+            return;
+        }
+
+        let start = file * ATTR_FILE_SIZE;
+        for cell in 0..ATTR_WIDTH * ATTR_HEIGHT {
+            let byte = self.attribute_files[start + cell / 4];
+            self.attributes[cell] = (byte >> (6 - (cell % 4) * 2)) & 0x03;
+        }
+    }
+
+    //
+    // VRAM transfers
+    //
+
+    /// The data is read from the first full frame displayed after the command
+    fn request_transfer(&mut self, transfer: Transfer) {
+        self.pending_transfer = Some((transfer, false));
+    }
+
+    /// Rebuild the 4KB sent by a VRAM transfer from the displayed frame
+    ///
+    /// The SGB only sees the LCD output: the first 256 tiles of the screen (20 per row)
+    /// are turned back into 2bpp tile data.
+    fn transfer_data(display: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(TRANSFER_SIZE);
+
+        for tile in 0..TRANSFER_SIZE / 16 {
+            let (tile_x, tile_y) = ((tile % ATTR_WIDTH) * 8, (tile / ATTR_WIDTH) * 8);
+            for row in 0..8 {
+                let (mut low, mut high) = (0u8, 0u8);
+                for column in 0..8 {
+                    let shade = Sgb::shade_at(display, tile_x + column, tile_y + row);
+                    low = (low << 1) | (shade & 0x01);
+                    high = (high << 1) | (shade >> 1);
+                }
+                data.push(low);
+                data.push(high);
+            }
+        }
+
+        data
+    }
+
+    fn run_transfer(&mut self, transfer: Transfer, data: &[u8]) {
+        let color = |bytes: &[u8], index: usize| {
+            u16::from_le_bytes([bytes[index * 2], bytes[index * 2 + 1]])
+        };
+
+        match transfer {
+            Transfer::Palettes => {
+                for (palette, bytes) in self.system_palettes.iter_mut().zip(data.chunks_exact(8)) {
+                    for index in 0..4 {
+                        palette[index] = color(bytes, index);
+                    }
+                }
+            }
+            Transfer::Tiles { is_upper_half } => {
+                let start = if is_upper_half { TRANSFER_SIZE } else { 0 };
+                self.border_tiles[start..start + TRANSFER_SIZE].copy_from_slice(data);
+            }
+            Transfer::Border => {
+                for (index, entry) in self.border_map.iter_mut().enumerate() {
+                    *entry = color(data, index);
+                }
+                for (palette, bytes) in self
+                    .border_palettes
+                    .iter_mut()
+                    .zip(data[0x800..].chunks_exact(32))
+                {
+                    for index in 0..16 {
+                        palette[index] = color(bytes, index);
+                    }
+                }
+            }
+            Transfer::Attributes => {
+                let size = self.attribute_files.len();
+                self.attribute_files.copy_from_slice(&data[..size]);
+            }
+        }
+    }
+
+    //
+    // Display
+    //
+
+    /// The Game Boy finished drawing a frame: run a pending transfer and composite the SNES picture
+    ///
+    /// `display` is the Game Boy screen (RGBA, shades of gray).
+    pub fn vblank(&mut self, display: &[u8]) {
+        if let Some((transfer, is_frame_started)) = self.pending_transfer {
+            if is_frame_started {
+                self.pending_transfer = None;
+                self.run_transfer(transfer, &Sgb::transfer_data(display));
+            } else {
+                self.pending_transfer = Some((transfer, true));
+            }
+        }
+
+        self.composite(display);
+    }
+
+    fn composite(&mut self, display: &[u8]) {
+        // Color the Game Boy screen
+        let backdrop = Sgb::rgb(self.palettes[0][0]);
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let color = match self.mask {
+                    MaskMode::Cancel => {
+                        let palette = self.attributes[(y / 8) * ATTR_WIDTH + (x / 8)] as usize;
+                        let shade = Sgb::shade_at(display, x, y) as usize;
+                        Sgb::rgb(self.palettes[palette][shade])
+                    }
+                    MaskMode::Freeze => continue,
+                    MaskMode::Black => [0, 0, 0, 0xFF],
+                    MaskMode::Color0 => backdrop,
+                };
+                let index = (y * SCREEN_WIDTH + x) * 4;
+                self.game_screen[index..index + 4].copy_from_slice(&color);
+            }
+        }
+
+        // Backdrop and Game Boy screen
+        for pixel in self.screen_buffer.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&backdrop);
+        }
+        for (y, row) in self.game_screen.chunks_exact(SCREEN_WIDTH * 4).enumerate() {
+            let index = ((SCREEN_Y + y) * SGB_SCREEN_WIDTH + SCREEN_X) * 4;
+            self.screen_buffer[index..index + row.len()].copy_from_slice(row);
+        }
+
+        // The border is drawn on top, its color 0 is transparent
+        for map_y in 0..BORDER_MAP_HEIGHT {
+            for map_x in 0..BORDER_MAP_WIDTH {
+                let entry = self.border_map[map_y * BORDER_MAP_WIDTH + map_x];
+                let tile_start = (entry & 0xFF) as usize * BORDER_TILE_SIZE;
+                let tile = &self.border_tiles[tile_start..tile_start + BORDER_TILE_SIZE];
+                let palette = &self.border_palettes[((entry >> 10) & 0x03) as usize];
+                let (is_x_flipped, is_y_flipped) = (entry & 0x4000 != 0, entry & 0x8000 != 0);
+
+                for row in 0..8 {
+                    let tile_row = if is_y_flipped { 7 - row } else { row };
+                    // Bit planes 0-1 are interleaved in the first 16 bytes, 2-3 in the last 16
+                    let planes = [
+                        tile[tile_row * 2],
+                        tile[tile_row * 2 + 1],
+                        tile[16 + tile_row * 2],
+                        tile[16 + tile_row * 2 + 1],
+                    ];
+
+                    for column in 0..8 {
+                        let bit = if is_x_flipped { column } else { 7 - column };
+                        let color_index =
+                            planes.iter().enumerate().fold(0, |color, (plane, byte)| {
+                                color | (((byte >> bit) & 0x01) << plane)
+                            });
+                        if color_index == 0 {
+                            continue;
+                        }
+
+                        let (x, y) = (map_x * 8 + column, map_y * 8 + row);
+                        let index = (y * SGB_SCREEN_WIDTH + x) * 4;
+                        self.screen_buffer[index..index + 4]
+                            .copy_from_slice(&Sgb::rgb(palette[color_index as usize]));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Shade (0-3) of a pixel of the Game Boy screen, from the gray levels of `Pixel::rgb_value`
+    fn shade_at(display: &[u8], x: usize, y: usize) -> u8 {
+        match display[(y * SCREEN_WIDTH + x) * 4] {
+            255 => 0,
+            170 => 1,
+            85 => 2,
+            _ => 3,
+        }
+    }
+
+    /// Convert an SNES color (BGR555) to RGBA
+    fn rgb(color: u16) -> [u8; 4] {
+        let channel = |shift: u16| {
+            let value = ((color >> shift) & 0x1F) as u8;
+            (value << 3) | (value >> 2)
+        };
+
+        [channel(0), channel(5), channel(10), 0xFF]
+    }
+
+    /// The composited SNES picture (RGBA, `SGB_SCREEN_WIDTH` x `SGB_SCREEN_HEIGHT`)
+    pub fn get_display(&self) -> &[u8] {
+        &self.screen_buffer
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use super::instruction::{BankedAddress, Symbols};
+
+/// Names of addresses, as found in the .sym files written by RGBDS (rgblink -n) and other tools
+///
+/// Every line is "bank:address name", in hexadecimal, and ';' starts a comment. Section headers
+/// ("[labels]") of no$gmb style files are skipped.
+#[derive(Default)]
+pub struct SymbolTable {
+    names: HashMap<BankedAddress, String>,
+    addresses: HashMap<String, BankedAddress>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    /// Parse the contents of a .sym file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = SymbolTable::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() || line.starts_with('[') {
+                continue;
+            }
+
+            let invalid = || format!("line {}: expected \"bank:address name\"", index + 1);
+            let (location, name) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let (bank, address) = location.split_once(':').ok_or_else(invalid)?;
+            let bank = u16::from_str_radix(bank, 16).map_err(|_| invalid())?;
+            let address = u16::from_str_radix(address, 16).map_err(|_| invalid())?;
+
+            let name = name.trim().to_string();
+            table.insert(BankedAddress::new(address, bank), name.clone());
+            // Addresses only hold a ROM bank, but looking up an external RAM label by name keeps its bank
+            if (0xA000..=0xBFFF).contains(&address) {
+                table
+                    .addresses
+                    .insert(name, BankedAddress { bank, address });
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// The first name given to an address is the one used to show it
+    pub fn insert(&mut self, address: BankedAddress, name: String) {
+        self.names.entry(address).or_insert_with(|| name.clone());
+        self.addresses.insert(name, address);
+    }
+
+    /// Address of a name, along with its bank when it is in switchable ROM or external RAM
+    pub fn address_of(&self, name: &str) -> Option<BankedAddress> {
+        self.addresses.get(name).copied()
+    }
+
+    /// Name of the closest symbol at or before `address`, in the same bank and memory area, with the
+    /// distance to it ("Main+$12")
+    pub fn describe(&self, address: BankedAddress) -> Option<String> {
+        let (symbol, name) = self
+            .names
+            .iter()
+            .filter(|(symbol, _)| {
+                symbol.bank == address.bank
+                    && area(symbol.address) == area(address.address)
+                    && symbol.address <= address.address
+            })
+            .max_by_key(|(symbol, _)| symbol.address)?;
+
+        match address.address - symbol.address {
+            0 => Some(name.clone()),
+            offset => Some(format!("{}+${:X}", name, offset)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&BankedAddress, &String)> {
+        self.names.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl Symbols for SymbolTable {
+    fn symbol(&self, address: BankedAddress) -> Option<&str> {
+        self.names.get(&address).map(|name| name.as_str())
+    }
+}
+
+/// Memory area of an address, a symbol does not extend past the end of its area
+fn area(address: u16) -> u8 {
+    match address {
+        0x0000..=0x3FFF => 0, // ROM0
+        0x4000..=0x7FFF => 1, // ROMX
+        0x8000..=0x9FFF => 2, // VRAM
+        0xA000..=0xBFFF => 3, // SRAM
+        0xC000..=0xDFFF => 4, // WRAM
+        0xE000..=0xFE9F => 5, // Echo RAM and OAM
+        0xFEA0..=0xFF7F => 6, // I/O registers
+        _ => 7,               // HRAM and IE
+    }
+}
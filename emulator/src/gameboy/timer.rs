@@ -1,20 +1,28 @@
 use super::{Bus, Interrupt};
 
 const CPU_CLOCK: f64 = 4194304f64;
-const TIMER_CLOCK_SPEED: u16 = 16384;
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// DIV/TIMA/TMA/TAC - https://gbdev.io/pandocs/Timer_Obscure_Behaviour.html
+///
+/// DIV is the upper byte of a 16-bit system counter that is incremented every T-cycle.
+/// TIMA is incremented on the falling edge of (the bit of the system counter selected by TAC) AND (the timer enable bit).
+/// Since that signal can also fall because of a write to DIV or TAC, those writes can increment TIMA as well.
+///
+/// The timer is not ticked every M-cycle, the bus has it catch up on the elapsed time (`advance`)
+/// when its interrupt is due or its registers are accessed.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Timer {
     // External Memory Mapped
-    div: u8, // Divider register - 0xFF04  |   These are the upper 8 bits that compose the internal div
     tima: u8, // Timer counter - 0xFF05
-    tma: u8, // Timer modulo - 0xFF06
-    tac: u8, // Timer control - 0xFF07
+    tma: u8,  // Timer modulo - 0xFF06
+    tac: u8,  // Timer control - 0xFF07
     // Internal Registers
-    internal_div: u16,
-    internal_tima: u16,
+    system_counter: u16, // Upper 8 bits are the divider register - 0xFF04
     prev_and_result: bool,
+    /// TIMA overflowed during the last M-cycle, it reads 0 until TMA is loaded at the end of the next one
     pending_tima_overflow: bool,
+    /// TMA was loaded into TIMA during the last M-cycle, writes to TIMA are ignored and writes to TMA go through
+    is_reloading_tima: bool,
     // Raise timer interrupt
     pub raise_interrupt: Option<Interrupt>,
 }
@@ -22,25 +30,23 @@ pub struct Timer {
 impl Timer {
     pub fn new() -> Self {
         Timer {
-            div: 0,
             tima: 0,
             tma: 0,
             tac: 0,
-            internal_div: 0,
-            internal_tima: 0,
+            system_counter: 0,
             prev_and_result: false,
             pending_tima_overflow: false,
+            is_reloading_tima: false,
             raise_interrupt: None,
         }
     }
 
     pub fn read_byte(&self, index: usize) -> u8 {
         match index {
-            //0x00 => ((self.internal_div & 0xFF00) >> 8) as u8,
-            0x00 => self.div,
+            0x00 => (self.system_counter >> 8) as u8,
             0x01 => self.tima,
             0x02 => self.tma,
-            0x03 => self.tac,
+            0x03 => self.tac | 0xF8, // Upper bits are unused
             _ => panic!(
                 "Timer should not be requesting to read an offset of: {:#X}",
                 index
@@ -50,17 +56,30 @@ impl Timer {
 
     pub fn write_byte(&mut self, index: usize, value: u8) {
         match index {
-            //0x00 => self.internal_div = 0x00, // Writing any value to this register resets it to 0x00
             0x00 => {
-                self.div = 0;
-                self.internal_div = 0;
+                // Writing any value to this register resets the whole system counter
+                self.system_counter = 0;
+                self.detect_falling_edge();
             }
             0x01 => {
-                self.tima = value;
-                //self.pending_tima_overflow = false
+                if self.pending_tima_overflow {
+                    // Writing during the overflow cycle cancels the reload (and interrupt)
+                    self.pending_tima_overflow = false;
+                    self.tima = value;
+                } else if !self.is_reloading_tima {
+                    self.tima = value;
+                }
+            }
+            0x02 => {
+                self.tma = value;
+                if self.is_reloading_tima {
+                    self.tima = value;
+                }
+            }
+            0x03 => {
+                self.tac = value & 0x7;
+                self.detect_falling_edge();
             }
-            0x02 => self.tma = value,
-            0x03 => self.tac = value,
             _ => panic!(
                 "Timer should not be requesting to write an offset of: {:#X}",
                 index
@@ -68,60 +87,106 @@ impl Timer {
         };
     }
 
-    pub fn reset_div(&mut self) {
-        self.div = 0;
+    /// Set the full 16-bit system counter, DIV being its upper byte
+    pub fn set_system_counter(&mut self, value: u16) {
+        self.system_counter = value;
+        self.prev_and_result = self.and_result();
     }
 
-    pub fn tick(&mut self, t_cycles: u8) {
-        // Increment DIV counter
-        self.internal_div += t_cycles as u16;
-
-        while self.internal_div >= 256 {
-            self.div = self.div.wrapping_add(1);
-            self.internal_div -= 256;
+    /// Catch up on the supplied amount of T-cycles
+    ///
+    /// M-cycles in which nothing but the system counter changes are skipped over all at once.
+    pub fn advance(&mut self, t_cycles: u64) {
+        let mut m_cycles = t_cycles / 4;
+
+        while m_cycles > 0 {
+            let skipped = self.quiet_m_cycles().min(m_cycles);
+            if skipped == 0 {
+                self.tick();
+                m_cycles -= 1;
+            } else {
+                // Truncating is fine, the system counter wraps around
+                self.system_counter = self.system_counter.wrapping_add((skipped * 4) as u16);
+                self.prev_and_result = self.and_result();
+                m_cycles -= skipped;
+            }
         }
+    }
 
-        // Increment TIMA
-        // (only if clock is enabled)
-        if self.is_timer_enabled() {
-            let clock_speed_cycles: u16 = self.get_clock_select();
+    /// The timer is clocked once per M-cycle
+    fn tick(&mut self) {
+        self.is_reloading_tima = false;
 
-            // Handle delayed TIMA overflow
-            if self.pending_tima_overflow {
-                self.raise_interrupt = Some(Interrupt::Timer);
-                self.tima = self.tma;
-                self.pending_tima_overflow = false;
-            }
+        // Handle delayed TIMA overflow
+        if self.pending_tima_overflow {
+            self.raise_interrupt = Some(Interrupt::Timer);
+            self.tima = self.tma;
+            self.pending_tima_overflow = false;
+            self.is_reloading_tima = true;
+        }
 
-            // Actually increment TIMA
-            self.internal_tima += t_cycles as u16;
+        self.system_counter = self.system_counter.wrapping_add(4);
+        self.detect_falling_edge();
+    }
 
-            while self.internal_tima >= clock_speed_cycles {
-                if self.tima == u8::MAX {
-                    // TIMA overflow
-                    self.pending_tima_overflow = true;
-                    break;
-                }
+    /// How many of the upcoming M-cycles only increment the system counter (no TIMA increment, overflow or reload)
+    fn quiet_m_cycles(&self) -> u64 {
+        if self.pending_tima_overflow || self.is_reloading_tima {
+            0
+        } else if !self.is_timer_enabled() {
+            u64::MAX
+        } else {
+            self.cycles_until_falling_edge() / 4 - 1
+        }
+    }
 
-                self.internal_tima -= clock_speed_cycles;
-                self.tima = self.tima.wrapping_add(1);
-            }
+    /// T-cycles until the timer signal falls: when the system counter reaches the next multiple of twice the TAC bit
+    fn cycles_until_falling_edge(&self) -> u64 {
+        let period = self.get_tac_bit_mask() as u64 * 2;
+        period - (self.system_counter as u64 % period)
+    }
+
+    /// T-cycles until the timer interrupt is requested, if it is running
+    ///
+    /// TIMA overflows on the falling edge that increments it past 0xFF, the interrupt follows one M-cycle later.
+    pub fn cycles_until_interrupt(&self) -> Option<u64> {
+        if self.pending_tima_overflow {
+            Some(4)
+        } else if self.is_timer_enabled() {
+            let period = self.get_tac_bit_mask() as u64 * 2;
+            let increments_left = (u8::MAX - self.tima) as u64;
+            Some(self.cycles_until_falling_edge() + increments_left * period + 4)
+        } else {
+            None
         }
     }
 
-    fn is_timer_enabled(&self) -> bool {
-        return self.tac & 0x4 != 0;
+    /// The signal TIMA is clocked by: the TAC selected bit of the system counter AND the timer enable bit
+    fn and_result(&self) -> bool {
+        self.is_timer_enabled() && self.system_counter & self.get_tac_bit_mask() != 0
     }
 
-    /// For internal use (timer.rs)
-    fn get_clock_select(&self) -> u16 {
-        match self.tac & 0x3 {
-            0b00 => 1024, // Frequency 4096
-            0b01 => 16,   // Frqeuency 262144
-            0b10 => 64,   // Frequency 65536
-            0b11 => 256,  // Frequency 16382
-            _ => panic!("Invalid clock freq!"),
+    /// Increment TIMA if the timer signal went from high to low
+    fn detect_falling_edge(&mut self) {
+        let and_result = self.and_result();
+        if self.prev_and_result && !and_result {
+            self.increment_tima();
         }
+        self.prev_and_result = and_result;
+    }
+
+    fn increment_tima(&mut self) {
+        if self.tima == u8::MAX {
+            // TIMA overflow - TMA is loaded (and the interrupt requested) one M-cycle later
+            self.tima = 0;
+            self.pending_tima_overflow = true;
+        } else {
+            self.tima += 1;
+        }
+    }
+
+    fn is_timer_enabled(&self) -> bool {
+        return self.tac & 0x4 != 0;
     }
 
     fn get_tac_bit_mask(&self) -> u16 {
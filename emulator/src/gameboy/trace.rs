@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+/// Destination for CPU trace lines
+///
+/// One line is emitted right before every instruction is executed, in the Gameboy Doctor format:
+/// `A:00 F:11 B:22 C:33 D:44 E:55 H:66 L:77 SP:8888 PC:9999 PCMEM:AA,BB,CC,DD`
+pub trait TraceSink {
+    fn trace(&mut self, line: &str);
+}
+
+/// Writes every trace line to a file
+pub struct FileTraceSink {
+    writer: BufWriter<File>,
+}
+
+impl FileTraceSink {
+    /// Create (or truncate) the log file at the supplied path
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(FileTraceSink {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+}
+
+impl TraceSink for FileTraceSink {
+    fn trace(&mut self, line: &str) {
+        if let Err(e) = writeln!(self.writer, "{}", line) {
+            eprintln!("Couldn't write to trace file: {}", e);
+        }
+    }
+}
+
+/// Keep every trace line in memory
+impl TraceSink for Vec<String> {
+    fn trace(&mut self, line: &str) {
+        self.push(line.to_string());
+    }
+}
+
+/// Stream trace lines to another part of the program (e.g. a trace comparison)
+impl TraceSink for Sender<String> {
+    fn trace(&mut self, line: &str) {
+        let _ = self.send(line.to_string());
+    }
+}
+
+/// Split a trace line into its `(field, value)` pairs
+///
+/// *Ex:* `"A:01 F:B0"` => `[("A", "01"), ("F", "B0")]`
+pub fn parse_trace_line(line: &str) -> Vec<(&str, &str)> {
+    line.split_whitespace()
+        .filter_map(|field| field.split_once(':'))
+        .collect()
+}
+
+/// Describe every field that differs between two trace lines
+///
+/// Differences in the F register are also broken down into the individual flags.
+pub fn diff_trace_lines(expected: &str, actual: &str) -> Vec<String> {
+    let expected_fields = parse_trace_line(expected);
+    let actual_fields = parse_trace_line(actual);
+    let mut differences = Vec::new();
+
+    for (name, expected_value) in expected_fields.iter() {
+        let actual_value = actual_fields
+            .iter()
+            .find(|(actual_name, _)| actual_name == name)
+            .map(|(_, value)| *value)
+            .unwrap_or("--");
+
+        if !expected_value.eq_ignore_ascii_case(actual_value) {
+            differences.push(format!(
+                "{:>5}: expected {}, got {}",
+                name, expected_value, actual_value
+            ));
+
+            if *name == "F" {
+                if let (Ok(expected_f), Ok(actual_f)) = (
+                    u8::from_str_radix(expected_value, 16),
+                    u8::from_str_radix(actual_value, 16),
+                ) {
+                    for (bit, flag) in [(7, "Z"), (6, "N"), (5, "H"), (4, "C")] {
+                        let e = (expected_f >> bit) & 0x1;
+                        let a = (actual_f >> bit) & 0x1;
+                        if e != a {
+                            differences.push(format!("{:>5}: expected {}, got {}", flag, e, a));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    differences
+}
@@ -0,0 +1,152 @@
+use std::cell::Cell;
+use std::ops::RangeInclusive;
+
+/// Accesses a watchpoint breaks on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    /// Reads and writes
+    Access,
+}
+
+/// Values a watchpoint breaks on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchCondition {
+    Always,
+    /// The byte read or written is this value
+    Equals(u8),
+    /// A write changes the byte in memory (reads never match)
+    Changes,
+}
+
+/// A memory access that matched a watchpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchpointHit {
+    /// Index of the watchpoint in `Watchpoints::list`
+    pub index: usize,
+    pub address: u16,
+    /// Byte read or written
+    pub value: u8,
+    pub is_write: bool,
+}
+
+/// Break when the CPU accesses a range of memory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    /// ROM bank (0x4000-0x7FFF) or external RAM bank (0xA000-0xBFFF) the range is in, any bank when None
+    pub bank: Option<u16>,
+    pub kind: WatchKind,
+    pub condition: WatchCondition,
+    /// Accesses that matched
+    pub hit_count: Cell<u32>,
+}
+
+impl Watchpoint {
+    pub fn new(range: RangeInclusive<u16>, kind: WatchKind, condition: WatchCondition) -> Self {
+        Watchpoint {
+            range,
+            bank: None,
+            kind,
+            condition,
+            hit_count: Cell::new(0),
+        }
+    }
+
+    /// Only break on accesses to this ROM or external RAM bank
+    pub fn in_bank(mut self, bank: u16) -> Self {
+        self.bank = Some(bank);
+        self
+    }
+
+    /// `bank` is the bank mapped at the address, `old_value` the byte a write replaces (when needed)
+    fn matches(
+        &self,
+        address: u16,
+        bank: u16,
+        value: u8,
+        is_write: bool,
+        old_value: Option<u8>,
+    ) -> bool {
+        let is_kind = match self.kind {
+            WatchKind::Read => !is_write,
+            WatchKind::Write => is_write,
+            WatchKind::Access => true,
+        };
+        let is_value = match self.condition {
+            WatchCondition::Always => true,
+            WatchCondition::Equals(expected) => value == expected,
+            WatchCondition::Changes => old_value.is_some_and(|old_value| old_value != value),
+        };
+        let is_bank = match (address, self.bank) {
+            (0x4000..=0x7FFF | 0xA000..=0xBFFF, Some(expected)) => bank == expected,
+            _ => true,
+        };
+
+        is_kind && is_value && is_bank && self.range.contains(&address)
+    }
+}
+
+/// Watchpoints checked by the bus on every CPU access, the first hit is kept until it is taken
+#[derive(Default)]
+pub struct Watchpoints {
+    pub list: Vec<Watchpoint>,
+    hit: Cell<Option<WatchpointHit>>,
+    last_hit: Cell<Option<WatchpointHit>>,
+}
+
+impl Watchpoints {
+    pub fn new() -> Self {
+        Watchpoints::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn check_read(&self, address: u16, bank: u16, value: u8) {
+        self.check(address, bank, value, false, None);
+    }
+
+    /// `old_value` is only needed when `needs_old_value` says so
+    pub fn check_write(&self, address: u16, bank: u16, value: u8, old_value: Option<u8>) {
+        self.check(address, bank, value, true, old_value);
+    }
+
+    /// Count the watchpoints matching an access, and keep the first hit
+    fn check(&self, address: u16, bank: u16, value: u8, is_write: bool, old_value: Option<u8>) {
+        for (index, watchpoint) in self.list.iter().enumerate() {
+            if watchpoint.matches(address, bank, value, is_write, old_value) {
+                watchpoint.hit_count.set(watchpoint.hit_count.get() + 1);
+                let hit = WatchpointHit {
+                    index,
+                    address,
+                    value,
+                    is_write,
+                };
+                self.last_hit.set(Some(hit));
+                if self.hit.get().is_none() {
+                    self.hit.set(Some(hit));
+                }
+            }
+        }
+    }
+
+    /// Whether a write to `address` needs the byte it replaces
+    pub fn needs_old_value(&self, address: u16) -> bool {
+        self.list.iter().any(|watchpoint| {
+            watchpoint.condition == WatchCondition::Changes && watchpoint.range.contains(&address)
+        })
+    }
+
+    /// The first hit since the last call
+    pub fn take_hit(&self) -> Option<WatchpointHit> {
+        self.hit.take()
+    }
+
+    /// The most recent hit, taken or not
+    pub fn last_hit(&self) -> Option<WatchpointHit> {
+        self.last_hit.get()
+    }
+}
@@ -4,10 +4,17 @@ pub mod gameboy;
 pub use crate::gameboy::joypad::JoypadInputKey;
 pub use gameboy::Bus;
 pub use gameboy::CartridgeHeader;
+pub use gameboy::FrameResult;
 pub use gameboy::GameBoy;
+pub use gameboy::Instruction;
 pub use gameboy::Joypad;
+pub use gameboy::MemoryAccess;
+pub use gameboy::OamBugAccess;
+pub use gameboy::Model;
 pub use gameboy::Registers;
+pub use gameboy::Sgb;
 pub use gameboy::Timer;
+pub use gameboy::TraceSink;
 pub use gameboy::CPU;
 pub use gameboy::PPU;
 
@@ -15,4 +22,7 @@ const SCREEN_WIDTH: usize = 160;
 const SCREEN_HEIGHT: usize = 144;
 const DEBUGGER_SCREEN_WIDTH: usize = 16 * 8;
 const DEBUGGER_SCREEN_HEIGHT: usize = 32 * 8;
-const CYCLES_PER_FRAME: f64 = (4194304 / 60) as f64;
+/// T-cycles in a frame: 154 lines of 456 dots
+pub const CYCLES_PER_FRAME: u32 = 70224;
+/// Frames per second: 4194304 Hz / 70224 cycles (~59.7275 Hz)
+pub const FRAME_RATE: f64 = 4194304.0 / CYCLES_PER_FRAME as f64;
@@ -1,6 +1,6 @@
-use emulator::GameBoy;
+use emulator::{GameBoy, Model};
 
 fn main() {
     println!("Creating GameBoy!");
-    let _gameboy = GameBoy::new();
+    let _gameboy = GameBoy::new(Model::DMG);
 }
@@ -0,0 +1,158 @@
+//! Assembler tests, everything the disassembler writes must assemble back to the same bytes
+
+use emulator::gameboy::assembler::assemble;
+use emulator::gameboy::instruction::{NoSymbols, Syntax};
+use emulator::gameboy::{BankedAddress, SymbolTable};
+use emulator::Instruction;
+
+const ORIGIN: u16 = 0xC100;
+
+fn round_trip(bytes: &[u8], syntax: Syntax) -> Result<(), String> {
+    let (instruction, info) =
+        Instruction::decode(|address| bytes[(address - ORIGIN) as usize], ORIGIN);
+    let text = instruction.format_as(syntax, 0, &NoSymbols);
+    let expected = &bytes[..info.length as usize];
+
+    let assembled =
+        assemble(&text, ORIGIN, &SymbolTable::new()).map_err(|e| format!("{}: {}", text, e))?;
+    if assembled != expected {
+        return Err(format!(
+            "{}: {:02X?} instead of {:02X?}",
+            text, assembled, expected
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn every_instruction_assembles_back() {
+    let mut failures = Vec::new();
+
+    for opcode in 0..=0xFF {
+        for syntax in [Syntax::Debugger, Syntax::Rgbds] {
+            let bytes: Vec<u8> = if opcode == 0xCB {
+                (0..=0xFF)
+                    .map(|cb_opcode| vec![0xCB, cb_opcode])
+                    .collect::<Vec<_>>()
+                    .concat()
+            } else {
+                // STOP is always assembled with a 0 padding byte
+                vec![opcode, if opcode == 0x10 { 0x00 } else { 0xF0 }, 0x12]
+            };
+            for instruction in bytes.chunks(if opcode == 0xCB { 2 } else { 3 }) {
+                let mut padded = instruction.to_vec();
+                padded.resize(3, 0);
+                if let Err(error) = round_trip(&padded, syntax) {
+                    failures.push(error);
+                }
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn resolves_labels_and_expressions() {
+    let source = "
+        ; Wait for V-Blank
+        Wait:
+            ldh a, [$FF44]      ; LY
+            cp 144
+            jr c, Wait
+            ld hl, Table + 2 * 3
+            ld a, (hl+)
+            sub a, b
+            add 1
+            jp Done
+        Table: db 1, 2, $FF, -1
+            dw Wait, @
+        Done: ret
+    ";
+    assert_eq!(
+        assemble(source, 0xC000, &SymbolTable::new()),
+        Ok(vec![
+            0xF0, 0x44, // LDH A, ($FF44)
+            0xFE, 0x90, // CP $90
+            0x38, 0xFA, // JR C, $C000
+            0x21, 0x16, 0xC0, // LD HL, $C010 + 6
+            0x2A, // LD A, (HL+)
+            0x90, // SUB B
+            0xC6, 0x01, // ADD A, $01
+            0xC3, 0x18, 0xC0, // JP $C018
+            0x01, 0x02, 0xFF, 0xFF, // DB
+            0x00, 0xC0, 0x14, 0xC0, // DW
+            0xC9, // RET
+        ])
+    );
+}
+
+#[test]
+fn uses_symbols() {
+    let mut symbols = SymbolTable::new();
+    symbols.insert(BankedAddress::new(0xFF40, 0), "rLCDC".to_string());
+    symbols.insert(BankedAddress::new(0x4123, 2), "Banked".to_string());
+
+    assert_eq!(
+        assemble("ldh [rLCDC], a\ncall Banked", 0x0150, &symbols),
+        Ok(vec![0xE0, 0x40, 0xCD, 0x23, 0x41])
+    );
+}
+
+#[test]
+fn reports_errors_with_line_numbers() {
+    let symbols = SymbolTable::new();
+    assert_eq!(
+        assemble("nop\nld a, a, a", 0, &symbols),
+        Err("line 2: invalid operands for LD".to_string())
+    );
+    assert_eq!(
+        assemble("frobnicate", 0, &symbols),
+        Err("line 1: unknown instruction FROBNICATE".to_string())
+    );
+    assert_eq!(
+        assemble("jp Nowhere", 0, &symbols),
+        Err("line 1: unknown name Nowhere".to_string())
+    );
+    assert_eq!(
+        assemble("ld a, 256", 0, &symbols),
+        Err("line 1: 256 does not fit".to_string())
+    );
+    assert!(assemble("x: nop\nx: nop", 0, &symbols).is_err());
+    // Upper-casing changes the length of some characters
+    assert!(assemble("ld hl, sp+ıı", 0, &symbols).is_err());
+    assert_eq!(assemble("ld hl, Sp + 4", 0, &symbols), Ok(vec![0xF8, 0x04]));
+}
+
+#[test]
+fn relative_jumps_reach_128_bytes_back_and_127_forward() {
+    let symbols = SymbolTable::new();
+    // The offset is counted from the end of the JR
+    let far = |offset: i32| {
+        assemble(
+            &format!("Start: jr Start + 2 + {}", offset),
+            0x7F00,
+            &symbols,
+        )
+    };
+
+    assert_eq!(far(0), Ok(vec![0x18, 0x00]));
+    assert_eq!(far(127), Ok(vec![0x18, 0x7F]));
+    assert_eq!(far(-128), Ok(vec![0x18, 0x80]));
+    assert_eq!(
+        far(128),
+        Err("line 1: $7F82 is too far for a relative jump".to_string())
+    );
+    assert_eq!(
+        far(-129),
+        Err("line 1: $7E81 is too far for a relative jump".to_string())
+    );
+
+    // Labels defined after the jump
+    let source = format!("jr Far\ndb {}\nFar: nop", vec!["0"; 127].join(", "));
+    let bytes = assemble(&source, 0x7F00, &symbols).unwrap();
+    assert_eq!(bytes[..2], [0x18, 0x7F]);
+    assert_eq!(bytes.len(), 2 + 127 + 1);
+    let source = format!("jr Far\ndb {}\nFar: nop", vec!["0"; 128].join(", "));
+    assert!(assemble(&source, 0x7F00, &symbols).is_err());
+}
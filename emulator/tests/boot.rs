@@ -0,0 +1,113 @@
+//! Built-in boot sequence tests
+//!
+//! Every test boots a blank 32 KiB cartridge carrying the logo and a valid header checksum, unless the
+//! test breaks one of them on purpose.
+
+use emulator::gameboy::boot::BootState;
+use emulator::{GameBoy, Model};
+
+const LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+/// Steps after which the sequence should be long over (it lasts 0x84 frames of 17556 M-cycles)
+const MAX_STEPS: usize = 0x90 * 17556;
+
+fn rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100] = 0x18; // JR -2
+    rom[0x0101] = 0xFE;
+    rom[0x0104..0x0134].copy_from_slice(&LOGO);
+    rom[0x0134..0x0138].copy_from_slice(b"BOOT");
+    fix_header_checksum(&mut rom);
+    rom
+}
+
+fn fix_header_checksum(rom: &mut [u8]) {
+    rom[0x014D] = rom[0x0134..=0x014C]
+        .iter()
+        .fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1));
+}
+
+/// Run the built-in boot sequence until it hands over or locks up, returns the steps it took
+fn boot(model: Model, rom: &Vec<u8>) -> (GameBoy, usize) {
+    let mut gameboy = GameBoy::new(model);
+    gameboy.read_rom(rom).unwrap();
+    gameboy.use_builtin_boot_rom();
+
+    for steps in 0..MAX_STEPS {
+        match &gameboy.boot_sequence {
+            None => return (gameboy, steps),
+            Some(sequence) if sequence.state == BootState::LockedUp => return (gameboy, steps),
+            Some(_) => {
+                gameboy.step();
+            }
+        }
+    }
+    panic!("the boot sequence did not end");
+}
+
+#[test]
+fn hands_over_at_0x0100_with_the_post_boot_registers() {
+    let (mut gameboy, steps) = boot(Model::DMG, &rom());
+
+    assert!(gameboy.boot_sequence.is_none());
+    // The logo scrolls for 0x64 frames and stays still for 0x20 more, one step per V-Blank from the first one
+    let frames = steps / 17556;
+    assert_eq!(frames, 0x84 - 1, "took {} frames", frames);
+
+    let registers = &gameboy.cpu.registers;
+    assert_eq!(registers.pc, 0x0100);
+    assert_eq!(registers.sp, 0xFFFE);
+    assert_eq!(registers.get_af(), 0x01B0);
+    assert_eq!(registers.get_bc(), 0x0013);
+    assert_eq!(registers.get_de(), 0x00D8);
+    assert_eq!(registers.get_hl(), 0x014D);
+
+    // The logo was left scrolled into place, and its first row drawn (doubled) into tile 1
+    assert_eq!(gameboy.bus.ram_read_byte(0xFF42), 0x00); // SCY
+    assert_eq!(gameboy.bus.ram_read_byte(0xFF40), 0x91); // LCDC
+    assert_eq!(gameboy.bus.ram_read_byte(0x8010), 0xF0); // 0xC -> 11110000
+    assert_eq!(gameboy.bus.ram_read_byte(0x8012), 0xF0);
+
+    // The cartridge runs from there
+    gameboy.step();
+    assert_eq!(gameboy.cpu.registers.pc, 0x0100);
+}
+
+#[test]
+fn locks_up_on_a_bad_header_checksum() {
+    let mut rom = rom();
+    rom[0x014D] = rom[0x014D].wrapping_add(1);
+    let (gameboy, _) = boot(Model::DMG, &rom);
+
+    assert_eq!(gameboy.boot_sequence.unwrap().state, BootState::LockedUp);
+    assert_ne!(gameboy.cpu.registers.pc, 0x0100);
+}
+
+#[test]
+fn locks_up_on_a_bad_logo() {
+    let mut rom = rom();
+    rom[0x0133] ^= 0x01;
+    fix_header_checksum(&mut rom);
+    let (gameboy, _) = boot(Model::DMG, &rom);
+
+    assert_eq!(gameboy.boot_sequence.unwrap().state, BootState::LockedUp);
+}
+
+#[test]
+fn cgb_only_checks_the_top_half_of_the_logo() {
+    let mut bottom_half_changed = rom();
+    bottom_half_changed[0x0133] ^= 0x01;
+    fix_header_checksum(&mut bottom_half_changed);
+    let (gameboy, _) = boot(Model::CGB, &bottom_half_changed);
+    assert!(gameboy.boot_sequence.is_none());
+    assert_eq!(gameboy.cpu.registers.get_af(), 0x1180);
+
+    let mut top_half_changed = rom();
+    top_half_changed[0x0104] ^= 0x01;
+    fix_header_checksum(&mut top_half_changed);
+    let (gameboy, _) = boot(Model::CGB, &top_half_changed);
+    assert_eq!(gameboy.boot_sequence.unwrap().state, BootState::LockedUp);
+}
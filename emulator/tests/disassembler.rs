@@ -0,0 +1,177 @@
+//! Static disassembler tests, mostly on a small MBC1 ROM
+
+use emulator::gameboy::assembler::assemble;
+use emulator::gameboy::{Disassembler, SymbolTable};
+use emulator::Instruction;
+
+const ROM_BANKS: usize = 4;
+
+fn rom() -> Vec<u8> {
+    let mut rom = vec![0xFFu8; ROM_BANKS * 0x4000];
+    // MBC1
+    rom[0x0147] = 0x01;
+    rom[0x0100..0x0104].copy_from_slice(&[
+        0x00, // 0100: NOP
+        0xC3, 0x50, 0x01, // 0101: JP $0150
+    ]);
+    rom[0x0150..0x0164].copy_from_slice(&[
+        0x3E, 0x02, // 0150: LD A, $02
+        0xEA, 0x00, 0x20, // 0152: LD ($2000), A
+        0xCD, 0x00, 0x40, // 0155: CALL $4000
+        0xEA, 0x00, 0xC0, // 0158: LD ($C000), A
+        0xFA, 0x44, 0xFF, // 015B: LD A, ($FF44)
+        0x18, 0xF0, // 015E: JR $0150
+        0x12, 0x34, 0x56, 0x78, // 0160: data
+    ]);
+    rom[0x8000] = 0xC9; // 02:4000: RET
+    rom
+}
+
+fn disassemble(rom: &[u8], symbols: Option<&str>) -> String {
+    let mut disassembler = Disassembler::new(rom).unwrap();
+    if let Some(symbols) = symbols {
+        disassembler.set_symbols(SymbolTable::parse(symbols).unwrap());
+    }
+    disassembler.analyze();
+    let mut source = String::new();
+    disassembler.write_source(&mut source).unwrap();
+    source
+}
+
+/// Source lines without the address comments
+fn lines(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .map(|line| line.split(';').next().unwrap().trim_end())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+fn follows_jumps_calls_and_bank_switches() {
+    let rom = rom();
+    let source = disassemble(&rom, None);
+    let lines = lines(&source);
+
+    let entry = lines
+        .iter()
+        .position(|line| *line == "EntryPoint:")
+        .unwrap();
+    assert_eq!(
+        lines[entry..entry + 3],
+        ["EntryPoint:", "    nop", "    jp Label_00_0150"]
+    );
+
+    let main = lines
+        .iter()
+        .position(|line| *line == "Label_00_0150:")
+        .unwrap();
+    assert_eq!(
+        lines[main..main + 8],
+        [
+            "Label_00_0150:",
+            "    ld a, $02",
+            "    ld [$2000], a",
+            "    call Label_02_4000",
+            "    ld [$C000], a",
+            // Would be assembled as LDH
+            "    db $FA, $44, $FF",
+            "    jr Label_00_0150",
+            "    db $12, $34, $56, $78, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF",
+        ]
+    );
+
+    let bank_2 = lines
+        .iter()
+        .position(|line| *line == "SECTION \"ROM Bank $002\", ROMX[$4000], BANK[$002]")
+        .unwrap();
+    assert_eq!(lines[bank_2 + 1..bank_2 + 3], ["Label_02_4000:", "    ret"]);
+    // Bank 1 is never mapped when calling $4000
+    assert!(!source.contains("Label_01_4000"));
+}
+
+/// Place every instruction (CB ones included) from `start`, and log them as code
+fn place_every_instruction(rom: &mut [u8], log: &mut [u8], start: usize) {
+    let instructions = (0x00..=0xFF)
+        .filter(|opcode| *opcode != 0xCB)
+        .map(|opcode| [opcode, 0x80, 0x43])
+        .chain((0x00..=0xFF).map(|opcode| [0xCB, opcode, 0x00]));
+
+    let mut offset = start;
+    for bytes in instructions {
+        rom[offset..offset + 3].copy_from_slice(&bytes);
+        let (instruction, info) = Instruction::decode(|address| bytes[address as usize], 0);
+        if !matches!(instruction, Instruction::Illegal(_)) {
+            // Jump targets are traced even after jumps and returns
+            log[offset] = 0x01 | 0x04;
+            offset += info.length as usize;
+        }
+    }
+}
+
+#[test]
+fn every_instruction_assembles_back_to_the_rom() {
+    // MBC0, 2 banks: the sections are contiguous and assemble as a single block at 0x0000
+    let mut rom = vec![0xFFu8; 0x8000];
+    let mut log = vec![0u8; rom.len()];
+    rom[0x0147] = 0x00;
+    rom[0x0100..0x0104].copy_from_slice(&[0x00, 0xC3, 0x00, 0x02]); // NOP, JP $0200
+    place_every_instruction(&mut rom, &mut log, 0x0200);
+    place_every_instruction(&mut rom, &mut log, 0x4100);
+
+    let mut disassembler = Disassembler::new(&rom).unwrap();
+    disassembler.set_code_data_log(&log).unwrap();
+    disassembler.analyze();
+    let mut source = String::new();
+    disassembler.write_source(&mut source).unwrap();
+
+    let body: Vec<&str> = source
+        .lines()
+        .filter(|line| !line.starts_with("SECTION"))
+        .collect();
+    let assembled = assemble(&body.join("\n"), 0x0000, &SymbolTable::new()).unwrap();
+    assert_eq!(assembled.len(), rom.len());
+    if let Some(offset) = (0..rom.len()).find(|offset| assembled[*offset] != rom[*offset]) {
+        panic!(
+            "${:04X}: {:02X?} instead of {:02X?}",
+            offset,
+            &assembled[offset..rom.len().min(offset + 4)],
+            &rom[offset..rom.len().min(offset + 4)]
+        );
+    }
+    // Almost all of it was decoded, not written as data
+    assert!(disassembler.code_size() > 2 * (0xF5 * 2 + 0x100 * 2));
+}
+
+#[test]
+fn names_addresses_after_symbols() {
+    let rom = rom();
+    let symbols = "; comment\n00:0150 Main\n02:4000 Banked\n00:C000 wCounter\n00:0161 Middle\n";
+    let source = disassemble(&rom, Some(symbols));
+    let lines = lines(&source);
+
+    assert!(lines.contains(&"DEF wCounter EQU $C000"));
+    assert!(lines.contains(&"Main:"));
+    assert!(lines.contains(&"    jp Main"));
+    assert!(lines.contains(&"    call Banked"));
+    assert!(lines.contains(&"    ld [wCounter], a"));
+    assert!(lines.contains(&"Banked:"));
+    // Symbols in ROM are traced as code
+    assert!(lines.contains(&"Middle:"));
+    assert!(lines.contains(&"    inc [hl]"));
+}
+
+#[test]
+fn rejects_bad_inputs() {
+    assert!(Disassembler::new(&[0; 0x4000]).is_err());
+    assert!(Disassembler::new(&[0; 0x8001]).is_err());
+
+    let rom = rom();
+    let mut disassembler = Disassembler::new(&rom).unwrap();
+    assert!(disassembler.set_code_data_log(&[0; 0x8000]).is_err());
+
+    assert_eq!(
+        SymbolTable::parse("00:0150 Main\nnot a symbol\n").err(),
+        Some("line 2: expected \"bank:address name\"".to_string())
+    );
+}
@@ -0,0 +1,129 @@
+//! Instruction decoding, formatting and listing tests
+
+use std::collections::HashMap;
+
+use emulator::gameboy::instruction::{BankedAddress, Listing, Operand, Register};
+use emulator::{GameBoy, Instruction, Model};
+
+#[test]
+fn every_documented_opcode_decodes() {
+    for first in 0..=0xFF {
+        for second in 0..=0xFF {
+            let bytes = [first, second, 0x12];
+            let (instruction, info) = Instruction::decode(|address| bytes[address as usize], 0);
+            if info.is_illegal() {
+                assert_eq!(instruction, Instruction::Illegal(first));
+            } else {
+                assert_eq!(
+                    instruction.mnemonic(),
+                    info.mnemonic,
+                    "{:02X} {:02X}",
+                    first,
+                    second
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn instructions_format_like_the_opcode_table() {
+    let disassemble = |bytes: [u8; 3]| {
+        let (instruction, info) =
+            Instruction::decode(|address| bytes[address as usize - 0x150], 0x150);
+        (instruction.to_string(), info.cycles, info.length)
+    };
+
+    assert_eq!(
+        disassemble([0x1E, 0x12, 0x00]),
+        ("LD E, $12".to_string(), 8, 2)
+    );
+    assert_eq!(
+        disassemble([0x26, 0x12, 0x00]),
+        ("LD H, $12".to_string(), 8, 2)
+    );
+    assert_eq!(
+        disassemble([0x2E, 0x12, 0x00]),
+        ("LD L, $12".to_string(), 8, 2)
+    );
+    assert_eq!(
+        disassemble([0xCB, 0x7E, 0x00]),
+        ("BIT 7, (HL)".to_string(), 12, 2)
+    );
+    assert_eq!(
+        disassemble([0xC2, 0x34, 0x12]),
+        ("JP NZ, $1234".to_string(), 12, 3)
+    );
+    assert_eq!(
+        disassemble([0x18, 0xFE, 0x00]),
+        ("JR $0150".to_string(), 12, 2)
+    );
+    assert_eq!(
+        disassemble([0xF8, 0xFE, 0x00]),
+        ("LD HL, SP - 2".to_string(), 12, 2)
+    );
+    assert_eq!(
+        disassemble([0xE0, 0x40, 0x00]),
+        ("LDH ($FF40), A".to_string(), 12, 2)
+    );
+    assert_eq!(
+        disassemble([0xD3, 0x00, 0x00]),
+        ("DB $D3".to_string(), 4, 1)
+    );
+}
+
+#[test]
+fn instructions_name_their_targets() {
+    let mut symbols = HashMap::new();
+    symbols.insert(BankedAddress::new(0x4123, 2), "Banked".to_string());
+    symbols.insert(BankedAddress::new(0xFF40, 2), "rLCDC".to_string());
+
+    let call = Instruction::Call(None, 0x4123);
+    assert_eq!(call.format(2, &symbols), "CALL Banked");
+    assert_eq!(call.format(3, &symbols), "CALL $4123");
+    assert_eq!(call.target(), Some(0x4123));
+
+    let store = Instruction::Ldh(Operand::HighAddress(0x40), Operand::Register(Register::A));
+    assert_eq!(store.format(1, &symbols), "LDH (rLCDC), A");
+}
+
+#[test]
+fn listing_finds_branches_and_calls() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0150..0x0158].copy_from_slice(&[
+        0xCD, 0x60, 0x01, // 0150: CALL $0160
+        0x20, 0x0B, // 0153: JR NZ, $0160
+        0xC3, 0x50, 0x01, // 0155: JP $0150
+    ]);
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+
+    let listing = Listing::decode(&gameboy.bus, 1, 0x0150..=0x0157);
+    assert_eq!(listing.instructions.len(), 3);
+    assert_eq!(
+        listing.references_to(BankedAddress::new(0x0160, 1)),
+        vec![BankedAddress::new(0x0150, 1), BankedAddress::new(0x0153, 1)]
+    );
+    assert_eq!(
+        listing.references_to(BankedAddress::new(0x0150, 1)),
+        vec![BankedAddress::new(0x0155, 1)]
+    );
+}
+
+#[test]
+fn listing_follows_banks_past_the_end_of_the_rom() {
+    // 4 banks MBC1, bank 2 starts with RET
+    let mut rom = vec![0u8; 4 * 0x4000];
+    rom[0x0147] = 0x01;
+    rom[0x0148] = 0x01;
+    rom[0x8000] = 0xC9;
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+
+    // Bank 6 does not exist, the MBC maps bank 2 instead
+    gameboy.bus.ram_write_byte(0x2000, 0x06);
+    assert_eq!(gameboy.bus.rom_bank(), 2);
+    let listing = Listing::decode(&gameboy.bus, gameboy.bus.rom_bank(), 0x4000..=0x4000);
+    assert_eq!(listing.instructions[0].0, BankedAddress::new(0x4000, 2));
+    assert_eq!(listing.instructions[0].1, Instruction::Ret(None));
+}
@@ -0,0 +1,158 @@
+//! Interrupt dispatch, EI/DI and HALT tests, run on a flat 64 KiB bus
+
+use emulator::{MemoryAccess, CPU};
+
+const IF: u16 = 0xFF0F;
+const IE: u16 = 0xFFFF;
+const CODE: u16 = 0xC000;
+
+struct FlatBus {
+    ram: Vec<u8>,
+}
+
+impl MemoryAccess for FlatBus {
+    fn ram_read_byte(&self, address: u16) -> u8 {
+        self.ram[address as usize]
+    }
+
+    fn ram_write_byte(&mut self, address: u16, byte: u8) {
+        self.ram[address as usize] = byte;
+    }
+}
+
+/// A CPU about to run `program` at 0xC000, with interrupt flags `if_` and enabled interrupts `ie`
+fn setup(program: &[u8], if_: u8, ie: u8, ime: bool) -> (CPU, FlatBus) {
+    let mut bus = FlatBus {
+        ram: vec![0; 0x10000],
+    };
+    let start = CODE as usize;
+    bus.ram[start..start + program.len()].copy_from_slice(program);
+    bus.ram[IF as usize] = if_;
+    bus.ram[IE as usize] = ie;
+
+    let mut cpu = CPU::new();
+    cpu.registers.pc = CODE;
+    cpu.registers.sp = 0xD000;
+    cpu.registers.a = 0;
+    cpu.interrupts_enabled = ime;
+    (cpu, bus)
+}
+
+fn pushed_address(cpu: &CPU, bus: &FlatBus) -> u16 {
+    bus.ram_read_word(cpu.registers.sp)
+}
+
+#[test]
+fn dispatch_takes_five_m_cycles() {
+    let (mut cpu, mut bus) = setup(&[0x00], 0x01, 0x01, true);
+
+    assert_eq!(cpu.step(&mut bus), 20);
+    assert_eq!(cpu.registers.pc, 0x0040);
+    assert_eq!(cpu.registers.sp, 0xCFFE);
+    assert_eq!(pushed_address(&cpu, &bus), CODE);
+    assert_eq!(bus.ram[IF as usize], 0x00);
+    assert!(!cpu.interrupts_enabled);
+}
+
+#[test]
+fn highest_priority_interrupt_is_dispatched_first() {
+    let (mut cpu, mut bus) = setup(&[0x00], 0x14, 0x1F, true);
+
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers.pc, 0x0050); // Timer
+    assert_eq!(bus.ram[IF as usize], 0x10); // Joypad still pending
+}
+
+#[test]
+fn pushing_pc_over_ie_can_cancel_the_dispatch() {
+    // SP = 0x0000: the high byte of PC (0x02) lands in IE, which no longer enables V-Blank
+    let (mut cpu, mut bus) = setup(&[], 0x01, 0x01, true);
+    cpu.registers.pc = 0x0200;
+    cpu.registers.sp = 0x0000;
+
+    assert_eq!(cpu.step(&mut bus), 20);
+    assert_eq!(cpu.registers.pc, 0x0000);
+    assert_eq!(bus.ram[IE as usize], 0x02);
+    assert_eq!(bus.ram[IF as usize], 0x01);
+}
+
+#[test]
+fn pushing_pc_over_ie_can_change_the_interrupt() {
+    // The high byte (0x04) leaves only the timer interrupt enabled, V-Blank stays pending
+    let (mut cpu, mut bus) = setup(&[], 0x05, 0x05, true);
+    cpu.registers.pc = 0x0400;
+    cpu.registers.sp = 0x0000;
+
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers.pc, 0x0050);
+    assert_eq!(bus.ram[IF as usize], 0x01);
+}
+
+#[test]
+fn ei_takes_effect_after_the_next_instruction() {
+    // EI / NOP / NOP
+    let (mut cpu, mut bus) = setup(&[0xFB, 0x00, 0x00], 0x01, 0x01, false);
+
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers.pc, CODE + 2);
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers.pc, 0x0040);
+    assert_eq!(pushed_address(&cpu, &bus), CODE + 2);
+}
+
+#[test]
+fn di_right_after_ei_cancels_it() {
+    // EI / DI / NOP
+    let (mut cpu, mut bus) = setup(&[0xFB, 0xF3, 0x00], 0x01, 0x01, false);
+
+    for _ in 0..3 {
+        cpu.step(&mut bus);
+    }
+    assert_eq!(cpu.registers.pc, CODE + 3);
+    assert!(!cpu.interrupts_enabled);
+}
+
+#[test]
+fn halt_waits_for_an_interrupt_even_with_ime_off() {
+    // HALT / INC A
+    let (mut cpu, mut bus) = setup(&[0x76, 0x3C], 0x00, 0x01, false);
+
+    cpu.step(&mut bus);
+    assert!(cpu.is_halted);
+    assert_eq!(cpu.step(&mut bus), 4);
+    assert_eq!(cpu.registers.pc, CODE + 1);
+
+    // Waking up takes an extra M-cycle, then the next instruction runs without a dispatch
+    bus.ram[IF as usize] = 0x01;
+    assert_eq!(cpu.step(&mut bus), 8);
+    assert!(!cpu.is_halted);
+    assert_eq!(cpu.registers.a, 1);
+    assert_eq!(cpu.registers.pc, CODE + 2);
+}
+
+#[test]
+fn halt_bug_reads_the_next_byte_twice() {
+    // HALT with IME off and an interrupt pending: HALT is skipped and INC A runs twice
+    let (mut cpu, mut bus) = setup(&[0x76, 0x3C, 0x00], 0x01, 0x01, false);
+
+    for _ in 0..3 {
+        cpu.step(&mut bus);
+    }
+    assert!(!cpu.is_halted);
+    assert_eq!(cpu.registers.a, 2);
+    assert_eq!(cpu.registers.pc, CODE + 2);
+}
+
+#[test]
+fn halt_bug_after_ei_returns_to_the_halt() {
+    // EI / HALT with an interrupt pending: the interrupt is dispatched, and returns to the HALT
+    let (mut cpu, mut bus) = setup(&[0xFB, 0x76, 0x3C], 0x01, 0x01, false);
+
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers.pc, 0x0040);
+    assert_eq!(pushed_address(&cpu, &bus), CODE + 1);
+    assert_eq!(cpu.registers.a, 0);
+}
@@ -0,0 +1,63 @@
+//! IO register read/write mask tests
+
+use emulator::{GameBoy, Model};
+
+const KEY1: u16 = 0xFF4D;
+
+/// LD A, 1 / LDH (KEY1), A / STOP / JR -2
+const SPEED_SWITCH_PROGRAM: [u8; 8] = [0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00, 0x18, 0xFE];
+
+fn gameboy(model: Model, program: &[u8]) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+
+    let mut gameboy = GameBoy::new(model);
+    gameboy.read_rom(&rom).unwrap();
+    gameboy
+}
+
+fn run_to(gameboy: &mut GameBoy, pc: u16) {
+    for _ in 0..10_000 {
+        if gameboy.cpu.registers.pc == pc {
+            return;
+        }
+        gameboy.step();
+    }
+    panic!("never reached {:#06X}", pc);
+}
+
+#[test]
+fn unused_bits_read_as_one() {
+    let mut gameboy = gameboy(Model::DMG, &[]);
+    let bus = &mut gameboy.bus;
+
+    bus.ram_write_byte(0xFF07, 0x00); // TAC
+    assert_eq!(bus.ram_read_byte(0xFF07), 0xF8);
+    bus.ram_write_byte(0xFF0F, 0x00); // IF
+    assert_eq!(bus.ram_read_byte(0xFF0F), 0xE0);
+    bus.ram_write_byte(0xFF02, 0x00); // SC
+    assert_eq!(bus.ram_read_byte(0xFF02), 0x7E);
+    bus.ram_write_byte(0xFF03, 0x00); // Not mapped
+    assert_eq!(bus.ram_read_byte(0xFF03), 0xFF);
+}
+
+#[test]
+fn key1_is_only_mapped_on_cgb() {
+    let mut gameboy = gameboy(Model::DMG, &SPEED_SWITCH_PROGRAM);
+
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0xFF);
+    gameboy.bus.ram_write_byte(KEY1, 0x01);
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0xFF);
+}
+
+#[test]
+fn key1_shows_the_speed_and_a_pending_switch() {
+    let mut gameboy = gameboy(Model::CGB, &SPEED_SWITCH_PROGRAM);
+
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0x7E);
+    run_to(&mut gameboy, 0x0104); // Armed, right before STOP
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0x7F);
+
+    run_to(&mut gameboy, 0x0106); // Switched
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0xFE);
+}
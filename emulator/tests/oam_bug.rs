@@ -0,0 +1,189 @@
+//! DMG OAM corruption bug tests
+//!
+//! OAM is filled with a distinct value in every byte, then a single instruction runs from WRAM with a register
+//! pointing into OAM. Every instruction is started on the first M-cycle of a line, so the PPU reads OAM row N
+//! during the instruction's M-cycle N: the expected OAM is computed from the corruption patterns in Pan Docs.
+
+use emulator::{GameBoy, Model};
+
+const LY: u16 = 0xFF44;
+const STAT: u16 = 0xFF41;
+const CODE: u16 = 0xD000;
+
+/// OAM as 20 rows of 4 words
+type Oam = [[u16; 4]; 20];
+
+fn setup(model: Model, instruction: &[u8]) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100] = 0x18; // JR -2
+    rom[0x0101] = 0xFE;
+
+    let mut gameboy = GameBoy::new(model);
+    gameboy.read_rom(&rom).unwrap();
+    for (offset, byte) in instruction.iter().enumerate() {
+        gameboy.bus.ram_write_byte(CODE + offset as u16, *byte);
+    }
+    for index in 0..0xA0 {
+        gameboy.bus.ppu.poke_byte(0xFE00 + index, index as u8);
+    }
+    gameboy
+}
+
+fn read_oam(gameboy: &GameBoy) -> Oam {
+    let mut oam = [[0; 4]; 20];
+    for (row, words) in oam.iter_mut().enumerate() {
+        for (word, value) in words.iter_mut().enumerate() {
+            let address = 0xFE00 + row * 8 + word * 2;
+            *value = u16::from_le_bytes([
+                gameboy.bus.ppu.peek_byte(address),
+                gameboy.bus.ppu.peek_byte(address + 1),
+            ]);
+        }
+    }
+    oam
+}
+
+/// Run NOPs from WRAM until the first M-cycle of line `ly`, then run the instruction at `CODE`
+fn run_at_line_start(gameboy: &mut GameBoy, ly: u8) {
+    gameboy.cpu.registers.pc = 0xC000;
+    while gameboy.bus.ram_read_byte(LY) == ly {
+        gameboy.step();
+    }
+    while gameboy.bus.ram_read_byte(LY) != ly {
+        gameboy.step();
+    }
+    gameboy.cpu.registers.pc = CODE;
+    gameboy.step();
+}
+
+fn write_corruption(oam: &mut Oam, row: usize) {
+    let (a, b, c) = (oam[row][0], oam[row - 1][0], oam[row - 1][2]);
+    oam[row] = oam[row - 1];
+    oam[row][0] = ((a ^ c) & (b ^ c)) ^ c;
+}
+
+fn read_corruption(oam: &mut Oam, row: usize) {
+    let (a, b, c) = (oam[row][0], oam[row - 1][0], oam[row - 1][2]);
+    oam[row] = oam[row - 1];
+    oam[row][0] = b | (a & c);
+}
+
+fn read_idu_corruption(oam: &mut Oam, row: usize) {
+    let (a, b, c, d) = (
+        oam[row - 2][0],
+        oam[row - 1][0],
+        oam[row][0],
+        oam[row - 1][2],
+    );
+    oam[row - 1][0] = (b & (a | c | d)) | (a & c & d);
+    oam[row] = oam[row - 1];
+    oam[row - 2] = oam[row - 1];
+    read_corruption(oam, row);
+}
+
+#[test]
+fn inc_and_dec_rr_corrupt_like_a_write() {
+    // INC HL, DEC DE - the IDU works on the register during the second M-cycle
+    for (instruction, pointer) in [(0x23, 0xFE00), (0x1B, 0xFE44)] {
+        let mut gameboy = setup(Model::DMG, &[instruction]);
+        gameboy.cpu.registers.set_hl(pointer);
+        gameboy.cpu.registers.set_de(pointer);
+        let mut expected = read_oam(&gameboy);
+
+        run_at_line_start(&mut gameboy, 10);
+        write_corruption(&mut expected, 2);
+        assert_eq!(read_oam(&gameboy), expected, "opcode {:#04X}", instruction);
+    }
+}
+
+#[test]
+fn reads_corrupt_like_a_read() {
+    // LD A, (HL)
+    let mut gameboy = setup(Model::DMG, &[0x7E]);
+    gameboy.cpu.registers.set_hl(0xFE10);
+    let mut expected = read_oam(&gameboy);
+
+    run_at_line_start(&mut gameboy, 10);
+    read_corruption(&mut expected, 2);
+    assert_eq!(read_oam(&gameboy), expected);
+}
+
+#[test]
+fn push_corrupts_three_rows_like_writes() {
+    // PUSH BC - SP is decremented by the IDU, then both bytes are written
+    let mut gameboy = setup(Model::DMG, &[0xC5]);
+    gameboy.cpu.registers.sp = 0xFE20;
+    let mut expected = read_oam(&gameboy);
+
+    run_at_line_start(&mut gameboy, 10);
+    for row in 2..=4 {
+        write_corruption(&mut expected, row);
+    }
+    assert_eq!(read_oam(&gameboy), expected);
+}
+
+#[test]
+fn pop_corrupts_two_rows_like_reads_with_the_idu() {
+    // POP BC - both bytes are read while the IDU increments SP
+    let mut gameboy = setup(Model::DMG, &[0xC1]);
+    gameboy.cpu.registers.sp = 0xFE20;
+    let mut expected = read_oam(&gameboy);
+
+    run_at_line_start(&mut gameboy, 10);
+    // Rows below 4 only get the read pattern
+    read_corruption(&mut expected, 2);
+    read_corruption(&mut expected, 3);
+    assert_eq!(read_oam(&gameboy), expected);
+}
+
+#[test]
+fn pop_further_into_the_scan_mixes_the_surrounding_rows() {
+    // NOP x 3, then POP BC: its reads happen on rows 5 and 6
+    let mut gameboy = setup(Model::DMG, &[0x00, 0x00, 0x00, 0xC1]);
+    gameboy.cpu.registers.sp = 0xFE20;
+    let mut expected = read_oam(&gameboy);
+
+    run_at_line_start(&mut gameboy, 10);
+    for _ in 0..3 {
+        gameboy.step();
+    }
+    read_idu_corruption(&mut expected, 5);
+    read_idu_corruption(&mut expected, 6);
+    assert_eq!(read_oam(&gameboy), expected);
+}
+
+#[test]
+fn no_corruption_outside_of_the_oam_scan() {
+    let mut gameboy = setup(Model::DMG, &[0x23]);
+    gameboy.cpu.registers.set_hl(0xFE00);
+    let expected = read_oam(&gameboy);
+
+    // H-Blank
+    gameboy.cpu.registers.pc = 0xC000;
+    while gameboy.bus.ram_read_byte(STAT) & 0x3 != 0 {
+        gameboy.step();
+    }
+    gameboy.cpu.registers.pc = CODE;
+    gameboy.step();
+    assert_eq!(read_oam(&gameboy), expected);
+}
+
+#[test]
+fn no_corruption_from_pointers_outside_of_oam() {
+    let mut gameboy = setup(Model::DMG, &[0x23]);
+    gameboy.cpu.registers.set_hl(0xFDFF);
+    let expected = read_oam(&gameboy);
+
+    run_at_line_start(&mut gameboy, 10);
+    assert_eq!(read_oam(&gameboy), expected);
+}
+
+#[test]
+fn no_corruption_on_cgb() {
+    let mut gameboy = setup(Model::CGB, &[0x23]);
+    gameboy.cpu.registers.set_hl(0xFE00);
+    let expected = read_oam(&gameboy);
+
+    run_at_line_start(&mut gameboy, 10);
+    assert_eq!(read_oam(&gameboy), expected);
+}
@@ -0,0 +1,128 @@
+//! Opcode table consistency tests
+//!
+//! The CPU takes its cycle counts from the opcode table, these check that the rest of the table
+//! (lengths and flag effects) matches what the CPU does.
+
+use emulator::gameboy::opcode::{self, FlagEffect, Opcode};
+use emulator::gameboy::FlagsRegister;
+use emulator::{MemoryAccess, CPU};
+
+/// Register states tried for every opcode
+const RUNS: usize = 64;
+
+const START: u16 = 0xC000;
+
+/// Instructions that change PC to something else than the next instruction
+const JUMPS: [&str; 6] = ["JP", "JR", "CALL", "RET", "RETI", "RST"];
+
+struct FlatBus {
+    ram: Vec<u8>,
+}
+
+impl MemoryAccess for FlatBus {
+    fn ram_read_byte(&self, address: u16) -> u8 {
+        self.ram[address as usize]
+    }
+
+    fn ram_write_byte(&mut self, address: u16, byte: u8) {
+        self.ram[address as usize] = byte;
+    }
+}
+
+/// xorshift, so that every run tries the same states
+fn next_random(state: &mut u32) -> u8 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state as u8
+}
+
+fn check_flag(name: &str, effect: FlagEffect, before: bool, after: bool) -> Result<(), String> {
+    let is_expected = match effect {
+        FlagEffect::Unaffected => after == before,
+        FlagEffect::Reset => !after,
+        FlagEffect::Set => after,
+        FlagEffect::Affected => true,
+    };
+    if is_expected {
+        Ok(())
+    } else {
+        Err(format!("flag {} went from {} to {}", name, before, after))
+    }
+}
+
+fn run(info: &Opcode, bytes: [u8; 2], random: &mut u32) -> Result<(), String> {
+    let mut cpu = CPU::new();
+    let mut bus = FlatBus {
+        ram: vec![0; 0x10000],
+    };
+
+    let registers = &mut cpu.registers;
+    registers.a = next_random(random);
+    registers.b = next_random(random);
+    registers.c = next_random(random);
+    registers.d = next_random(random);
+    registers.e = next_random(random);
+    registers.h = next_random(random);
+    registers.l = next_random(random);
+    registers.f = FlagsRegister::from(next_random(random) & 0xF0);
+    registers.sp = 0xD000;
+    registers.pc = START;
+    bus.ram[START as usize] = bytes[0];
+    bus.ram[START as usize + 1] = bytes[1];
+    bus.ram[START as usize + 2] = next_random(random);
+    bus.ram[START as usize + 3] = next_random(random);
+
+    let before = cpu.registers.f;
+    cpu.execute_opcode(&mut bus);
+    let after = cpu.registers.f;
+
+    check_flag("Z", info.flags.zero, before.zero, after.zero)?;
+    check_flag("N", info.flags.subtract, before.subtract, after.subtract)?;
+    check_flag(
+        "H",
+        info.flags.half_carry,
+        before.half_carry,
+        after.half_carry,
+    )?;
+    check_flag("C", info.flags.carry, before.carry, after.carry)?;
+
+    let length = cpu.registers.pc.wrapping_sub(START);
+    if !JUMPS.contains(&info.mnemonic) && length != info.length as u16 {
+        return Err(format!("PC moved by {}, expected {}", length, info.length));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn opcode_table_matches_the_cpu() {
+    let mut random = 0x1234_5678;
+    let mut failures = Vec::new();
+
+    for cb_opcode in 0..=0xFF {
+        let info = opcode::lookup(0xCB, cb_opcode);
+        for _ in 0..RUNS {
+            if let Err(error) = run(info, [0xCB, cb_opcode], &mut random) {
+                failures.push(format!("CB {:02X} {}: {}", cb_opcode, info.mnemonic, error));
+                break;
+            }
+        }
+    }
+
+    for opcode in 0..=0xFF {
+        let info = opcode::lookup(opcode, 0);
+        // HALT and STOP depend on the rest of the system, the illegal opcodes lock up the CPU
+        if opcode == 0xCB || opcode == 0x76 || opcode == 0x10 || info.is_illegal() {
+            continue;
+        }
+        for _ in 0..RUNS {
+            if let Err(error) = run(info, [opcode, 0], &mut random) {
+                failures.push(format!("{:02X} {}: {}", opcode, info.mnemonic, error));
+                break;
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,204 @@
+//! Super Game Boy packet transfer and command tests
+
+use emulator::{GameBoy, Joypad, Model};
+
+const P1: u16 = 0xFF00;
+
+fn sgb_rom(is_sgb_supported: bool) -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    if is_sgb_supported {
+        rom[0x0146] = 0x03;
+        rom[0x014B] = 0x33;
+    }
+    rom
+}
+
+fn sgb_gameboy() -> GameBoy {
+    let mut gameboy = GameBoy::new(Model::SGB);
+    gameboy.read_rom(&sgb_rom(true)).unwrap();
+    gameboy
+}
+
+/// P1 writes sending a packet: reset pulse, 128 bits (LSB first), stop bit
+fn packet_writes(packet: &[u8; 16]) -> Vec<u8> {
+    let mut writes = vec![0x00, 0x30];
+    for bit in 0..128 {
+        let is_set = packet[bit / 8] & (1 << (bit % 8)) != 0;
+        writes.push(if is_set { 0x10 } else { 0x20 });
+        writes.push(0x30);
+    }
+    writes.extend([0x20, 0x30]);
+    writes
+}
+
+fn send_packet(gameboy: &mut GameBoy, packet: &[u8; 16]) {
+    for byte in packet_writes(packet) {
+        gameboy.bus.ram_write_byte(P1, byte);
+    }
+}
+
+/// Build a packet from its first bytes, the rest is zero
+fn packet(bytes: &[u8]) -> [u8; 16] {
+    let mut packet = [0; 16];
+    packet[..bytes.len()].copy_from_slice(bytes);
+    packet
+}
+
+#[test]
+fn joypad_decodes_packets() {
+    let expected: [u8; 16] = std::array::from_fn(|index| (index as u8).wrapping_mul(0x3B) ^ 0xA5);
+    let mut joypad = Joypad::new();
+    joypad.is_sgb_enabled = true;
+
+    let writes = packet_writes(&expected);
+    let (bits, stop_bit) = writes.split_at(writes.len() - 2);
+    for byte in bits {
+        joypad.write_byte(*byte);
+    }
+    // The packet is only complete once the stop bit is sent
+    assert_eq!(joypad.sgb_packet, None);
+    joypad.write_byte(stop_bit[0]);
+    assert_eq!(joypad.sgb_packet, Some(expected));
+}
+
+#[test]
+fn joypad_ignores_bits_without_a_reset_pulse() {
+    let mut joypad = Joypad::new();
+    joypad.is_sgb_enabled = true;
+
+    for byte in packet_writes(&[0xFF; 16]).into_iter().skip(2) {
+        joypad.write_byte(byte);
+    }
+    assert_eq!(joypad.sgb_packet, None);
+}
+
+#[test]
+fn joypad_ignores_packets_when_disabled() {
+    let mut joypad = Joypad::new();
+
+    for byte in packet_writes(&[0xFF; 16]) {
+        joypad.write_byte(byte);
+    }
+    assert_eq!(joypad.sgb_packet, None);
+}
+
+#[test]
+fn pal01_sets_palettes_0_and_1() {
+    let mut gameboy = sgb_gameboy();
+    // PAL01, 1 packet: color 0, colors 1-3 of palette 0, colors 1-3 of palette 1
+    let mut bytes = vec![0x01];
+    for color in [0x1111u16, 0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006] {
+        bytes.extend(color.to_le_bytes());
+    }
+    send_packet(&mut gameboy, &packet(&bytes));
+
+    let sgb = gameboy.bus.sgb.as_ref().unwrap();
+    assert_eq!(sgb.palettes[0], [0x1111, 0x0001, 0x0002, 0x0003]);
+    assert_eq!(sgb.palettes[1], [0x1111, 0x0004, 0x0005, 0x0006]);
+    // Color 0 is shared, the other colors of palettes 2 and 3 are kept
+    assert_eq!(sgb.palettes[2][0], 0x1111);
+    assert_eq!(sgb.palettes[3][0], 0x1111);
+    assert_ne!(sgb.palettes[2][1], 0x0001);
+}
+
+#[test]
+fn commands_wait_for_all_of_their_packets() {
+    let mut gameboy = sgb_gameboy();
+    // ATTR_LIN, 2 packets: 14 lines in the first packet, the 15th in the second one
+    let mut first = [0u8; 16];
+    first[0] = (0x05 << 3) | 0x02;
+    first[1] = 15;
+    for (index, line) in first[2..].iter_mut().enumerate() {
+        // Vertical lines 0-13, palette 1
+        *line = 0x20 | index as u8;
+    }
+    // Horizontal line 17, palette 2
+    let second = packet(&[0x80 | 0x40 | 17]);
+
+    send_packet(&mut gameboy, &first);
+    let sgb = gameboy.bus.sgb.as_ref().unwrap();
+    assert!(sgb.attributes.iter().all(|palette| *palette == 0));
+
+    send_packet(&mut gameboy, &second);
+    let sgb = gameboy.bus.sgb.as_ref().unwrap();
+    assert_eq!(sgb.attributes[0], 1);
+    assert_eq!(sgb.attributes[13], 1);
+    assert_eq!(sgb.attributes[14], 0);
+    assert_eq!(sgb.attributes[17 * 20..], [2; 20]);
+}
+
+#[test]
+fn mlt_req_switches_between_controllers() {
+    let mut gameboy = sgb_gameboy();
+    // Neither group selected: a single controller reads as released
+    gameboy.bus.ram_write_byte(P1, 0x30);
+    assert_eq!(gameboy.bus.ram_read_byte(P1), 0xFF);
+
+    // MLT_REQ, 2 players
+    send_packet(&mut gameboy, &packet(&[(0x11 << 3) | 0x01, 0x01]));
+    // The lines hold the ID of the current controller, P15 going high selects the next one
+    assert_eq!(gameboy.bus.ram_read_byte(P1), 0xFF);
+    gameboy.bus.ram_write_byte(P1, 0x10);
+    gameboy.bus.ram_write_byte(P1, 0x30);
+    assert_eq!(gameboy.bus.ram_read_byte(P1), 0xFE);
+    gameboy.bus.ram_write_byte(P1, 0x10);
+    gameboy.bus.ram_write_byte(P1, 0x30);
+    assert_eq!(gameboy.bus.ram_read_byte(P1), 0xFF);
+
+    // Back to 1 player
+    send_packet(&mut gameboy, &packet(&[(0x11 << 3) | 0x01, 0x00]));
+    gameboy.bus.ram_write_byte(P1, 0x10);
+    gameboy.bus.ram_write_byte(P1, 0x30);
+    assert_eq!(gameboy.bus.ram_read_byte(P1), 0xFF);
+}
+
+#[test]
+fn mask_en_hides_the_game_screen() {
+    use emulator::gameboy::sgb::{MaskMode, SGB_SCREEN_WIDTH};
+    let mut gameboy = sgb_gameboy();
+    let white = vec![0xFF; 160 * 144 * 4];
+    // Center of the Game Boy screen, inside the border
+    let center = ((40 + 72) * SGB_SCREEN_WIDTH + 48 + 80) * 4;
+
+    // MASK_EN, black
+    send_packet(&mut gameboy, &packet(&[(0x17 << 3) | 0x01, 0x02]));
+    let sgb = gameboy.bus.sgb.as_mut().unwrap();
+    assert_eq!(sgb.mask, MaskMode::Black);
+    sgb.vblank(&white);
+    assert_eq!(sgb.get_display()[center..center + 4], [0, 0, 0, 0xFF]);
+
+    // MASK_EN, cancel: the screen is shown with color 0
+    send_packet(&mut gameboy, &packet(&[(0x17 << 3) | 0x01, 0x00]));
+    let sgb = gameboy.bus.sgb.as_mut().unwrap();
+    assert_eq!(sgb.mask, MaskMode::Cancel);
+    sgb.vblank(&white);
+    assert_ne!(sgb.get_display()[center..center + 4], [0, 0, 0, 0xFF]);
+}
+
+#[test]
+fn sgb_functions_need_an_sgb_model_and_cartridge() {
+    for (model, is_sgb_supported, is_enabled) in [
+        (Model::SGB, true, true),
+        (Model::SGB2, true, true),
+        (Model::SGB, false, false),
+        (Model::DMG, true, false),
+        (Model::CGB, true, false),
+    ] {
+        let mut gameboy = GameBoy::new(model);
+        gameboy.read_rom(&sgb_rom(is_sgb_supported)).unwrap();
+        assert_eq!(
+            gameboy.bus.sgb.is_some(),
+            is_enabled,
+            "{:?}, SGB flag: {}",
+            model,
+            is_sgb_supported
+        );
+
+        // Packets are only decoded with the SGB functions enabled
+        send_packet(&mut gameboy, &packet(&[(0x11 << 3) | 0x01, 0x01]));
+        gameboy.bus.ram_write_byte(P1, 0x10);
+        gameboy.bus.ram_write_byte(P1, 0x30);
+        let expected = if is_enabled { 0xFE } else { 0xFF };
+        assert_eq!(gameboy.bus.ram_read_byte(P1), expected, "{:?}", model);
+    }
+}
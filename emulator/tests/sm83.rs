@@ -0,0 +1,310 @@
+//! SM83 single-instruction tests
+//!
+//! Runs the community per-opcode JSON test vectors (https://github.com/SingleStepTests/sm83)
+//! through `CPU::execute_opcode` against a flat 64 KiB bus.
+//!
+//! A hand-checked subset in the same format is kept in `tests/sm83` and always runs.
+//! The full set is not checked into the repository: download the `v1` directory and either place it at
+//! `../roms/sm83/v1` (next to the other test ROMs) or point `SM83_TEST_DIR` at it, then run
+//! `cargo test --test sm83 -- --ignored`.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use emulator::gameboy::FlagsRegister;
+use emulator::{MemoryAccess, CPU};
+use serde_json::Value;
+
+const DEFAULT_TEST_DIR: &str = "../roms/sm83/v1";
+const BUNDLED_TEST_DIR: &str = "tests/sm83";
+
+/// Opcodes that can not be verified by a single-instruction test
+/// > HALT/STOP depend on the rest of the system
+/// > The illegal opcodes lock up the CPU
+const SKIPPED_OPCODES: [&str; 13] = [
+    "10", "76", "d3", "db", "dd", "e3", "e4", "eb", "ec", "ed", "f4", "fc", "fd",
+];
+
+/// Bus activity of one M-cycle: (address, value, is_write), None for an internal M-cycle
+type Cycle = Option<(u16, u8, bool)>;
+
+/// A flat 64 KiB address space that records the bus activity of every M-cycle
+struct TestBus {
+    ram: Vec<u8>,
+    cycles: RefCell<Vec<Cycle>>,
+}
+
+impl TestBus {
+    fn new() -> Self {
+        TestBus {
+            ram: vec![0; 0x10000],
+            cycles: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The CPU ticks one M-cycle before each access, the access fills it in
+    fn record(&self, access: (u16, u8, bool)) {
+        let mut cycles = self.cycles.borrow_mut();
+        match cycles.last_mut() {
+            Some(cycle @ None) => *cycle = Some(access),
+            _ => panic!("access to {:#06X} outside of an M-cycle", access.0),
+        }
+    }
+}
+
+impl MemoryAccess for TestBus {
+    fn ram_read_byte(&self, address: u16) -> u8 {
+        let value = self.ram[address as usize];
+        self.record((address, value, false));
+        value
+    }
+
+    fn ram_write_byte(&mut self, address: u16, byte: u8) {
+        self.ram[address as usize] = byte;
+        self.record((address, byte, true));
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        for _ in 0..cycles / 4 {
+            self.cycles.borrow_mut().push(None);
+        }
+    }
+}
+
+#[test]
+fn sm83_bundled_tests() {
+    let test_count = run_directory(&PathBuf::from(BUNDLED_TEST_DIR));
+    assert!(test_count > 0, "no test vectors in {}", BUNDLED_TEST_DIR);
+}
+
+#[test]
+#[ignore = "needs the SingleStepTests vectors, see the module documentation"]
+fn sm83_single_instruction_tests() {
+    let test_dir = PathBuf::from(
+        std::env::var("SM83_TEST_DIR").unwrap_or_else(|_| DEFAULT_TEST_DIR.to_string()),
+    );
+    run_directory(&test_dir);
+}
+
+/// Run every vector file in `test_dir`, panics on failures, returns the number of tests run
+fn run_directory(test_dir: &Path) -> usize {
+    let entries = fs::read_dir(test_dir)
+        .unwrap_or_else(|error| panic!("SM83 test vectors not found at {:?}: {}", test_dir, error));
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut failures: Vec<String> = Vec::new();
+    let mut test_count = 0;
+
+    for file in files {
+        let stem = file.file_stem().unwrap().to_string_lossy().to_lowercase();
+        if SKIPPED_OPCODES.contains(&stem.as_str()) {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&file).expect("unable to read test file");
+        let tests: Vec<Value> = serde_json::from_str(&contents).expect("invalid test file");
+
+        for test in tests.iter() {
+            test_count += 1;
+            if let Err(error) = run_test(test) {
+                failures.push(format!("{}: {}", test["name"], error));
+                break; // One failure per opcode is enough to go on
+            }
+        }
+    }
+
+    println!("Ran {} SM83 tests", test_count);
+    if !failures.is_empty() {
+        panic!(
+            "{} opcode(s) failed:\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+
+    test_count
+}
+
+fn run_test(test: &Value) -> Result<(), String> {
+    let initial = &test["initial"];
+    let expected = &test["final"];
+
+    let mut cpu = CPU::new();
+    let mut bus = TestBus::new();
+    load_state(&mut cpu, &mut bus, initial);
+
+    // The vectors model the SM83 fetch/execute overlap: the opcode (at PC - 1) has already been
+    // fetched and the last M-cycle of every instruction fetches the next one.
+    // `execute_opcode` fetches its own opcode, so start one byte earlier and expect to end one byte earlier.
+    cpu.registers.pc = cpu.registers.pc.wrapping_sub(1);
+    let cycles = cpu.execute_opcode(&mut bus);
+    cpu.registers.pc = cpu.registers.pc.wrapping_add(1);
+
+    compare_state(&cpu, &bus, expected)?;
+
+    // Bus activity, M-cycle by M-cycle. `execute_opcode` starts with the fetch of its own opcode, and internal
+    // M-cycles at the end of an instruction are left for the caller to tick.
+    // The vectors instead end with the fetch of the next opcode, at the final PC - 1.
+    let expected_cycles: Vec<Cycle> = test["cycles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|cycle| {
+            let cycle = cycle.as_array()?;
+            let address = cycle[0].as_u64()? as u16;
+            let value = cycle[1].as_u64()? as u8;
+            let is_write = cycle[2].as_str()?.contains('w');
+            Some((address, value, is_write))
+        })
+        .collect();
+
+    if cycles as usize != expected_cycles.len() * 4 {
+        return Err(format!(
+            "took {} T-cycles, expected {}",
+            cycles,
+            expected_cycles.len() * 4
+        ));
+    }
+
+    let mut actual_cycles = bus.cycles.borrow().clone();
+    actual_cycles.resize(expected_cycles.len(), None);
+    let opcode_address = word(initial, "pc").wrapping_sub(1);
+    let next_fetch = cpu.registers.pc.wrapping_sub(1);
+    let wanted_cycles: Vec<Cycle> = std::iter::once(Some((
+        opcode_address,
+        bus.ram[opcode_address as usize],
+        false,
+    )))
+    .chain(expected_cycles[..expected_cycles.len() - 1].iter().copied())
+    .collect();
+
+    if actual_cycles != wanted_cycles {
+        return Err(format!(
+            "bus activity is {}, expected {}",
+            format_cycles(&actual_cycles),
+            format_cycles(&wanted_cycles)
+        ));
+    }
+    match expected_cycles.last() {
+        Some(Some((address, _, false))) if *address == next_fetch => {}
+        last => {
+            return Err(format!(
+                "ended before the fetch at {:#06X}, expected {}",
+                next_fetch,
+                format_cycles(&[last.copied().flatten()])
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+fn load_state(cpu: &mut CPU, bus: &mut TestBus, state: &Value) {
+    let registers = &mut cpu.registers;
+    registers.a = byte(state, "a");
+    registers.b = byte(state, "b");
+    registers.c = byte(state, "c");
+    registers.d = byte(state, "d");
+    registers.e = byte(state, "e");
+    registers.f = FlagsRegister::from(byte(state, "f"));
+    registers.h = byte(state, "h");
+    registers.l = byte(state, "l");
+    registers.pc = word(state, "pc");
+    registers.sp = word(state, "sp");
+    cpu.interrupts_enabled = byte(state, "ime") != 0;
+
+    for (address, value) in ram(state) {
+        bus.ram[address as usize] = value;
+    }
+}
+
+fn compare_state(cpu: &CPU, bus: &TestBus, expected: &Value) -> Result<(), String> {
+    let registers = &cpu.registers;
+    let actual_bytes = [
+        ("a", registers.a),
+        ("b", registers.b),
+        ("c", registers.c),
+        ("d", registers.d),
+        ("e", registers.e),
+        ("f", u8::from(registers.f)),
+        ("h", registers.h),
+        ("l", registers.l),
+    ];
+    for (name, actual) in actual_bytes {
+        let wanted = byte(expected, name);
+        if actual != wanted {
+            return Err(format!(
+                "register {} is {:#04X}, expected {:#04X}",
+                name, actual, wanted
+            ));
+        }
+    }
+
+    for (name, actual) in [("pc", registers.pc), ("sp", registers.sp)] {
+        let wanted = word(expected, name);
+        if actual != wanted {
+            return Err(format!(
+                "register {} is {:#06X}, expected {:#06X}",
+                name, actual, wanted
+            ));
+        }
+    }
+
+    let wanted_ime = byte(expected, "ime") != 0;
+    if cpu.interrupts_enabled != wanted_ime {
+        return Err(format!(
+            "IME is {}, expected {}",
+            cpu.interrupts_enabled, wanted_ime
+        ));
+    }
+
+    for (address, wanted) in ram(expected) {
+        let actual = bus.ram[address as usize];
+        if actual != wanted {
+            return Err(format!(
+                "memory at {:#06X} is {:#04X}, expected {:#04X}",
+                address, actual, wanted
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn format_cycles(cycles: &[Cycle]) -> String {
+    let cycles: Vec<String> = cycles
+        .iter()
+        .map(|cycle| match cycle {
+            Some((address, value, false)) => format!("read {:#04X} at {:#06X}", value, address),
+            Some((address, value, true)) => format!("write {:#04X} to {:#06X}", value, address),
+            None => "internal".to_string(),
+        })
+        .collect();
+    format!("[{}]", cycles.join(", "))
+}
+
+fn byte(state: &Value, name: &str) -> u8 {
+    state[name].as_u64().unwrap_or(0) as u8
+}
+
+fn word(state: &Value, name: &str) -> u16 {
+    state[name].as_u64().unwrap_or(0) as u16
+}
+
+fn ram(state: &Value) -> Vec<(u16, u8)> {
+    state["ram"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.as_array()?;
+            Some((entry[0].as_u64()? as u16, entry[1].as_u64()? as u8))
+        })
+        .collect()
+}
@@ -0,0 +1,78 @@
+//! STAT interrupt line tests
+
+use emulator::{GameBoy, Model, PPU};
+
+const IF: u16 = 0xFF0F;
+const STAT: u16 = 0xFF41;
+const LY: u16 = 0xFF44;
+const LYC: u16 = 0xFF45;
+
+/// STAT sources
+const LYC_SOURCE: u8 = 0x40;
+const OAM_SOURCE: u8 = 0x20;
+
+fn setup(stat: u8, lyc: u8) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100] = 0x18; // JR -2
+    rom[0x0101] = 0xFE;
+
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+    // Only the IF flags are looked at, the interrupts are never dispatched
+    gameboy.bus.ram_write_byte(0xFFFF, 0x00);
+    gameboy.bus.ram_write_byte(LYC, lyc);
+    gameboy.bus.ram_write_byte(STAT, stat);
+    gameboy
+}
+
+fn run_to_line(gameboy: &mut GameBoy, ly: u8) {
+    while gameboy.bus.ram_read_byte(LY) == ly {
+        gameboy.step();
+    }
+    while gameboy.bus.ram_read_byte(LY) != ly {
+        gameboy.step();
+    }
+}
+
+fn is_lcd_interrupt_requested(gameboy: &GameBoy) -> bool {
+    gameboy.bus.ram_read_byte(IF) & 0x02 != 0
+}
+
+#[test]
+fn oam_source_fires_at_the_start_of_line_144() {
+    let mut gameboy = setup(OAM_SOURCE, 0xFF);
+    run_to_line(&mut gameboy, 143);
+    for _ in 0..100 {
+        gameboy.step(); // Past mode 2
+    }
+    gameboy.bus.ram_write_byte(IF, 0x00);
+
+    run_to_line(&mut gameboy, 144);
+    assert!(is_lcd_interrupt_requested(&gameboy));
+}
+
+#[test]
+fn lyc_145_fires_after_the_line_144_oam_source() {
+    let mut gameboy = setup(OAM_SOURCE | LYC_SOURCE, 145);
+    run_to_line(&mut gameboy, 144);
+    // The OAM source only lasts for the first M-cycle of line 144
+    gameboy.step();
+    gameboy.step();
+    gameboy.bus.ram_write_byte(IF, 0x00);
+
+    run_to_line(&mut gameboy, 145);
+    gameboy.step();
+    assert!(is_lcd_interrupt_requested(&gameboy));
+}
+
+#[test]
+fn line_144_oam_source_end_is_a_ppu_event() {
+    let mut ppu = PPU::new();
+    ppu.poke_byte(0xFF40, 0x91); // LCD on
+    ppu.set_vblank_line(144);
+
+    // The bus only runs the PPU on its events, the STAT line is re-evaluated 4 dots into line 144
+    assert_eq!(ppu.dots_until_next_event(), Some(4));
+    ppu.tick(4);
+    assert_eq!(ppu.dots_until_next_event(), Some(452));
+}
@@ -0,0 +1,193 @@
+//! STOP mode and CGB speed switch tests
+
+use emulator::{GameBoy, JoypadInputKey, MemoryAccess, Model, CPU};
+
+const DIV: u16 = 0xFF04;
+const KEY1: u16 = 0xFF4D;
+
+/// LD A, $20 / LDH (P1), A (select the directions) / STOP / JR -2
+const STOP_PROGRAM: [u8; 8] = [0x3E, 0x20, 0xE0, 0x00, 0x10, 0x00, 0x18, 0xFE];
+/// LD A, 1 / LDH (KEY1), A / STOP / NOP / LDH (KEY1), A / STOP / JR -2
+const SPEED_SWITCH_PROGRAM: [u8; 13] = [
+    0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00, 0x00, 0xE0, 0x4D, 0x10, 0x00, 0x18, 0xFE,
+];
+
+fn gameboy(model: Model, program: &[u8]) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+
+    let mut gameboy = GameBoy::new(model);
+    gameboy.read_rom(&rom).unwrap();
+    gameboy
+}
+
+/// Flat memory counting the T-cycles the CPU ticks
+struct CountingBus {
+    ram: Vec<u8>,
+    cycles: u32,
+    is_stopped: bool,
+}
+
+impl MemoryAccess for CountingBus {
+    fn ram_read_byte(&self, address: u16) -> u8 {
+        self.ram[address as usize]
+    }
+
+    fn ram_write_byte(&mut self, address: u16, byte: u8) {
+        self.ram[address as usize] = byte;
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as u32;
+    }
+
+    fn set_stop_mode(&mut self, is_stopped: bool) {
+        self.is_stopped = is_stopped;
+    }
+
+    fn is_stop_mode(&self) -> bool {
+        self.is_stopped
+    }
+}
+
+/// Run STOP (0x10 0x00) at $C000 with the given joypad lines and pending interrupts, returns what
+/// `step` reported, the T-cycles that were ticked and the PC after it
+fn step_stop(p1: u8, pending_interrupts: u8) -> (u8, u32, u16) {
+    let mut bus = CountingBus {
+        ram: vec![0; 0x10000],
+        cycles: 0,
+        is_stopped: false,
+    };
+    bus.ram[0xC000..0xC002].copy_from_slice(&[0x10, 0x00]);
+    bus.ram[0xFF00] = p1;
+    bus.ram[0xFF0F] = pending_interrupts;
+    bus.ram[0xFFFF] = pending_interrupts;
+    let mut cpu = CPU::new();
+    cpu.registers.pc = 0xC000;
+
+    let cycles = cpu.step(&mut bus);
+    (cycles, bus.cycles, cpu.registers.pc)
+}
+
+/// Step until PC reaches `pc`, returns the steps it took
+fn run_to(gameboy: &mut GameBoy, pc: u16) -> usize {
+    for steps in 0..10_000 {
+        if gameboy.cpu.registers.pc == pc {
+            return steps;
+        }
+        gameboy.step();
+    }
+    panic!("never reached {:#06X}", pc);
+}
+
+#[test]
+fn stop_freezes_the_system_until_a_key_is_pressed() {
+    let mut gameboy = gameboy(Model::DMG, &STOP_PROGRAM);
+    run_to(&mut gameboy, 0x0104);
+    gameboy.step();
+    assert!(gameboy.bus.is_stopped);
+    // STOP resets DIV, which then stays put
+    assert_eq!(gameboy.bus.ram_read_byte(DIV), 0x00);
+
+    for _ in 0..1000 {
+        gameboy.step();
+    }
+    assert!(gameboy.bus.is_stopped);
+    assert_eq!(gameboy.cpu.registers.pc, 0x0106);
+    assert_eq!(gameboy.bus.ram_read_byte(DIV), 0x00);
+
+    gameboy.bus.joypad.press_key(JoypadInputKey::Right);
+    gameboy.step();
+    assert!(!gameboy.bus.is_stopped);
+    for _ in 0..1000 {
+        gameboy.step();
+    }
+    assert_ne!(gameboy.bus.ram_read_byte(DIV), 0x00);
+}
+
+#[test]
+fn stop_with_a_switch_armed_changes_the_speed_instead() {
+    let mut gameboy = gameboy(Model::CGB, &SPEED_SWITCH_PROGRAM);
+    run_to(&mut gameboy, 0x0104);
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0x7F);
+
+    gameboy.step();
+    assert!(gameboy.bus.is_double_speed);
+    assert!(!gameboy.bus.is_stopped);
+    // The armed bit is cleared, and the CPU pauses while the clock settles
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0xFE);
+    let steps = run_to(&mut gameboy, 0x0107);
+    assert_eq!(steps, 2050 + 1);
+
+    // Switch back
+    run_to(&mut gameboy, 0x010B);
+    assert!(!gameboy.bus.is_double_speed);
+    assert_eq!(gameboy.bus.ram_read_byte(KEY1), 0x7E);
+}
+
+#[test]
+fn double_speed_makes_a_line_last_twice_as_many_cycles() {
+    let mut gameboy = gameboy(Model::CGB, &SPEED_SWITCH_PROGRAM[..6]);
+    run_to(&mut gameboy, 0x0106);
+
+    // The PPU keeps its speed: a line lasts 456 dots, so 912 CPU T-cycles
+    let line_duration = |gameboy: &mut GameBoy| {
+        let ly = gameboy.bus.ram_read_byte(0xFF44);
+        while gameboy.bus.ram_read_byte(0xFF44) == ly {
+            gameboy.step();
+        }
+        let ly = gameboy.bus.ram_read_byte(0xFF44);
+        let start = gameboy.bus.scheduler.now();
+        while gameboy.bus.ram_read_byte(0xFF44) == ly {
+            gameboy.step();
+        }
+        gameboy.bus.scheduler.now() - start
+    };
+    let cycles = line_duration(&mut gameboy);
+    assert!((908..=916).contains(&cycles), "{} T-cycles", cycles);
+}
+
+#[test]
+fn dmg_ignores_key1() {
+    let mut gameboy = gameboy(Model::DMG, &SPEED_SWITCH_PROGRAM);
+    run_to(&mut gameboy, 0x0104);
+    gameboy.step();
+
+    assert!(!gameboy.bus.is_double_speed);
+    assert!(gameboy.bus.is_stopped);
+}
+
+#[test]
+fn stop_reports_the_cycles_it_ticked() {
+    // The second byte is read: 2 M-cycles
+    assert_eq!(step_stop(0xFF, 0x00), (8, 8, 0xC002));
+    // A key is held: acts as HALT, the second byte is read too
+    assert_eq!(step_stop(0xEE, 0x00), (8, 8, 0xC002));
+    // An interrupt is pending: the second byte is not read
+    assert_eq!(step_stop(0xFF, 0x01), (4, 4, 0xC001));
+}
+
+#[test]
+fn the_rtc_keeps_counting_in_stop_mode() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0100 + STOP_PROGRAM.len()].copy_from_slice(&STOP_PROGRAM);
+    rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+    rom[0x0149] = 0x02; // 8 KiB
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+    run_to(&mut gameboy, 0x0106);
+    assert!(gameboy.bus.is_stopped);
+
+    // A bit more than a second
+    for _ in 0..1_100_000 {
+        gameboy.step();
+    }
+    assert!(gameboy.bus.is_stopped);
+
+    // Enable RTC access, latch and read the seconds
+    gameboy.bus.ram_write_byte(0x0000, 0x0A);
+    gameboy.bus.ram_write_byte(0x4000, 0x08);
+    gameboy.bus.ram_write_byte(0x6000, 0x00);
+    gameboy.bus.ram_write_byte(0x6000, 0x01);
+    assert_eq!(gameboy.bus.ram_read_byte(0xA000), 1);
+}
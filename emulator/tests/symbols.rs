@@ -0,0 +1,79 @@
+//! Symbol table and call stack tests
+
+use emulator::gameboy::instruction::call_stack;
+use emulator::gameboy::{BankedAddress, SymbolTable};
+use emulator::{GameBoy, Instruction, Model};
+
+#[test]
+fn describes_addresses_from_the_closest_symbol() {
+    let symbols =
+        SymbolTable::parse("[labels]\n00:0150 Main\n02:4000 Banked\n00:C000 wCounter\n").unwrap();
+
+    assert_eq!(
+        symbols.describe(BankedAddress::new(0x0150, 1)),
+        Some("Main".to_string())
+    );
+    assert_eq!(
+        symbols.describe(BankedAddress::new(0x0162, 1)),
+        Some("Main+$12".to_string())
+    );
+    assert_eq!(
+        symbols.describe(BankedAddress::new(0x4010, 2)),
+        Some("Banked+$10".to_string())
+    );
+    assert_eq!(symbols.describe(BankedAddress::new(0x4010, 3)), None);
+    // Not past the end of ROM0
+    assert_eq!(symbols.describe(BankedAddress::new(0x4010, 1)), None);
+    assert_eq!(symbols.describe(BankedAddress::new(0xFF80, 0)), None);
+}
+
+#[test]
+fn keeps_the_bank_of_switchable_areas() {
+    let symbols = SymbolTable::parse(
+        "00:0150 Main\n02:4000 Banked\n03:A000 sSave\n01:D000 wBuffer\n00:FF80 hTemp\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        symbols.address_of("Banked"),
+        Some(BankedAddress::new(0x4000, 2))
+    );
+    assert_eq!(symbols.address_of("sSave").unwrap().bank, 3);
+    // Addresses are still named, whatever the bank
+    assert_eq!(
+        symbols.describe(BankedAddress::new(0xA010, 1)),
+        Some("sSave+$10".to_string())
+    );
+    assert_eq!(symbols.address_of("wBuffer").unwrap().bank, 0);
+    assert_eq!(
+        symbols.address_of("hTemp"),
+        Some(BankedAddress::new(0xFF80, 0))
+    );
+    assert_eq!(symbols.address_of("Missing"), None);
+}
+
+#[test]
+fn call_stack_finds_the_callers() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0150..0x0153].copy_from_slice(&[0xCD, 0x00, 0x02]); // 0150: CALL $0200
+    rom[0x0200] = 0xEF; // 0200: RST $28
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+
+    // RST $28 return address, a stray word, then the CALL return address
+    for (offset, byte) in [0x01, 0x02, 0x34, 0x12, 0x53, 0x01].iter().enumerate() {
+        gameboy.bus.ram_write_byte(0xDFF0 + offset as u16, *byte);
+    }
+
+    let calls = call_stack(&gameboy.bus, 0xDFF0, 1);
+    assert_eq!(
+        calls,
+        vec![
+            (BankedAddress::new(0x0200, 1), Instruction::Rst(0x28)),
+            (
+                BankedAddress::new(0x0150, 1),
+                Instruction::Call(None, 0x0200)
+            ),
+        ]
+    );
+}
@@ -0,0 +1,160 @@
+//! Regression tests driven by test ROMs
+//!
+//! The ROMs are not checked into the repository, so these tests are ignored by default. Place them in
+//! `../roms` (or point `TEST_ROM_DIR` at them) and run `cargo test --test test_roms -- --ignored`.
+//! A missing ROM fails its test.
+//!
+//! Screenshot tests (Mealybug Tearoom tests, dmg-acid2) run until the ROM executes `LD B, B`, then compare the
+//! screen against the expected image next to the ROM (`<name>.pgm`, converted from the suite's DMG PNG with
+//! e.g. `convert <name>.png <name>.pgm`). Colors are compared as one of the 4 shades.
+
+use std::path::PathBuf;
+
+use emulator::{GameBoy, Model};
+
+const DEFAULT_TEST_ROM_DIR: &str = "../roms";
+const SCREEN_WIDTH: usize = 160;
+const SCREEN_HEIGHT: usize = 144;
+/// T-cycles a ROM may run for before it is considered stuck (20 seconds)
+const MAX_CYCLES: u64 = 20 * 4194304;
+/// LD B, B - used by the test suites as a breakpoint, to signal the end of the test
+const LD_B_B: u8 = 0x40;
+
+fn rom_path(name: &str) -> PathBuf {
+    let directory =
+        std::env::var("TEST_ROM_DIR").unwrap_or_else(|_| DEFAULT_TEST_ROM_DIR.to_string());
+    PathBuf::from(directory).join(name)
+}
+
+fn load(name: &str) -> GameBoy {
+    let path = rom_path(name);
+    let rom =
+        std::fs::read(&path).unwrap_or_else(|error| panic!("unable to read {:?}: {}", path, error));
+
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+    gameboy
+}
+
+/// Run until the CPU is about to execute LD B, B
+fn run_to_breakpoint(gameboy: &mut GameBoy, name: &str) {
+    let mut cycles: u64 = 0;
+    while gameboy.bus.ram_read_byte(gameboy.cpu.registers.pc) != LD_B_B {
+        cycles += gameboy.step() as u64;
+        if cycles > MAX_CYCLES {
+            panic!("{} did not finish", name);
+        }
+    }
+}
+
+/// Shade (0 is white, 3 is black) of every pixel of a grey scale image
+fn shades(grey: impl Iterator<Item = u8>) -> Vec<u8> {
+    grey.map(|value| ((255 - value as u16 + 42) / 85) as u8)
+        .collect()
+}
+
+/// Pixels of a binary (P5) PGM image
+fn read_pgm(path: &PathBuf) -> (usize, usize, Vec<u8>) {
+    let data =
+        std::fs::read(path).unwrap_or_else(|error| panic!("unable to read {:?}: {}", path, error));
+
+    // Magic number, width, height and maximum value, separated by whitespace and comments
+    let mut fields = Vec::new();
+    let mut index = 0;
+    while fields.len() < 4 {
+        while data[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        if data[index] == b'#' {
+            while data[index] != b'\n' {
+                index += 1;
+            }
+            continue;
+        }
+        let start = index;
+        while !data[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        fields.push(String::from_utf8_lossy(&data[start..index]).into_owned());
+    }
+    assert_eq!(fields[0], "P5", "{:?} is not a binary PGM image", path);
+    assert_eq!(fields[3], "255", "{:?} does not use 8-bit values", path);
+
+    let width: usize = fields[1].parse().unwrap();
+    let height: usize = fields[2].parse().unwrap();
+    let pixels = data[index + 1..index + 1 + width * height].to_vec();
+    (width, height, pixels)
+}
+
+fn write_pgm(path: &PathBuf, pixels: &[u8]) {
+    let mut data = format!("P5\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+    data.extend_from_slice(pixels);
+    std::fs::write(path, data).unwrap();
+}
+
+/// Run a screenshot test, the screen is saved to the temporary directory when it does not match
+fn screenshot_test(name: &str) {
+    let mut gameboy = load(&format!("{}.gb", name));
+    run_to_breakpoint(&mut gameboy, name);
+
+    let screen: Vec<u8> = gameboy
+        .bus
+        .ppu
+        .get_display()
+        .iter()
+        .step_by(4)
+        .copied()
+        .collect();
+    let (width, height, expected) = read_pgm(&rom_path(&format!("{}.pgm", name)));
+    assert_eq!(
+        (width, height),
+        (SCREEN_WIDTH, SCREEN_HEIGHT),
+        "unexpected image size"
+    );
+
+    let actual_shades = shades(screen.iter().copied());
+    let expected_shades = shades(expected.into_iter());
+    if actual_shades != expected_shades {
+        let different = actual_shades
+            .iter()
+            .zip(&expected_shades)
+            .filter(|(a, e)| a != e)
+            .count();
+        let file_name = PathBuf::from(name)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let actual_path = std::env::temp_dir().join(format!("{}.actual.pgm", file_name));
+        write_pgm(&actual_path, &screen);
+        panic!(
+            "{}: {} pixels differ, the screen was saved to {:?}",
+            name, different, actual_path
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs dmg-acid2, see the module documentation"]
+fn dmg_acid2() {
+    screenshot_test("dmg-acid2");
+}
+
+#[test]
+#[ignore = "needs the Mealybug Tearoom tests, see the module documentation"]
+fn mealybug_window() {
+    for name in [
+        "m2_win_en_toggle",
+        "m3_lcdc_win_en_change_multiple",
+        "m3_lcdc_win_en_change_multiple_wx",
+        "m3_lcdc_win_map_change",
+        "m3_window_timing",
+        "m3_window_timing_wx_0",
+        "m3_wx_4_change",
+        "m3_wx_4_change_sprites",
+        "m3_wx_5_change",
+        "m3_wx_6_change",
+    ] {
+        screenshot_test(&format!("mealybug/{}", name));
+    }
+}
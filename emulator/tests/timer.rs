@@ -0,0 +1,123 @@
+//! Timer tests: falling-edge TIMA clocking and the overflow/reload sequence
+//!
+//! TAC 0x05 (enabled, 262144 Hz) clocks TIMA on the falling edge of bit 3 of the system counter,
+//! so every 16 T-cycles.
+
+use emulator::gameboy::{Interrupt, Timer};
+
+const DIV: usize = 0x00;
+const TIMA: usize = 0x01;
+const TMA: usize = 0x02;
+const TAC: usize = 0x03;
+
+fn running_timer(system_counter: u16, tima: u8) -> Timer {
+    let mut timer = Timer::new();
+    timer.write_byte(TAC, 0x05);
+    timer.set_system_counter(system_counter);
+    timer.write_byte(TIMA, tima);
+    timer.write_byte(TMA, 0x42);
+    timer
+}
+
+#[test]
+fn tima_counts_falling_edges_of_the_selected_bit() {
+    let mut timer = running_timer(0x0000, 0x00);
+
+    timer.advance(15 * 4);
+    assert_eq!(timer.read_byte(TIMA), 3);
+    timer.advance(4);
+    assert_eq!(timer.read_byte(TIMA), 4);
+    assert_eq!(timer.read_byte(DIV), 0x00);
+    timer.advance(0x100 - 64);
+    assert_eq!(timer.read_byte(DIV), 0x01);
+    assert_eq!(timer.read_byte(TIMA), 16);
+}
+
+#[test]
+fn writing_div_increments_tima_when_the_selected_bit_was_set() {
+    let mut timer = running_timer(0x0008, 0x00);
+    timer.write_byte(DIV, 0x12);
+    assert_eq!(timer.read_byte(DIV), 0x00);
+    assert_eq!(timer.read_byte(TIMA), 1);
+
+    // Bit 3 is clear, so the reset is not a falling edge
+    let mut timer = running_timer(0x0004, 0x00);
+    timer.write_byte(DIV, 0x12);
+    assert_eq!(timer.read_byte(TIMA), 0);
+}
+
+#[test]
+fn changing_tac_can_increment_tima() {
+    // Selecting a bit that is clear while the old one was set
+    let mut timer = running_timer(0x0008, 0x00);
+    timer.write_byte(TAC, 0x04); // Bit 9
+    assert_eq!(timer.read_byte(TIMA), 1);
+
+    // Disabling the timer while the selected bit is set
+    let mut timer = running_timer(0x0008, 0x00);
+    timer.write_byte(TAC, 0x01);
+    assert_eq!(timer.read_byte(TIMA), 1);
+
+    // Selecting a bit that is set as well is not a falling edge
+    let mut timer = running_timer(0x0208, 0x00);
+    timer.write_byte(TAC, 0x04);
+    assert_eq!(timer.read_byte(TIMA), 0);
+}
+
+#[test]
+fn overflow_reloads_tma_one_m_cycle_later() {
+    let mut timer = running_timer(0x0000, 0xFF);
+
+    timer.advance(16);
+    // TIMA reads 0 during the M-cycle after the overflow
+    assert_eq!(timer.read_byte(TIMA), 0x00);
+    assert_eq!(timer.raise_interrupt, None);
+
+    timer.advance(4);
+    assert_eq!(timer.read_byte(TIMA), 0x42);
+    assert_eq!(timer.raise_interrupt, Some(Interrupt::Timer));
+}
+
+#[test]
+fn writing_tima_during_the_overflow_cycle_cancels_the_reload() {
+    let mut timer = running_timer(0x0000, 0xFF);
+    timer.advance(16);
+
+    timer.write_byte(TIMA, 0x10);
+    timer.advance(4);
+    assert_eq!(timer.read_byte(TIMA), 0x10);
+    assert_eq!(timer.raise_interrupt, None);
+}
+
+#[test]
+fn writes_during_the_reload_cycle() {
+    let mut timer = running_timer(0x0000, 0xFF);
+    timer.advance(20);
+
+    // TIMA writes are ignored, TMA writes are copied into TIMA as well
+    timer.write_byte(TIMA, 0x10);
+    assert_eq!(timer.read_byte(TIMA), 0x42);
+    timer.write_byte(TMA, 0x55);
+    assert_eq!(timer.read_byte(TIMA), 0x55);
+
+    // Until the next M-cycle
+    timer.advance(4);
+    timer.write_byte(TIMA, 0x10);
+    assert_eq!(timer.read_byte(TIMA), 0x10);
+}
+
+#[test]
+fn interrupt_is_scheduled_when_it_happens() {
+    let mut timer = running_timer(0x0000, 0xFE);
+
+    // Edges at 16 (0xFF) and 32 (overflow), the interrupt follows one M-cycle later
+    assert_eq!(timer.cycles_until_interrupt(), Some(36));
+    timer.advance(32);
+    assert_eq!(timer.raise_interrupt, None);
+    assert_eq!(timer.cycles_until_interrupt(), Some(4));
+    timer.advance(4);
+    assert_eq!(timer.raise_interrupt, Some(Interrupt::Timer));
+
+    timer.write_byte(TAC, 0x00);
+    assert_eq!(timer.cycles_until_interrupt(), None);
+}
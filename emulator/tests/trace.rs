@@ -0,0 +1,54 @@
+//! Trace line parsing and comparison tests (Gameboy Doctor format)
+
+use emulator::gameboy::trace::{diff_trace_lines, parse_trace_line};
+
+const LINE: &str = "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02";
+
+#[test]
+fn parses_every_field() {
+    let fields = parse_trace_line(LINE);
+
+    assert_eq!(fields.len(), 11);
+    assert_eq!(fields[0], ("A", "01"));
+    assert_eq!(fields[8], ("SP", "FFFE"));
+    assert_eq!(fields[10], ("PCMEM", "00,C3,13,02"));
+}
+
+#[test]
+fn ignores_extra_whitespace_and_words_without_a_value() {
+    let fields = parse_trace_line("  A:01\tF:B0   garbage  PC:0100 ");
+
+    assert_eq!(fields, vec![("A", "01"), ("F", "B0"), ("PC", "0100")]);
+    assert!(parse_trace_line("").is_empty());
+}
+
+#[test]
+fn identical_lines_have_no_differences() {
+    assert!(diff_trace_lines(LINE, LINE).is_empty());
+    // Values are compared regardless of case
+    let lower_case = "A:01 F:b0 B:00 C:13 D:00 E:d8 H:01 L:4d SP:fffe PC:0100 PCMEM:00,c3,13,02";
+    assert!(diff_trace_lines(LINE, lower_case).is_empty());
+}
+
+#[test]
+fn differences_are_listed_with_flags_broken_down() {
+    let actual = LINE.replace("F:B0", "F:80").replace("PC:0100", "PC:0101");
+    let differences = diff_trace_lines(LINE, &actual);
+
+    assert_eq!(
+        differences,
+        vec![
+            "    F: expected B0, got 80",
+            "    H: expected 1, got 0",
+            "    C: expected 1, got 0",
+            "   PC: expected 0100, got 0101",
+        ]
+    );
+}
+
+#[test]
+fn missing_fields_are_reported() {
+    let differences = diff_trace_lines("A:01 F:B0", "A:01");
+
+    assert_eq!(differences, vec!["    F: expected B0, got --"]);
+}
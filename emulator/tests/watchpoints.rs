@@ -0,0 +1,158 @@
+//! Memory watchpoints, checked by the bus on CPU accesses
+
+use emulator::gameboy::watchpoint::{WatchCondition, WatchKind};
+use emulator::gameboy::{Watchpoint, WatchpointHit};
+use emulator::{GameBoy, Model};
+
+/// Counts up in $C000 forever, reading it back after every write
+fn gameboy() -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x010B].copy_from_slice(&[
+        0xAF, // 0100: XOR A
+        0xEA, 0x00, 0xC0, // 0101: LD ($C000), A
+        0xFA, 0x00, 0xC0, // 0104: LD A, ($C000)
+        0x3C, // 0107: INC A
+        0xC3, 0x01, 0x01, // 0108: JP $0101
+    ]);
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+    gameboy
+}
+
+#[test]
+fn breaks_after_the_access() {
+    let mut gameboy = gameboy();
+    gameboy.bus.watchpoints.list.push(Watchpoint::new(
+        0xC000..=0xC000,
+        WatchKind::Read,
+        WatchCondition::Always,
+    ));
+
+    let result = gameboy.run_frame();
+    assert!(result.is_breakpoint_hit);
+    assert_eq!(
+        result.watchpoint_hit,
+        Some(WatchpointHit {
+            index: 0,
+            address: 0xC000,
+            value: 0x00,
+            is_write: false,
+        })
+    );
+    assert_eq!(gameboy.cpu.registers.pc, 0x0107);
+    assert_eq!(gameboy.bus.watchpoints.list[0].hit_count.get(), 1);
+}
+
+#[test]
+fn matches_values() {
+    let mut gameboy = gameboy();
+    gameboy.bus.watchpoints.list.push(Watchpoint::new(
+        0xC000..=0xC0FF,
+        WatchKind::Write,
+        WatchCondition::Equals(3),
+    ));
+
+    let result = gameboy.run_frame();
+    assert_eq!(
+        result.watchpoint_hit.map(|hit| (hit.value, hit.is_write)),
+        Some((3, true))
+    );
+    assert_eq!(gameboy.bus.peek_byte(0xC000), 3);
+}
+
+#[test]
+fn changes_ignore_writes_of_the_same_value() {
+    let mut gameboy = gameboy();
+    gameboy.bus.ram_write_byte(0xC000, 0x00);
+    gameboy.bus.watchpoints.list.push(Watchpoint::new(
+        0xC000..=0xC000,
+        WatchKind::Access,
+        WatchCondition::Changes,
+    ));
+
+    // The first write stores the 0 that is already there
+    let result = gameboy.run_frame();
+    assert_eq!(result.watchpoint_hit.map(|hit| hit.value), Some(1));
+}
+
+#[test]
+fn debugger_reads_are_not_watched() {
+    let mut gameboy = gameboy();
+    gameboy
+        .bus
+        .watchpoints
+        .list
+        .push(Watchpoint::new(0xC000..=0xC000, WatchKind::Read, WatchCondition::Always).in_bank(0));
+
+    gameboy.bus.peek_byte(0xC000);
+    assert_eq!(gameboy.bus.watchpoints.take_hit(), None);
+    assert_eq!(gameboy.bus.watchpoints.list[0].hit_count.get(), 0);
+}
+
+#[test]
+fn interrupt_polling_and_dispatch_are_not_watched() {
+    let mut gameboy = gameboy();
+    for address in [0xFF0F, 0xFFFF] {
+        gameboy.bus.watchpoints.list.push(Watchpoint::new(
+            address..=address,
+            WatchKind::Access,
+            WatchCondition::Always,
+        ));
+    }
+    // A pending timer interrupt, polled before every instruction and dispatched to 0x0050 (NOPs up to 0x0100)
+    gameboy.bus.write_byte_unwatched(0xFFFF, 0x04);
+    gameboy.bus.write_byte_unwatched(0xFF0F, 0x04);
+    gameboy.cpu.interrupts_enabled = true;
+
+    let result = gameboy.run_frame();
+    assert_eq!(result.watchpoint_hit, None);
+    assert!(gameboy
+        .bus
+        .watchpoints
+        .list
+        .iter()
+        .all(|watchpoint| watchpoint.hit_count.get() == 0));
+    // The interrupt was dispatched
+    assert_eq!(gameboy.bus.peek_byte(0xFF0F) & 0x04, 0);
+}
+
+#[test]
+fn program_accesses_to_interrupt_registers_are_watched() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0104].copy_from_slice(&[
+        0x00, // 0100: NOP
+        0xF0, 0x0F, // 0101: LDH A, ($FF0F)
+        0x00, // 0103: NOP
+    ]);
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+    gameboy.bus.watchpoints.list.push(Watchpoint::new(
+        0xFF0F..=0xFF0F,
+        WatchKind::Read,
+        WatchCondition::Always,
+    ));
+
+    gameboy.step();
+    assert_eq!(gameboy.bus.watchpoints.take_hit(), None);
+    gameboy.step();
+    assert!(gameboy.bus.watchpoints.take_hit().is_some());
+    assert_eq!(gameboy.bus.watchpoints.list[0].hit_count.get(), 1);
+}
+
+#[test]
+fn tracing_is_not_watched() {
+    let mut gameboy = gameboy();
+    // The trace line shows the 4 bytes at PC, past the end of the 1 byte XOR A
+    gameboy.bus.watchpoints.list.push(Watchpoint::new(
+        0x0101..=0x0103,
+        WatchKind::Read,
+        WatchCondition::Always,
+    ));
+    gameboy
+        .cpu
+        .set_trace_sink(Some(Box::new(Vec::<String>::new())));
+
+    gameboy.step();
+    assert_eq!(gameboy.bus.watchpoints.take_hit(), None);
+    assert_eq!(gameboy.bus.watchpoints.list[0].hit_count.get(), 0);
+}
@@ -0,0 +1,150 @@
+//! Window rendering regression tests
+//!
+//! Every test sets up a window made of a single tile, whose first row is solid black and whose other
+//! rows only have their leftmost pixel black, over a white background. Which screen pixels end up black
+//! then shows both where the window starts on a line and which window line was drawn.
+
+use emulator::{GameBoy, Model};
+
+const LCDC: u16 = 0xFF40;
+const SCX: u16 = 0xFF43;
+const LY: u16 = 0xFF44;
+const WY: u16 = 0xFF4A;
+const WX: u16 = 0xFF4B;
+
+/// LCD on, window map at 0x9C00, window on, tile data at 0x8000, BG on
+const LCDC_WINDOW_ON: u8 = 0xF1;
+/// Same as above, with the window turned off
+const LCDC_WINDOW_OFF: u8 = 0xD1;
+
+/// Window lines 0, 8, 16, ... are solid black
+const FULL_ROW: usize = 160;
+/// One black pixel per tile
+const STRIPED_ROW: usize = 20;
+
+fn setup(wx: u8, wy: u8, scx: u8) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100] = 0x18; // JR -2
+    rom[0x0101] = 0xFE;
+
+    let mut gameboy = GameBoy::new(Model::DMG);
+    gameboy.read_rom(&rom).unwrap();
+    let bus = &mut gameboy.bus;
+
+    bus.ram_write_byte(LCDC, 0x00);
+    bus.ram_write_byte(0xFFFF, 0x00); // IE
+    for address in 0x9800..0xA000 {
+        let tile_id = if address < 0x9C00 { 0 } else { 1 };
+        bus.ram_write_byte(address, tile_id);
+    }
+    for address in 0x8000..0x8010 {
+        bus.ram_write_byte(address, 0x00);
+    }
+    for row in 0..8 {
+        let bits = if row == 0 { 0xFF } else { 0x80 };
+        bus.ram_write_byte(0x8010 + row * 2, bits);
+        bus.ram_write_byte(0x8011 + row * 2, bits);
+    }
+    bus.ram_write_byte(0xFF47, 0xE4); // BGP
+    bus.ram_write_byte(SCX, scx);
+    bus.ram_write_byte(WY, wy);
+    bus.ram_write_byte(WX, wx);
+    bus.ram_write_byte(LCDC, LCDC_WINDOW_ON);
+
+    // The first frame after turning the LCD on is not displayed
+    run_frame(&mut gameboy);
+    gameboy
+}
+
+fn run_to_line(gameboy: &mut GameBoy, ly: u8) {
+    while gameboy.bus.ram_read_byte(LY) != ly {
+        gameboy.step();
+    }
+}
+
+/// Run until the start of the next V-Blank
+fn run_frame(gameboy: &mut GameBoy) {
+    while gameboy.bus.ram_read_byte(LY) == 144 {
+        gameboy.step();
+    }
+    run_to_line(gameboy, 144);
+}
+
+fn black_pixels(gameboy: &GameBoy, line: usize) -> Vec<usize> {
+    let display = gameboy.bus.ppu.get_display();
+    (0..160)
+        .filter(|x| display[(line * 160 + x) * 4] == 0)
+        .collect()
+}
+
+#[test]
+fn window_starts_on_the_wy_line() {
+    let mut gameboy = setup(7, 50, 0);
+    run_frame(&mut gameboy);
+
+    assert!(black_pixels(&gameboy, 49).is_empty());
+    assert_eq!(black_pixels(&gameboy, 50).len(), FULL_ROW);
+    assert_eq!(black_pixels(&gameboy, 51).len(), STRIPED_ROW);
+}
+
+#[test]
+fn wy_is_latched_for_the_rest_of_the_frame() {
+    let mut gameboy = setup(7, 50, 0);
+
+    // Moving WY below LY does not hide the window again
+    run_to_line(&mut gameboy, 60);
+    gameboy.bus.ram_write_byte(WY, 100);
+    run_frame(&mut gameboy);
+    assert_eq!(black_pixels(&gameboy, 74).len(), FULL_ROW);
+
+    // WY only triggers when LY passes it, setting it to an earlier line does nothing
+    gameboy.bus.ram_write_byte(WY, 200);
+    run_to_line(&mut gameboy, 60);
+    gameboy.bus.ram_write_byte(WY, 20);
+    run_frame(&mut gameboy);
+    assert!(black_pixels(&gameboy, 80).is_empty());
+}
+
+#[test]
+fn wx_below_7_starts_off_screen() {
+    let mut gameboy = setup(3, 0, 0);
+    run_frame(&mut gameboy);
+
+    let expected: Vec<usize> = (0..160).filter(|x| (x + 4) % 8 == 0).collect();
+    assert_eq!(black_pixels(&gameboy, 1), expected);
+}
+
+#[test]
+fn wx_0_is_shifted_by_fine_scx() {
+    let mut gameboy = setup(0, 0, 3);
+    run_frame(&mut gameboy);
+
+    let expected: Vec<usize> = (0..160).filter(|x| (x + 10) % 8 == 0).collect();
+    assert_eq!(black_pixels(&gameboy, 1), expected);
+}
+
+#[test]
+fn wx_166_covers_the_next_line() {
+    let mut gameboy = setup(166, 0, 0);
+    run_frame(&mut gameboy);
+
+    assert_eq!(black_pixels(&gameboy, 0), vec![159]);
+    let expected: Vec<usize> = (0..160).filter(|x| x % 8 == 0).collect();
+    assert_eq!(black_pixels(&gameboy, 1), expected);
+}
+
+#[test]
+fn window_resumes_after_being_disabled_mid_frame() {
+    let mut gameboy = setup(7, 0, 0);
+
+    run_to_line(&mut gameboy, 8);
+    gameboy.bus.ram_write_byte(LCDC, LCDC_WINDOW_OFF);
+    run_to_line(&mut gameboy, 14);
+    gameboy.bus.ram_write_byte(LCDC, LCDC_WINDOW_ON);
+    run_frame(&mut gameboy);
+
+    assert!(black_pixels(&gameboy, 10).is_empty());
+    // Window line 8 (not 14) is drawn on line 14
+    assert_eq!(black_pixels(&gameboy, 14).len(), FULL_ROW);
+    assert_eq!(black_pixels(&gameboy, 15).len(), STRIPED_ROW);
+}
@@ -9,12 +9,16 @@ use serde::{Deserialize, Serialize};
 use winit::event::VirtualKeyCode;
 
 use crate::gameboy::joypad::JoypadInputKey;
+use crate::gameboy::Model;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct GameBoyConfig {
     pub selected_rom: Option<PathBuf>,
     pub input_mapper: HashMap<JoypadInputKey, [Option<VirtualKeyCode>; 2]>,
     pub color_palette: [PixelColor; 4],
+    /// Hardware revision used for the next ROM loaded
+    #[serde(default)]
+    pub model: Model,
 }
 
 impl Default for GameBoyConfig {
@@ -37,6 +41,7 @@ impl Default for GameBoyConfig {
                 pc_from_gray_value(85),
                 pc_from_gray_value(0),
             ],
+            model: Model::default(),
         }
     }
 }
@@ -2,6 +2,7 @@ use crate::{snapshot::GameBoyGameSave, GameBoyState};
 use egui::{ClippedPrimitive, Context, TexturesDelta};
 use egui_wgpu::renderer::{Renderer, ScreenDescriptor};
 use emulator::gameboy::joypad::JoypadInputKey;
+use emulator::Model;
 use pixels::{wgpu, PixelsContext};
 use rfd::FileDialog;
 use std::fmt;
@@ -245,6 +246,20 @@ impl Gui {
                     }
                 });
 
+                // Model (applies to the next ROM loaded)
+                ui.menu_button("Model", |ui| {
+                    for model in Model::ALL {
+                        let label = match model == gameboy_state.config.model {
+                            true => format!("[{}]", model),
+                            false => format!("{}", model),
+                        };
+
+                        if ui.button(label).clicked() {
+                            gameboy_state.config.model = model;
+                        }
+                    }
+                });
+
                 // Debug
                 ui.menu_button("Debug", |ui| {
                     if ui.button("Show debug window").clicked() {
@@ -9,6 +9,7 @@ use std::time::{Duration, Instant};
 
 use crate::gui::Framework;
 use config::GameBoyConfig;
+use emulator::gameboy::sgb::{SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH};
 use emulator::*;
 use error_iter::ErrorIter as _;
 use log::error;
@@ -29,7 +30,8 @@ const SCREEN_HEIGHT: usize = 144;
 
 const WINDOW_WIDTH: u32 = (SCREEN_WIDTH as u32) * SCALE;
 const WINDOW_HEIGHT: u32 = (SCREEN_HEIGHT as u32) * SCALE;
-const CYCLES_PER_FRAME: f64 = (4194304 / 60) as f64;
+/// Frames the emulation may fall behind the wall clock before giving up on catching up
+const MAX_FRAMES_BEHIND: u32 = 4;
 
 /// Representation of the application state. In this example, a box will bounce around the screen.
 struct GameBoyState {
@@ -73,8 +75,8 @@ fn main() -> Result<(), Error> {
     };
 
     let mut gameboy_state = GameBoyState::new();
-    let frame_duration = Duration::new(0, (1000.0 / 60.0) as u32);
-    let mut last_frame = Instant::now();
+    let mut buffer_size = (SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+    let mut next_frame = Instant::now();
 
     event_loop.run(move |event, _, control_flow| {
         // Handle input events
@@ -119,7 +121,7 @@ fn main() -> Result<(), Error> {
             window.request_redraw();
         }
 
-        *control_flow = ControlFlow::WaitUntil(last_frame + frame_duration); // Ideally "wakes up" when the next frame should be processed & displayed
+        *control_flow = ControlFlow::WaitUntil(next_frame); // Ideally "wakes up" when the next frame should be processed & displayed
 
         match event {
             Event::WindowEvent { event, .. } => {
@@ -151,6 +153,18 @@ fn main() -> Result<(), Error> {
             }
             // Draw the current frame
             Event::RedrawRequested(_) => {
+                // SGB games are shown with their border, which needs a bigger buffer
+                let display_size = gameboy_state.display_size();
+                if display_size != buffer_size {
+                    if let Err(err) = pixels.resize_buffer(display_size.0, display_size.1) {
+                        log_error("pixels.resize_buffer", err);
+                        gameboy_state.auto_save();
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    buffer_size = display_size;
+                }
+
                 // Draw the world
                 gameboy_state.draw(pixels.frame_mut());
 
@@ -180,7 +194,15 @@ fn main() -> Result<(), Error> {
                 requested_resume,
             }) => {
                 gameboy_state.update();
-                last_frame = Instant::now();
+
+                // Schedule from the previous deadline rather than from now, so that wake-up latency does not add up
+                let frame_duration = gameboy_state.frame_duration();
+                next_frame += frame_duration;
+                // Too far behind (e.g. the window was being dragged): resync instead of running frames back to back
+                let now = Instant::now();
+                if now > next_frame + frame_duration * MAX_FRAMES_BEHIND {
+                    next_frame = now;
+                }
             }
             _ => {
                 window.request_redraw();
@@ -199,25 +221,31 @@ fn log_error<E: std::error::Error + 'static>(method_name: &str, err: E) {
 impl GameBoyState {
     /// Create a new `World` instance that can draw a moving box.
     fn new() -> Self {
+        let config = GameBoyConfig::load();
         let mut gbs = Self {
-            gameboy: Box::new(GameBoy::new()),
-            config: GameBoyConfig::load(),
+            gameboy: Box::new(GameBoy::new(config.model)),
+            config,
             is_menu_visible: true,
             speed_modifier: 1.0,
         };
 
-        gbs.gameboy
-            .read_boot_rom(&GameBoyState::read_rom_into_buffer("DMG_ROM.bin"));
-        gbs.gameboy.read_rom(&GameBoyState::read_rom_into_buffer(
+        // Prefer a real boot ROM when one is supplied
+        match std::fs::read("../roms/DMG_ROM.bin") {
+            Ok(boot_rom) => gbs.gameboy.read_boot_rom(&boot_rom),
+            Err(_) => gbs.gameboy.use_builtin_boot_rom(),
+        }
+        if let Err(error) = gbs.gameboy.read_rom(&GameBoyState::read_rom_into_buffer(
             "emulator-only/mbc1/ram_256kb.gb",
-        ));
+        )) {
+            println!("{}", error);
+        }
 
         //GameBoySnapshot::load(&mut gbs.gameboy);
         gbs
     }
 
     fn reset(&mut self) {
-        self.gameboy = Box::new(GameBoy::new());
+        self.gameboy = Box::new(GameBoy::new(self.config.model));
     }
 
     fn read_rom_into_buffer(rom_name: &str) -> Vec<u8> {
@@ -239,16 +267,22 @@ impl GameBoyState {
     }
 
     fn load_rom(&mut self, path_buf: &PathBuf) {
-        self.gameboy
-            .read_rom(&GameBoyState::read_rom_from_file_path(&path_buf));
+        if let Err(error) = self
+            .gameboy
+            .read_rom(&GameBoyState::read_rom_from_file_path(&path_buf))
+        {
+            println!("{}", error);
+        }
     }
 
-    /// Update the Gameboy internal state; process a frame worth of cycles
+    /// Update the Gameboy internal state; run until the next frame is ready
     fn update(&mut self) {
-        let mut cycles: f64 = 0.0;
-        while cycles < CYCLES_PER_FRAME * self.speed_modifier {
-            cycles += self.gameboy.step() as f64;
-        }
+        self.gameboy.run_frame();
+    }
+
+    /// Wall clock time between two frames, the speed modifier speeds up or slows down the frame rate
+    fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / (FRAME_RATE * self.speed_modifier))
     }
 
     /// Read the current state of each input/joypad key and update the gameboy's state accordingly
@@ -266,11 +300,25 @@ impl GameBoyState {
         }
     }
 
+    /// Size of the frame buffer: the SGB border surrounds the screen when the SGB functions are active
+    fn display_size(&self) -> (u32, u32) {
+        match self.gameboy.bus.sgb {
+            Some(_) => (SGB_SCREEN_WIDTH as u32, SGB_SCREEN_HEIGHT as u32),
+            None => (SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32),
+        }
+    }
+
     /// Draw the Gameboy state to the frame buffer.
     ///
     /// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
     /// Attempt to pad pixels at the top of the screen due to the menu bar
     fn draw(&self, frame: &mut [u8]) {
+        // The SGB colors the screen itself, the custom palette does not apply
+        if let Some(sgb) = &self.gameboy.bus.sgb {
+            frame.copy_from_slice(sgb.get_display());
+            return;
+        }
+
         let display = self.gameboy.bus.ppu.get_display();
         let mut display_vec = display.to_vec();
 